@@ -1,23 +1,32 @@
 use std::hash::{DefaultHasher, Hasher};
 
 use calc_rs::{
-    constants::{BASE_FEE_BPS, MAX_TOTAL_AFFILIATE_BPS, MIN_FEE_BPS},
     core::{Contract, ContractError, ContractResult},
     manager::{
-        Affiliate, ManagerConfig, ManagerExecuteMsg, ManagerQueryMsg, Strategy, StrategyStatus,
+        AddressBook, Affiliate, ManagerConfig, ManagerExecuteMsg, ManagerQueryMsg,
+        PendingAddressBook, PendingChange, PendingUpdate, Portfolio, PortfolioStrategy, Strategy,
+        StrategyStatus, Timelock,
+    },
+    oracle::value_in,
+    strategy::{
+        Node, StrategyConfig, StrategyExecuteMsg, StrategyInstantiateMsg, StrategyQueryMsg,
     },
-    strategy::{StrategyExecuteMsg, StrategyInstantiateMsg},
 };
 use cosmwasm_schema::cw_serde;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    instantiate2_address, to_json_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order,
-    Response, StdError, StdResult, WasmMsg,
+    from_json, instantiate2_address, to_json_binary, Addr, Binary, Coin, Coins, Deps, DepsMut,
+    Empty, Env, Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgResult, Uint128, WasmMsg,
 };
 use cw_storage_plus::Bound;
 
-use crate::state::{updated_at_cursor, CONFIG, STRATEGIES, STRATEGY_COUNTER};
+use crate::state::{
+    index_assets, label_cursor, updated_at_cursor, ADDRESS_BOOKS, CONFIG, DENOM_STRATEGIES,
+    PAIR_STRATEGIES, PAUSED, PENDING_ADDRESS_BOOKS, PENDING_UPDATES, STRATEGIES, STRATEGY_COUNTER,
+    TIMELOCKS,
+};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -26,23 +35,53 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: ManagerConfig,
 ) -> ContractResult {
+    validate_config(deps.as_ref(), &msg)?;
+
+    CONFIG.save(deps.storage, &msg)?;
+    STRATEGY_COUNTER.save(deps.storage, &0)?;
+
+    Ok(Response::new())
+}
+
+fn validate_config(deps: Deps, config: &ManagerConfig) -> Result<(), ContractError> {
+    if let Some(admin) = &config.admin {
+        deps.api
+            .addr_validate(admin.as_str())
+            .map_err(|_| ContractError::generic_err("Invalid admin address"))?;
+    }
+
     deps.api
-        .addr_validate(msg.fee_collector.as_str())
+        .addr_validate(config.fee_collector.as_str())
         .map_err(|_| ContractError::generic_err("Invalid fee collector address"))?;
 
+    config.fee_schedule.validate()?;
+
     deps.querier
-        .query_wasm_code_info(msg.strategy_code_id)
+        .query_wasm_code_info(config.strategy_code_id)
         .map_err(|_| {
             ContractError::generic_err(format!(
                 "Invalid strategy code ID: {}",
-                msg.strategy_code_id
+                config.strategy_code_id
             ))
         })?;
 
-    CONFIG.save(deps.storage, &msg)?;
-    STRATEGY_COUNTER.save(deps.storage, &0)?;
+    Ok(())
+}
 
-    Ok(Response::new())
+fn assert_not_paused(deps: Deps) -> Result<(), ContractError> {
+    if PAUSED.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::generic_err("Manager is paused"));
+    }
+
+    Ok(())
+}
+
+fn assert_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if CONFIG.load(deps.storage)?.admin.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
 }
 
 #[cw_serde]
@@ -71,28 +110,18 @@ pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> ContractResult {
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn sudo(deps: DepsMut, _env: Env, msg: ManagerConfig) -> ContractResult {
-    deps.api
-        .addr_validate(msg.fee_collector.as_str())
-        .map_err(|_| ContractError::generic_err("Invalid fee collector address"))?;
-
-    deps.querier
-        .query_wasm_code_info(msg.strategy_code_id)
-        .map_err(|_| {
-            ContractError::generic_err(format!(
-                "Invalid strategy code ID: {}",
-                msg.strategy_code_id
-            ))
-        })?;
+    validate_config(deps.as_ref(), &msg)?;
 
     CONFIG.save(deps.storage, &msg)?;
     Ok(Response::new())
 }
 
 const MAX_LABEL_LENGTH: usize = 100;
+const MAX_PORTFOLIO_LIMIT: u16 = 50;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ManagerExecuteMsg,
@@ -104,7 +133,14 @@ pub fn execute(
             label,
             affiliates,
             nodes,
+            checkpoint_interval,
+            restrict_outflows,
+            performance_fee,
+            migratable,
+            valuation,
         } => {
+            assert_not_paused(deps.as_ref())?;
+
             let owner = owner.unwrap_or(info.sender);
 
             if deps.api.addr_validate(owner.as_str()).is_err() {
@@ -119,6 +155,9 @@ pub fn execute(
                 )));
             }
 
+            let config = CONFIG.load(deps.storage)?;
+            let max_total_affiliate_bps = config.fee_schedule.max_total_affiliate_bps;
+
             let total_affiliate_bps = affiliates.iter().try_fold(0, |acc, affiliate| {
                 if affiliate.label.is_empty() || affiliate.label.len() > MAX_LABEL_LENGTH {
                     return Err(ContractError::generic_err(format!(
@@ -138,23 +177,28 @@ pub fn execute(
 
                 let total = acc + affiliate.bps;
 
-                if total > MAX_TOTAL_AFFILIATE_BPS {
+                if total > max_total_affiliate_bps {
                     return Err(ContractError::generic_err(format!(
-                        "Total affiliate bps cannot exceed {MAX_TOTAL_AFFILIATE_BPS}, got at least {total}",
+                        "Total affiliate bps cannot exceed {max_total_affiliate_bps}, got at least {total}",
                     )));
                 }
 
                 Ok(total)
             })?;
 
-            let config = CONFIG.load(deps.storage)?;
+            if let Some(performance_fee) = &performance_fee {
+                performance_fee.validate(deps.as_ref())?;
+            }
 
             let affiliates = [
                 vec![Affiliate {
                     address: config.fee_collector,
-                    bps: BASE_FEE_BPS
-                        .saturating_sub(total_affiliate_bps)
-                        .max(MIN_FEE_BPS),
+                    bps: config.fee_schedule.protocol_fee_bps(
+                        deps.as_ref(),
+                        source.as_ref(),
+                        &owner,
+                        total_affiliate_bps,
+                    )?,
                     label: "CALC".to_string(),
                 }],
                 affiliates,
@@ -195,6 +239,7 @@ pub fn execute(
                     source,
                     owner: owner.clone(),
                     contract_address: contract_address.clone(),
+                    code_id: config.strategy_code_id,
                     created_at: env.block.time.seconds(),
                     updated_at: env.block.time.seconds(),
                     label: label.clone(),
@@ -202,8 +247,18 @@ pub fn execute(
                 },
             )?;
 
+            index_assets(deps.branch(), &contract_address, &nodes)?;
+
+            // Migratable strategies are administered by the manager so they can be
+            // upgraded in bulk, otherwise the owner is the admin
+            let admin = if migratable {
+                env.contract.address.clone()
+            } else {
+                owner.clone()
+            };
+
             let init_message = WasmMsg::Instantiate2 {
-                admin: Some(owner.to_string()),
+                admin: Some(admin.to_string()),
                 code_id: config.strategy_code_id,
                 label,
                 salt: salt.into(),
@@ -212,6 +267,11 @@ pub fn execute(
                     owner: owner.clone(),
                     affiliates,
                     nodes,
+                    checkpoint_interval,
+                    restrict_outflows,
+                    performance_fee,
+                    thorchain_affiliate: config.fee_schedule.thorchain_affiliate,
+                    valuation,
                 })?,
                 funds: info.funds,
             };
@@ -225,6 +285,9 @@ pub fn execute(
                 .add_message(init_message))
         }
         ManagerExecuteMsg::Execute { contract_address } => {
+            // The scheduler checks the pause before dispatching, so its triggers are kept
+            assert_not_paused(deps.as_ref())?;
+
             let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
 
             if strategy.status != StrategyStatus::Active {
@@ -255,70 +318,88 @@ pub fn execute(
             contract_address,
             nodes,
         } => {
+            assert_not_paused(deps.as_ref())?;
+
             let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
 
             if strategy.owner != info.sender {
                 return Err(ContractError::Unauthorized {});
             }
 
-            STRATEGIES.save(
-                deps.storage,
-                contract_address.clone(),
-                &Strategy {
-                    updated_at: env.block.time.seconds(),
-                    ..strategy
-                },
-            )?;
+            if let Some(timelock) = TIMELOCKS.may_load(deps.storage, contract_address.clone())? {
+                let config = deps.querier.query_wasm_smart::<StrategyConfig>(
+                    &contract_address,
+                    &StrategyQueryMsg::Config {},
+                )?;
+
+                let current_recipients = config
+                    .nodes
+                    .iter()
+                    .flat_map(|node| node.recipients())
+                    .collect::<Vec<_>>();
+
+                let changes_outflows = nodes
+                    .iter()
+                    .flat_map(|node| node.recipients())
+                    .any(|recipient| !current_recipients.contains(&recipient));
+
+                if changes_outflows {
+                    if !info.funds.is_empty() {
+                        return Err(ContractError::generic_err(
+                            "Cannot send funds with an update that changes outflow destinations",
+                        ));
+                    }
 
-            let update_msg = Contract(contract_address.clone()).call(
-                to_json_binary(&StrategyExecuteMsg::Update(nodes))?,
-                info.funds,
-            );
+                    return queue_update(
+                        deps,
+                        &env,
+                        contract_address,
+                        PendingChange::Nodes(nodes),
+                        timelock.duration,
+                    );
+                }
+            }
 
-            Ok(Response::new()
-                .add_event(
-                    Event::new(format!("{}/strategy.update", env!("CARGO_PKG_NAME")))
-                        .add_attribute("strategy_address", contract_address.as_str()),
-                )
-                .add_message(update_msg))
+            update_strategy(deps, &env, strategy, nodes, info.funds)
         }
         ManagerExecuteMsg::UpdateStatus {
             contract_address,
             status,
         } => {
+            // Owners can always pause their strategies, even while the manager is paused
+            if status != StrategyStatus::Paused {
+                assert_not_paused(deps.as_ref())?;
+            }
+
             let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
 
             if strategy.owner != info.sender {
                 return Err(ContractError::Unauthorized {});
             }
 
-            STRATEGIES.save(
-                deps.storage,
-                contract_address.clone(),
-                &Strategy {
-                    status: status.clone(),
-                    updated_at: env.block.time.seconds(),
-                    ..strategy
-                },
-            )?;
-
-            let strategy_msg = Contract(contract_address.clone()).call(
-                to_json_binary(&match status {
-                    StrategyStatus::Active => StrategyExecuteMsg::Execute {},
-                    StrategyStatus::Paused | StrategyStatus::Archived => {
-                        StrategyExecuteMsg::Cancel {}
+            // Resuming or archiving waits out the timelock like any other change,
+            // but pausing must stay instant
+            if status != StrategyStatus::Paused {
+                if let Some(timelock) =
+                    TIMELOCKS.may_load(deps.storage, contract_address.clone())?
+                {
+                    if !info.funds.is_empty() {
+                        return Err(ContractError::generic_err(
+                            "Cannot send funds with a timelocked status change",
+                        ));
                     }
-                })?,
-                info.funds,
-            );
 
-            Ok(Response::new()
-                .add_event(
-                    Event::new(format!("{}/strategy.update-status", env!("CARGO_PKG_NAME")))
-                        .add_attribute("status", status.as_str())
-                        .add_attribute("strategy_address", contract_address.as_str()),
-                )
-                .add_message(strategy_msg))
+                    return queue_update(
+                        deps,
+                        &env,
+                        contract_address,
+                        PendingChange::Status(status),
+                        timelock.duration,
+                    );
+                }
+            }
+
+            update_status(deps, &env, strategy, status, info.funds)
         }
         ManagerExecuteMsg::UpdateLabel {
             contract_address,
@@ -351,88 +432,1004 @@ pub fn execute(
                     .add_attribute("strategy_address", contract_address.as_str()),
             ))
         }
-    }
-}
+        ManagerExecuteMsg::SetTimelock {
+            contract_address,
+            timelock,
+        } => {
+            let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: ManagerQueryMsg) -> StdResult<Binary> {
-    match msg {
-        ManagerQueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
-        ManagerQueryMsg::Strategy { address } => {
-            to_json_binary(&STRATEGIES.load(deps.storage, address)?)
+            if strategy.owner != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            if let Some(timelock) = &timelock {
+                if timelock.duration == 0 {
+                    return Err(ContractError::generic_err(
+                        "Timelock duration must be greater than zero",
+                    ));
+                }
+
+                if let Some(guardian) = &timelock.guardian {
+                    deps.api.addr_validate(guardian.as_str()).map_err(|_| {
+                        ContractError::generic_err(format!("Invalid guardian address: {guardian}"))
+                    })?;
+                }
+            }
+
+            // Loosening an existing timelock must wait out the existing timelock
+            if let Some(existing) = TIMELOCKS.may_load(deps.storage, contract_address.clone())? {
+                return queue_update(
+                    deps,
+                    &env,
+                    contract_address,
+                    PendingChange::Timelock(timelock),
+                    existing.duration,
+                );
+            }
+
+            set_timelock(deps, contract_address, timelock)
         }
-        ManagerQueryMsg::Strategies {
-            owner,
-            status,
-            start_after,
-            limit,
-        } => {
-            let partition = match owner {
-                Some(owner) => match status {
-                    Some(status) => STRATEGIES
-                        .idx
-                        .owner_status_updated_at
-                        .prefix((owner, status as u8)),
-                    None => STRATEGIES.idx.owner_updated_at.prefix(owner),
-                },
-                None => match status {
-                    Some(status) => STRATEGIES.idx.status_updated_at.prefix(status as u8),
-                    None => STRATEGIES.idx.updated_at.prefix(()),
-                },
+        ManagerExecuteMsg::ApplyPendingUpdate { contract_address } => {
+            let pending_update = PENDING_UPDATES
+                .may_load(deps.storage, contract_address.clone())?
+                .ok_or_else(|| {
+                    ContractError::generic_err(format!(
+                        "No pending update for strategy {contract_address}"
+                    ))
+                })?;
+
+            if env.block.time.seconds() < pending_update.eta {
+                return Err(ContractError::generic_err(format!(
+                    "Pending update cannot be applied before {}",
+                    pending_update.eta
+                )));
+            }
+
+            PENDING_UPDATES.remove(deps.storage, contract_address.clone());
+
+            match pending_update.change {
+                PendingChange::Nodes(nodes) => {
+                    assert_not_paused(deps.as_ref())?;
+
+                    let strategy = STRATEGIES.load(deps.storage, contract_address)?;
+                    update_strategy(deps, &env, strategy, nodes, vec![])
+                }
+                PendingChange::Status(status) => {
+                    assert_not_paused(deps.as_ref())?;
+
+                    let strategy = STRATEGIES.load(deps.storage, contract_address)?;
+                    update_status(deps, &env, strategy, status, vec![])
+                }
+                PendingChange::Timelock(timelock) => set_timelock(deps, contract_address, timelock),
+                PendingChange::CodeId(code_id) => {
+                    assert_manager_admin(deps.as_ref(), &env, &contract_address)?;
+
+                    set_strategy_code_id(deps.storage, &contract_address, code_id)?;
+
+                    Ok(Response::new()
+                        .add_event(
+                            Event::new(format!("{}/strategy.migrate", env!("CARGO_PKG_NAME")))
+                                .add_attribute("code_id", code_id.to_string())
+                                .add_attribute("strategy_address", contract_address.as_str()),
+                        )
+                        .add_submessage(migrate_strategy_msg(&contract_address, code_id)?))
+                }
+                PendingChange::ReleaseAdmin => {
+                    let strategy = STRATEGIES.load(deps.storage, contract_address)?;
+                    release_admin(deps.as_ref(), &env, strategy)
+                }
+            }
+        }
+        ManagerExecuteMsg::CancelPendingUpdate { contract_address } => {
+            let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
+
+            let guardian = TIMELOCKS
+                .may_load(deps.storage, contract_address.clone())?
+                .and_then(|timelock| timelock.guardian);
+
+            if strategy.owner != info.sender && guardian != Some(info.sender.clone()) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            if !PENDING_UPDATES.has(deps.storage, contract_address.clone()) {
+                return Err(ContractError::generic_err(format!(
+                    "No pending update for strategy {contract_address}"
+                )));
+            }
+
+            PENDING_UPDATES.remove(deps.storage, contract_address.clone());
+
+            Ok(Response::new().add_event(
+                Event::new(format!(
+                    "{}/strategy.cancel-pending-update",
+                    env!("CARGO_PKG_NAME")
+                ))
+                .add_attribute("canceller", info.sender)
+                .add_attribute("strategy_address", contract_address.as_str()),
+            ))
+        }
+        ManagerExecuteMsg::UpdateAddressBook { addresses, memos } => {
+            for address in addresses.iter() {
+                deps.api.addr_validate(address.as_str()).map_err(|_| {
+                    ContractError::generic_err(format!("Invalid address book address: {address}"))
+                })?;
+            }
+
+            if memos.iter().any(|memo| memo.is_empty()) {
+                return Err(ContractError::generic_err(
+                    "Address book memos cannot be empty",
+                ));
+            }
+
+            let current = ADDRESS_BOOKS
+                .may_load(deps.storage, info.sender.clone())?
+                .unwrap_or_default();
+
+            let additions = AddressBook {
+                addresses: addresses
+                    .iter()
+                    .filter(|address| !current.addresses.contains(address))
+                    .cloned()
+                    .collect(),
+                memos: memos
+                    .iter()
+                    .filter(|memo| !current.memos.contains(memo))
+                    .cloned()
+                    .collect(),
             };
 
-            let strategies: Result<Vec<Strategy>, StdError> = partition
+            let event = Event::new(format!("{}/address-book.update", env!("CARGO_PKG_NAME")))
+                .add_attribute("owner", info.sender.as_str())
+                .add_attribute("addresses", addresses.len().to_string())
+                .add_attribute("memos", memos.len().to_string());
+
+            // Removals take effect immediately, but additions open new outflow
+            // destinations so wait out the timelocks of the owner's strategies
+            let timelock_duration = owner_timelocks(deps.as_ref(), &info.sender)?
+                .iter()
+                .map(|timelock| timelock.duration)
+                .max();
+
+            match timelock_duration {
+                Some(duration) if !additions.is_empty() => {
+                    if let Some(pending_address_book) =
+                        PENDING_ADDRESS_BOOKS.may_load(deps.storage, info.sender.clone())?
+                    {
+                        return Err(ContractError::generic_err(format!(
+                            "Address book of {} already has additions pending until {}, cancel them first",
+                            info.sender, pending_address_book.eta
+                        )));
+                    }
+
+                    ADDRESS_BOOKS.save(
+                        deps.storage,
+                        info.sender.clone(),
+                        &AddressBook {
+                            addresses: current
+                                .addresses
+                                .into_iter()
+                                .filter(|address| addresses.contains(address))
+                                .collect(),
+                            memos: current
+                                .memos
+                                .into_iter()
+                                .filter(|memo| memos.contains(memo))
+                                .collect(),
+                        },
+                    )?;
+
+                    let eta = env.block.time.seconds() + duration;
+
+                    PENDING_ADDRESS_BOOKS.save(
+                        deps.storage,
+                        info.sender.clone(),
+                        &PendingAddressBook {
+                            owner: info.sender.clone(),
+                            additions,
+                            queued_at: env.block.time.seconds(),
+                            eta,
+                        },
+                    )?;
+
+                    Ok(Response::new().add_event(event.add_attribute("eta", eta.to_string())))
+                }
+                _ => {
+                    ADDRESS_BOOKS.save(
+                        deps.storage,
+                        info.sender.clone(),
+                        &AddressBook { addresses, memos },
+                    )?;
+
+                    Ok(Response::new().add_event(event))
+                }
+            }
+        }
+        ManagerExecuteMsg::ApplyPendingAddressBook { owner } => {
+            let pending_address_book = PENDING_ADDRESS_BOOKS
+                .may_load(deps.storage, owner.clone())?
+                .ok_or_else(|| {
+                    ContractError::generic_err(format!(
+                        "No pending address book additions for {owner}"
+                    ))
+                })?;
+
+            if env.block.time.seconds() < pending_address_book.eta {
+                return Err(ContractError::generic_err(format!(
+                    "Pending address book additions cannot be applied before {}",
+                    pending_address_book.eta
+                )));
+            }
+
+            PENDING_ADDRESS_BOOKS.remove(deps.storage, owner.clone());
+
+            let mut address_book = ADDRESS_BOOKS
+                .may_load(deps.storage, owner.clone())?
+                .unwrap_or_default();
+
+            for address in pending_address_book.additions.addresses {
+                if !address_book.addresses.contains(&address) {
+                    address_book.addresses.push(address);
+                }
+            }
+
+            for memo in pending_address_book.additions.memos {
+                if !address_book.memos.contains(&memo) {
+                    address_book.memos.push(memo);
+                }
+            }
+
+            ADDRESS_BOOKS.save(deps.storage, owner.clone(), &address_book)?;
+
+            Ok(Response::new().add_event(
+                Event::new(format!("{}/address-book.update", env!("CARGO_PKG_NAME")))
+                    .add_attribute("owner", owner)
+                    .add_attribute("addresses", address_book.addresses.len().to_string())
+                    .add_attribute("memos", address_book.memos.len().to_string()),
+            ))
+        }
+        ManagerExecuteMsg::CancelPendingAddressBook { owner } => {
+            let guardians = owner_timelocks(deps.as_ref(), &owner)?
+                .into_iter()
+                .filter_map(|timelock| timelock.guardian)
+                .collect::<Vec<_>>();
+
+            if owner != info.sender && !guardians.contains(&info.sender) {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            if !PENDING_ADDRESS_BOOKS.has(deps.storage, owner.clone()) {
+                return Err(ContractError::generic_err(format!(
+                    "No pending address book additions for {owner}"
+                )));
+            }
+
+            PENDING_ADDRESS_BOOKS.remove(deps.storage, owner.clone());
+
+            Ok(Response::new().add_event(
+                Event::new(format!(
+                    "{}/address-book.cancel-pending",
+                    env!("CARGO_PKG_NAME")
+                ))
+                .add_attribute("canceller", info.sender)
+                .add_attribute("owner", owner),
+            ))
+        }
+        ManagerExecuteMsg::UpdateConfig(config) => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+            validate_config(deps.as_ref(), &config)?;
+
+            CONFIG.save(deps.storage, &config)?;
+
+            Ok(Response::new().add_event(
+                Event::new(format!("{}/config.update", env!("CARGO_PKG_NAME")))
+                    .add_attribute("admin", info.sender),
+            ))
+        }
+        ManagerExecuteMsg::MigrateStrategies { start_after, limit } => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+
+            let config = CONFIG.load(deps.storage)?;
+
+            let strategies = STRATEGIES
                 .range(
                     deps.storage,
+                    start_after.map(Bound::exclusive),
                     None,
-                    start_after
-                        .map(|updated_at| Bound::exclusive(updated_at_cursor(updated_at, None))),
-                    Order::Descending,
+                    Order::Ascending,
                 )
                 .take(limit.unwrap_or(30) as usize)
                 .map(|result| result.map(|(_, strategy)| strategy))
-                .collect();
+                .collect::<StdResult<Vec<_>>>()?;
+
+            let mut sub_messages = vec![];
+            let mut events = vec![];
+            let mut queued = 0;
+
+            for strategy in strategies.iter() {
+                let contract_info = deps
+                    .querier
+                    .query_wasm_contract_info(&strategy.contract_address)?;
+
+                let mut code_id = contract_info.code_id;
+
+                // Only strategies that opted into manager administration can be migrated
+                if contract_info.admin.as_ref() == Some(&env.contract.address)
+                    && code_id != config.strategy_code_id
+                {
+                    // Timelocked strategies get the same window to react to new code
+                    // as they do to node updates
+                    if let Some(timelock) =
+                        TIMELOCKS.may_load(deps.storage, strategy.contract_address.clone())?
+                    {
+                        match queue_update(
+                            deps.branch(),
+                            &env,
+                            strategy.contract_address.clone(),
+                            PendingChange::CodeId(config.strategy_code_id),
+                            timelock.duration,
+                        ) {
+                            Ok(response) => {
+                                events.extend(response.events);
+                                queued += 1;
+                            }
+                            Err(err) => events.push(
+                                migrate_result_event(&strategy.contract_address)
+                                    .add_attribute("status", "skipped")
+                                    .add_attribute("error", err.to_string()),
+                            ),
+                        }
+                    } else {
+                        sub_messages.push(migrate_strategy_msg(
+                            &strategy.contract_address,
+                            config.strategy_code_id,
+                        )?);
+
+                        code_id = config.strategy_code_id;
+                    }
+                }
 
-            to_json_binary(&strategies?)
+                if code_id != strategy.code_id {
+                    STRATEGIES.save(
+                        deps.storage,
+                        strategy.contract_address.clone(),
+                        &Strategy {
+                            code_id,
+                            ..strategy.clone()
+                        },
+                    )?;
+                }
+            }
+
+            let mut event = Event::new(format!("{}/strategy.migrate", env!("CARGO_PKG_NAME")))
+                .add_attribute("code_id", config.strategy_code_id.to_string())
+                .add_attribute("migrated", sub_messages.len().to_string())
+                .add_attribute("queued", queued.to_string());
+
+            if let Some(last) = strategies.last() {
+                event = event.add_attribute("last", last.contract_address.as_str());
+            }
+
+            Ok(Response::new()
+                .add_event(event)
+                .add_events(events)
+                .add_submessages(sub_messages))
         }
-        ManagerQueryMsg::StrategiesById { start_after, limit } => {
-            let strategies: Vec<Strategy> = STRATEGIES
+        ManagerExecuteMsg::ReleaseAdmin { contract_address } => {
+            let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
+
+            if strategy.owner != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            // An owner administered strategy can be migrated to any code, so
+            // releasing admin waits out the timelock like a code ID change
+            if let Some(timelock) = TIMELOCKS.may_load(deps.storage, contract_address.clone())? {
+                assert_manager_admin(deps.as_ref(), &env, &contract_address)?;
+
+                return queue_update(
+                    deps,
+                    &env,
+                    contract_address,
+                    PendingChange::ReleaseAdmin,
+                    timelock.duration,
+                );
+            }
+
+            release_admin(deps.as_ref(), &env, strategy)
+        }
+        ManagerExecuteMsg::ReindexStrategies { start_after, limit } => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+
+            let strategies = STRATEGIES
                 .range(
                     deps.storage,
                     start_after.map(Bound::exclusive),
                     None,
                     Order::Ascending,
                 )
-                .filter_map(|item| item.ok().map(|(_, strategy)| strategy))
                 .take(limit.unwrap_or(30) as usize)
-                .collect();
+                .map(|result| result.map(|(_, strategy)| strategy))
+                .collect::<StdResult<Vec<_>>>()?;
 
-            to_json_binary(&strategies)
-        }
-        ManagerQueryMsg::Count {} => to_json_binary(&STRATEGY_COUNTER.load(deps.storage)?),
-    }
-}
+            for strategy in strategies.iter() {
+                let config = deps.querier.query_wasm_smart::<StrategyConfig>(
+                    &strategy.contract_address,
+                    &StrategyQueryMsg::Config {},
+                )?;
 
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::{
-        testing::{message_info, mock_dependencies, mock_env},
-        Addr,
-    };
+                index_assets(deps.branch(), &strategy.contract_address, &config.nodes)?;
 
-    use super::*;
+                // Re-saving fills in indexes added after the strategy was created
+                STRATEGIES.save(deps.storage, strategy.contract_address.clone(), strategy)?;
+            }
 
-    #[test]
-    fn test_cannot_execute_inactive_strategy() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let info = message_info(&deps.api.addr_make("anyone"), &[]);
+            let mut event = Event::new(format!("{}/strategy.reindex", env!("CARGO_PKG_NAME")))
+                .add_attribute("count", strategies.len().to_string());
 
-        let strategy = Strategy {
-            id: 1,
-            source: None,
-            owner: info.sender.clone(),
-            contract_address: Addr::unchecked("contract"),
+            if let Some(last) = strategies.last() {
+                event = event.add_attribute("last", last.contract_address.as_str());
+            }
+
+            Ok(Response::new().add_event(event))
+        }
+        ManagerExecuteMsg::SetPaused { paused } => {
+            assert_admin(deps.as_ref(), &info.sender)?;
+
+            PAUSED.save(deps.storage, &paused)?;
+
+            Ok(Response::new().add_event(
+                Event::new(format!("{}/set-paused", env!("CARGO_PKG_NAME")))
+                    .add_attribute("admin", info.sender)
+                    .add_attribute("paused", paused.to_string()),
+            ))
+        }
+        ManagerExecuteMsg::SetDisabledNodes {
+            contract_address,
+            nodes,
+        } => {
+            let strategy = STRATEGIES.load(deps.storage, contract_address.clone())?;
+
+            if strategy.owner != info.sender {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let disabled_nodes = nodes
+                .iter()
+                .map(|node| node.index.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let set_disabled_nodes_msg = Contract(contract_address.clone()).call(
+                to_json_binary(&StrategyExecuteMsg::SetDisabledNodes(nodes))?,
+                vec![],
+            );
+
+            Ok(Response::new()
+                .add_event(
+                    Event::new(format!(
+                        "{}/strategy.set-disabled-nodes",
+                        env!("CARGO_PKG_NAME")
+                    ))
+                    .add_attribute("nodes", disabled_nodes)
+                    .add_attribute("strategy_address", contract_address.as_str()),
+                )
+                .add_message(set_disabled_nodes_msg))
+        }
+    }
+}
+
+fn update_strategy(
+    deps: DepsMut,
+    env: &Env,
+    strategy: Strategy,
+    nodes: Vec<Node>,
+    funds: Vec<Coin>,
+) -> ContractResult {
+    let contract_address = strategy.contract_address.clone();
+
+    STRATEGIES.save(
+        deps.storage,
+        contract_address.clone(),
+        &Strategy {
+            updated_at: env.block.time.seconds(),
+            ..strategy
+        },
+    )?;
+
+    index_assets(deps, &contract_address, &nodes)?;
+
+    let update_msg = Contract(contract_address.clone())
+        .call(to_json_binary(&StrategyExecuteMsg::Update(nodes))?, funds);
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(format!("{}/strategy.update", env!("CARGO_PKG_NAME")))
+                .add_attribute("strategy_address", contract_address.as_str()),
+        )
+        .add_message(update_msg))
+}
+
+fn update_status(
+    deps: DepsMut,
+    env: &Env,
+    strategy: Strategy,
+    status: StrategyStatus,
+    funds: Vec<Coin>,
+) -> ContractResult {
+    let contract_address = strategy.contract_address.clone();
+
+    STRATEGIES.save(
+        deps.storage,
+        contract_address.clone(),
+        &Strategy {
+            status: status.clone(),
+            updated_at: env.block.time.seconds(),
+            ..strategy
+        },
+    )?;
+
+    let strategy_msg = Contract(contract_address.clone()).call(
+        to_json_binary(&match status {
+            StrategyStatus::Active => StrategyExecuteMsg::Execute {},
+            StrategyStatus::Paused | StrategyStatus::Archived => StrategyExecuteMsg::Cancel {},
+        })?,
+        funds,
+    );
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(format!("{}/strategy.update-status", env!("CARGO_PKG_NAME")))
+                .add_attribute("status", status.as_str())
+                .add_attribute("strategy_address", contract_address.as_str()),
+        )
+        .add_message(strategy_msg))
+}
+
+fn queue_update(
+    deps: DepsMut,
+    env: &Env,
+    contract_address: Addr,
+    change: PendingChange,
+    duration: u64,
+) -> ContractResult {
+    // Replacing a pending update would silently restart its timelock
+    if let Some(pending_update) =
+        PENDING_UPDATES.may_load(deps.storage, contract_address.clone())?
+    {
+        return Err(ContractError::generic_err(format!(
+            "Strategy {contract_address} already has an update pending until {}, cancel it first",
+            pending_update.eta
+        )));
+    }
+
+    let eta = env.block.time.seconds() + duration;
+
+    PENDING_UPDATES.save(
+        deps.storage,
+        contract_address.clone(),
+        &PendingUpdate {
+            contract_address: contract_address.clone(),
+            change,
+            queued_at: env.block.time.seconds(),
+            eta,
+        },
+    )?;
+
+    Ok(Response::new().add_event(
+        Event::new(format!("{}/strategy.queue-update", env!("CARGO_PKG_NAME")))
+            .add_attribute("eta", eta.to_string())
+            .add_attribute("strategy_address", contract_address.as_str()),
+    ))
+}
+
+// Address books are shared by all of an owner's strategies, so changes to them
+// are held to the timelocks of every one of those strategies
+fn owner_timelocks(deps: Deps, owner: &Addr) -> StdResult<Vec<Timelock>> {
+    STRATEGIES
+        .idx
+        .owner_updated_at
+        .prefix(owner.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .filter_map(|contract_address| {
+            contract_address
+                .and_then(|contract_address| TIMELOCKS.may_load(deps.storage, contract_address))
+                .transpose()
+        })
+        .collect()
+}
+
+fn assert_manager_admin(
+    deps: Deps,
+    env: &Env,
+    contract_address: &Addr,
+) -> Result<(), ContractError> {
+    let contract_info = deps.querier.query_wasm_contract_info(contract_address)?;
+
+    if contract_info.admin.as_ref() != Some(&env.contract.address) {
+        return Err(ContractError::generic_err(format!(
+            "Strategy {contract_address} is not administered by the manager"
+        )));
+    }
+
+    Ok(())
+}
+
+fn release_admin(deps: Deps, env: &Env, strategy: Strategy) -> ContractResult {
+    assert_manager_admin(deps, env, &strategy.contract_address)?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(format!("{}/strategy.release-admin", env!("CARGO_PKG_NAME")))
+                .add_attribute("owner", strategy.owner.as_str())
+                .add_attribute("strategy_address", strategy.contract_address.as_str()),
+        )
+        .add_message(WasmMsg::UpdateAdmin {
+            contract_addr: strategy.contract_address.to_string(),
+            admin: strategy.owner.to_string(),
+        }))
+}
+
+// Failed migrations are reported per strategy without reverting the rest of the batch
+fn migrate_strategy_msg(contract_address: &Addr, code_id: u64) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_error(
+        WasmMsg::Migrate {
+            contract_addr: contract_address.to_string(),
+            new_code_id: code_id,
+            msg: to_json_binary(&Empty {})?,
+        },
+        0,
+    )
+    .with_payload(to_json_binary(contract_address)?))
+}
+
+fn migrate_result_event(contract_address: &Addr) -> Event {
+    Event::new(format!(
+        "{}/strategy.migrate-result",
+        env!("CARGO_PKG_NAME")
+    ))
+    .add_attribute("strategy_address", contract_address.as_str())
+}
+
+fn set_strategy_code_id(
+    storage: &mut dyn Storage,
+    contract_address: &Addr,
+    code_id: u64,
+) -> StdResult<()> {
+    let strategy = STRATEGIES.load(storage, contract_address.clone())?;
+
+    STRATEGIES.save(
+        storage,
+        contract_address.clone(),
+        &Strategy {
+            code_id,
+            ..strategy
+        },
+    )
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> ContractResult {
+    let contract_address = from_json::<Addr>(&reply.payload)?;
+
+    match reply.result {
+        SubMsgResult::Ok(_) => Ok(Response::new()),
+        SubMsgResult::Err(err) => {
+            // The strategy stays on the code it was running before the failed migration
+            let contract_info = deps.querier.query_wasm_contract_info(&contract_address)?;
+            set_strategy_code_id(deps.storage, &contract_address, contract_info.code_id)?;
+
+            Ok(Response::new().add_event(
+                migrate_result_event(&contract_address)
+                    .add_attribute("status", "error")
+                    .add_attribute("error", err),
+            ))
+        }
+    }
+}
+
+fn set_timelock(
+    deps: DepsMut,
+    contract_address: Addr,
+    timelock: Option<Timelock>,
+) -> ContractResult {
+    let event = Event::new(format!("{}/strategy.set-timelock", env!("CARGO_PKG_NAME")))
+        .add_attribute("strategy_address", contract_address.as_str());
+
+    match timelock {
+        Some(timelock) => {
+            TIMELOCKS.save(deps.storage, contract_address, &timelock)?;
+            Ok(Response::new()
+                .add_event(event.add_attribute("duration", timelock.duration.to_string())))
+        }
+        None => {
+            TIMELOCKS.remove(deps.storage, contract_address);
+            Ok(Response::new().add_event(event.add_attribute("duration", "0")))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: ManagerQueryMsg) -> StdResult<Binary> {
+    match msg {
+        ManagerQueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        ManagerQueryMsg::Strategy { address } => {
+            to_json_binary(&STRATEGIES.load(deps.storage, address)?)
+        }
+        ManagerQueryMsg::Strategies {
+            owner,
+            status,
+            start_after,
+            limit,
+        } => {
+            let partition = match owner {
+                Some(owner) => match status {
+                    Some(status) => STRATEGIES
+                        .idx
+                        .owner_status_updated_at
+                        .prefix((owner, status as u8)),
+                    None => STRATEGIES.idx.owner_updated_at.prefix(owner),
+                },
+                None => match status {
+                    Some(status) => STRATEGIES.idx.status_updated_at.prefix(status as u8),
+                    None => STRATEGIES.idx.updated_at.prefix(()),
+                },
+            };
+
+            let strategies: Result<Vec<Strategy>, StdError> = partition
+                .range(
+                    deps.storage,
+                    None,
+                    start_after
+                        .map(|updated_at| Bound::exclusive(updated_at_cursor(updated_at, None))),
+                    Order::Descending,
+                )
+                .take(limit.unwrap_or(30) as usize)
+                .map(|result| result.map(|(_, strategy)| strategy))
+                .collect();
+
+            to_json_binary(&strategies?)
+        }
+        ManagerQueryMsg::StrategiesBySource {
+            source,
+            start_after,
+            limit,
+        } => {
+            let strategies = STRATEGIES
+                .idx
+                .source_updated_at
+                .prefix(source)
+                .range(
+                    deps.storage,
+                    None,
+                    start_after
+                        .map(|updated_at| Bound::exclusive(updated_at_cursor(updated_at, None))),
+                    Order::Descending,
+                )
+                .take(limit.unwrap_or(30) as usize)
+                .map(|result| result.map(|(_, strategy)| strategy))
+                .collect::<StdResult<Vec<_>>>()?;
+
+            to_json_binary(&strategies)
+        }
+        ManagerQueryMsg::StrategiesByLabel {
+            prefix,
+            start_after,
+            limit,
+        } => {
+            let min = match start_after {
+                Some(address) => Bound::exclusive(label_cursor(
+                    &STRATEGIES.load(deps.storage, address.clone())?.label,
+                    &address,
+                )),
+                None => Bound::inclusive(prefix.clone()),
+            };
+
+            let strategies = STRATEGIES
+                .idx
+                .label
+                .range(
+                    deps.storage,
+                    Some(min),
+                    Some(Bound::exclusive(format!("{prefix}{}", char::MAX))),
+                    Order::Ascending,
+                )
+                .map(|result| result.map(|(_, strategy)| strategy))
+                .take(limit.unwrap_or(30) as usize)
+                .collect::<StdResult<Vec<_>>>()?;
+
+            to_json_binary(&strategies)
+        }
+        ManagerQueryMsg::StrategiesByDenom {
+            denom,
+            start_after,
+            limit,
+        } => {
+            let strategies = DENOM_STRATEGIES
+                .prefix(denom)
+                .keys(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit.unwrap_or(30) as usize)
+                .map(|result| result.and_then(|address| STRATEGIES.load(deps.storage, address)))
+                .collect::<StdResult<Vec<_>>>()?;
+
+            to_json_binary(&strategies)
+        }
+        ManagerQueryMsg::StrategiesByPair {
+            pair_address,
+            start_after,
+            limit,
+        } => {
+            let strategies = PAIR_STRATEGIES
+                .prefix(pair_address)
+                .keys(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit.unwrap_or(30) as usize)
+                .map(|result| result.and_then(|address| STRATEGIES.load(deps.storage, address)))
+                .collect::<StdResult<Vec<_>>>()?;
+
+            to_json_binary(&strategies)
+        }
+        ManagerQueryMsg::Portfolio {
+            owner,
+            valuation,
+            start_after,
+            limit,
+        } => {
+            let strategies = STRATEGIES
+                .idx
+                .owner_updated_at
+                .prefix(owner.clone())
+                .range(
+                    deps.storage,
+                    None,
+                    // Strategies updated in the same block are told apart by address
+                    start_after.map(|(updated_at, contract_address)| {
+                        Bound::exclusive(updated_at_cursor(updated_at, Some(&contract_address)))
+                    }),
+                    Order::Descending,
+                )
+                .take(limit.unwrap_or(30).min(MAX_PORTFOLIO_LIMIT) as usize)
+                .map(|result| result.map(|(_, strategy)| strategy))
+                .collect::<StdResult<Vec<_>>>()?;
+
+            let mut balances = Coins::default();
+            let mut value = valuation.as_ref().map(|_| Uint128::zero());
+            let mut portfolio_strategies = Vec::with_capacity(strategies.len());
+
+            for strategy in strategies {
+                // One broken strategy should not hide the rest of the portfolio
+                let strategy_balances = match deps.querier.query_wasm_smart::<Vec<Coin>>(
+                    &strategy.contract_address,
+                    &StrategyQueryMsg::Balances {},
+                ) {
+                    Ok(strategy_balances) => strategy_balances,
+                    Err(err) => {
+                        value = None;
+
+                        portfolio_strategies.push(PortfolioStrategy {
+                            strategy,
+                            balances: vec![],
+                            value: None,
+                            error: Some(err.to_string()),
+                        });
+
+                        continue;
+                    }
+                };
+
+                for balance in strategy_balances.iter() {
+                    balances.add(balance.clone())?;
+                }
+
+                let strategy_value = valuation.as_ref().and_then(|valuation| {
+                    value_in(
+                        deps,
+                        &strategy_balances,
+                        &valuation.quote,
+                        &valuation.oracle,
+                    )
+                    .ok()
+                });
+
+                value = value.zip(strategy_value).map(|(total, v)| total + v);
+
+                portfolio_strategies.push(PortfolioStrategy {
+                    strategy,
+                    balances: strategy_balances,
+                    value: strategy_value,
+                    error: None,
+                });
+            }
+
+            to_json_binary(&Portfolio {
+                owner,
+                strategies: portfolio_strategies,
+                balances: balances.to_vec(),
+                value,
+            })
+        }
+        ManagerQueryMsg::StrategiesById { start_after, limit } => {
+            let strategies: Vec<Strategy> = STRATEGIES
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .filter_map(|item| item.ok().map(|(_, strategy)| strategy))
+                .take(limit.unwrap_or(30) as usize)
+                .collect();
+
+            to_json_binary(&strategies)
+        }
+        ManagerQueryMsg::Count {} => to_json_binary(&STRATEGY_COUNTER.load(deps.storage)?),
+        ManagerQueryMsg::Timelock { address } => {
+            to_json_binary(&TIMELOCKS.may_load(deps.storage, address)?)
+        }
+        ManagerQueryMsg::PendingUpdate { address } => {
+            to_json_binary(&PENDING_UPDATES.may_load(deps.storage, address)?)
+        }
+        ManagerQueryMsg::AddressBook { owner } => to_json_binary(
+            &ADDRESS_BOOKS
+                .may_load(deps.storage, owner)?
+                .unwrap_or_default(),
+        ),
+        ManagerQueryMsg::PendingAddressBook { owner } => {
+            to_json_binary(&PENDING_ADDRESS_BOOKS.may_load(deps.storage, owner)?)
+        }
+        ManagerQueryMsg::Paused {} => {
+            to_json_binary(&PAUSED.may_load(deps.storage)?.unwrap_or(false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calc_rs::{
+        actions::{
+            action::Action,
+            distribution::{Destination, Distribution, Recipient},
+            limit_orders::{fin_limit_order::LimitOrderStats, fin_market_maker::FinMarketMaker},
+            swaps::{
+                fin::FinRoute,
+                swap::{Swap, SwapAmountAdjustment, SwapRoute, SwapRouting},
+            },
+        },
+        conditions::asset_value_ratio::PriceSource,
+        core::Amount,
+        manager::{FeeSchedule, Valuation},
+        strategy::DisabledNode,
+    };
+    use cosmwasm_std::{
+        from_json,
+        testing::{message_info, mock_dependencies, mock_env},
+        Addr, ContractResult, CosmosMsg, Decimal, SystemResult, Uint128, WasmQuery,
+    };
+    use rujira_rs::fin::{ConfigResponse, Denoms, Tick};
+
+    use super::*;
+    use crate::state::{StrategyAssets, STRATEGY_ASSETS};
+
+    #[test]
+    fn test_cannot_execute_inactive_strategy() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&deps.api.addr_make("anyone"), &[]);
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: info.sender.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds(),
             label: "Test Strategy".to_string(),
@@ -490,6 +1487,7 @@ mod tests {
             source: None,
             owner: info.sender.clone(),
             contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds(),
             label: "Test Strategy".to_string(),
@@ -540,6 +1538,7 @@ mod tests {
             source: None,
             owner: info.sender.clone(),
             contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds(),
             label: "Test Strategy".to_string(),
@@ -590,6 +1589,7 @@ mod tests {
             source: None,
             owner: info.sender.clone(),
             contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds() - 1000,
             label: "Test Strategy".to_string(),
@@ -633,6 +1633,7 @@ mod tests {
             source: None,
             owner: info.sender.clone(),
             contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds() - 1000,
             label: "Test Strategy".to_string(),
@@ -677,6 +1678,7 @@ mod tests {
             source: None,
             owner: info.sender.clone(),
             contract_address: Addr::unchecked("contract"),
+            code_id: 1,
             created_at: env.block.time.seconds(),
             updated_at: env.block.time.seconds() - 1000,
             label: "Test Strategy".to_string(),
@@ -710,4 +1712,1559 @@ mod tests {
         assert!(updated_strategy.updated_at == env.block.time.seconds());
         assert!(updated_strategy.updated_at > strategy.updated_at);
     }
+
+    #[test]
+    fn test_only_owner_can_set_disabled_nodes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&deps.api.addr_make("owner"), &[]);
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: info.sender.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        let nodes = vec![DisabledNode {
+            index: 0,
+            branch: None,
+        }];
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ManagerExecuteMsg::SetDisabledNodes {
+                contract_address: strategy.contract_address.clone(),
+                nodes: nodes.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            Contract(strategy.contract_address.clone()).call(
+                to_json_binary(&StrategyExecuteMsg::SetDisabledNodes(nodes.clone())).unwrap(),
+                vec![]
+            )
+        );
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env,
+                message_info(&deps.api.addr_make("not-owner"), &[]),
+                ManagerExecuteMsg::SetDisabledNodes {
+                    contract_address: strategy.contract_address.clone(),
+                    nodes,
+                },
+            ),
+            Err(ContractError::Unauthorized {})
+        );
+    }
+
+    fn distribute_to(address: &Addr) -> Node {
+        Node::Action {
+            action: Action::Distribute(Distribution {
+                denoms: vec!["rune".to_string()],
+                destinations: vec![Destination {
+                    shares: Uint128::new(10_000),
+                    recipient: Recipient::Bank {
+                        address: address.clone(),
+                    },
+                    label: None,
+                    distributions: None,
+                }],
+            }),
+            index: 0,
+            next: None,
+        }
+    }
+
+    #[test]
+    fn test_timelocked_update_is_queued_when_outflows_change() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = message_info(&deps.api.addr_make("owner"), &[]);
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: info.sender.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        TIMELOCKS
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &Timelock {
+                    duration: 86_400,
+                    guardian: None,
+                },
+            )
+            .unwrap();
+
+        let owner = info.sender.clone();
+
+        deps.querier.update_wasm(move |_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&StrategyConfig {
+                    manager: Addr::unchecked("manager"),
+                    owner: owner.clone(),
+                    nodes: vec![distribute_to(&owner)],
+                    withdrawals: vec![],
+                })
+                .unwrap(),
+            ))
+        });
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ManagerExecuteMsg::Update {
+                contract_address: strategy.contract_address.clone(),
+                nodes: vec![distribute_to(&info.sender)],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+
+        let attacker = deps.api.addr_make("attacker");
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ManagerExecuteMsg::Update {
+                contract_address: strategy.contract_address.clone(),
+                nodes: vec![distribute_to(&attacker)],
+            },
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+
+        let pending_update = PENDING_UPDATES
+            .load(deps.as_ref().storage, strategy.contract_address.clone())
+            .unwrap();
+
+        assert_eq!(
+            pending_update.change,
+            PendingChange::Nodes(vec![distribute_to(&attacker)])
+        );
+        assert_eq!(pending_update.eta, env.block.time.seconds() + 86_400);
+
+        let mut later_env = env.clone();
+        later_env.block.time = later_env.block.time.plus_seconds(3_600);
+
+        assert!(execute(
+            deps.as_mut(),
+            later_env,
+            info.clone(),
+            ManagerExecuteMsg::Update {
+                contract_address: strategy.contract_address.clone(),
+                nodes: vec![distribute_to(&deps.api.addr_make("other"))],
+            },
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("already has an update pending"));
+
+        assert_eq!(
+            PENDING_UPDATES
+                .load(deps.as_ref().storage, strategy.contract_address.clone())
+                .unwrap(),
+            pending_update
+        );
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&attacker, &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .is_err());
+
+        env.block.time = env.block.time.plus_seconds(86_400);
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&attacker, &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            Contract(strategy.contract_address.clone()).call(
+                to_json_binary(&StrategyExecuteMsg::Update(vec![distribute_to(&attacker)]))
+                    .unwrap(),
+                vec![]
+            )
+        );
+
+        assert!(!PENDING_UPDATES.has(deps.as_ref().storage, strategy.contract_address.clone()));
+    }
+
+    #[test]
+    fn test_only_owner_or_guardian_can_cancel_pending_update() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let guardian = deps.api.addr_make("guardian");
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: owner.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::SetTimelock {
+                contract_address: strategy.contract_address.clone(),
+                timelock: Some(Timelock {
+                    duration: 3_600,
+                    guardian: Some(guardian.clone()),
+                }),
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::SetTimelock {
+                contract_address: strategy.contract_address.clone(),
+                timelock: None,
+            },
+        )
+        .unwrap();
+
+        assert!(TIMELOCKS.has(deps.as_ref().storage, strategy.contract_address.clone()));
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&deps.api.addr_make("anyone"), &[]),
+                ManagerExecuteMsg::CancelPendingUpdate {
+                    contract_address: strategy.contract_address.clone(),
+                },
+            ),
+            Err(ContractError::Unauthorized {})
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&guardian, &[]),
+            ManagerExecuteMsg::CancelPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .unwrap();
+
+        assert!(!PENDING_UPDATES.has(deps.as_ref().storage, strategy.contract_address.clone()));
+        assert!(TIMELOCKS.has(deps.as_ref().storage, strategy.contract_address.clone()));
+    }
+
+    #[test]
+    fn test_timelocked_address_book_additions_are_queued() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let guardian = deps.api.addr_make("guardian");
+        let trusted = deps.api.addr_make("trusted");
+        let attacker = deps.api.addr_make("attacker");
+
+        let address_book = |deps: Deps| ADDRESS_BOOKS.load(deps.storage, owner.clone()).unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateAddressBook {
+                addresses: vec![trusted.clone()],
+                memos: vec![],
+            },
+        )
+        .unwrap();
+
+        // Owners without timelocked strategies update their book straight away
+        assert_eq!(address_book(deps.as_ref()).addresses, vec![trusted.clone()]);
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: owner.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        TIMELOCKS
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &Timelock {
+                    duration: 3_600,
+                    guardian: Some(guardian.clone()),
+                },
+            )
+            .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateAddressBook {
+                addresses: vec![trusted.clone(), attacker.clone()],
+                memos: vec!["=:ETH.ETH:0xattacker".to_string()],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(address_book(deps.as_ref()).addresses, vec![trusted.clone()]);
+
+        let pending_address_book = PENDING_ADDRESS_BOOKS
+            .load(deps.as_ref().storage, owner.clone())
+            .unwrap();
+
+        assert_eq!(
+            pending_address_book.additions,
+            AddressBook {
+                addresses: vec![attacker.clone()],
+                memos: vec!["=:ETH.ETH:0xattacker".to_string()],
+            }
+        );
+        assert_eq!(pending_address_book.eta, env.block.time.seconds() + 3_600);
+
+        // Removals are never delayed
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateAddressBook {
+                addresses: vec![],
+                memos: vec![],
+            },
+        )
+        .unwrap();
+
+        assert!(address_book(deps.as_ref()).is_empty());
+        assert!(PENDING_ADDRESS_BOOKS.has(deps.as_ref().storage, owner.clone()));
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::ApplyPendingAddressBook {
+                owner: owner.clone(),
+            },
+        )
+        .is_err());
+
+        env.block.time = env.block.time.plus_seconds(3_600);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&deps.api.addr_make("keeper"), &[]),
+            ManagerExecuteMsg::ApplyPendingAddressBook {
+                owner: owner.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            address_book(deps.as_ref()),
+            AddressBook {
+                addresses: vec![attacker.clone()],
+                memos: vec!["=:ETH.ETH:0xattacker".to_string()],
+            }
+        );
+        assert!(!PENDING_ADDRESS_BOOKS.has(deps.as_ref().storage, owner.clone()));
+
+        // The guardian of any of the owner's strategies can cancel additions
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateAddressBook {
+                addresses: vec![attacker.clone(), trusted.clone()],
+                memos: vec![],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&attacker, &[]),
+                ManagerExecuteMsg::CancelPendingAddressBook {
+                    owner: owner.clone(),
+                },
+            ),
+            Err(ContractError::Unauthorized {})
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&guardian, &[]),
+            ManagerExecuteMsg::CancelPendingAddressBook {
+                owner: owner.clone(),
+            },
+        )
+        .unwrap();
+
+        assert!(!PENDING_ADDRESS_BOOKS.has(deps.as_ref().storage, owner.clone()));
+        assert_eq!(address_book(deps.as_ref()).addresses, vec![attacker]);
+    }
+
+    #[test]
+    fn test_timelocked_status_changes_are_queued_except_pausing() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = deps.api.addr_make("owner");
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: owner.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        TIMELOCKS
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &Timelock {
+                    duration: 86_400,
+                    guardian: None,
+                },
+            )
+            .unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateStatus {
+                contract_address: strategy.contract_address.clone(),
+                status: StrategyStatus::Paused,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+        assert!(!PENDING_UPDATES.has(deps.as_ref().storage, strategy.contract_address.clone()));
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::UpdateStatus {
+                contract_address: strategy.contract_address.clone(),
+                status: StrategyStatus::Archived,
+            },
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+        assert_eq!(
+            PENDING_UPDATES
+                .load(deps.as_ref().storage, strategy.contract_address.clone())
+                .unwrap()
+                .change,
+            PendingChange::Status(StrategyStatus::Archived)
+        );
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, strategy.contract_address.clone())
+                .unwrap()
+                .status,
+            StrategyStatus::Paused
+        );
+
+        env.block.time = env.block.time.plus_seconds(86_400);
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&deps.api.addr_make("anyone"), &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            Contract(strategy.contract_address.clone()).call(
+                to_json_binary(&StrategyExecuteMsg::Cancel {}).unwrap(),
+                vec![]
+            )
+        );
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, strategy.contract_address.clone())
+                .unwrap()
+                .status,
+            StrategyStatus::Archived
+        );
+    }
+
+    #[test]
+    fn test_timelocked_release_admin_is_queued() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = env.contract.address.clone();
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: owner.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        TIMELOCKS
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &Timelock {
+                    duration: 86_400,
+                    guardian: None,
+                },
+            )
+            .unwrap();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(Binary::from(
+                format!(
+                    r#"{{"code_id":1,"creator":"{manager}","admin":"{manager}","pinned":false}}"#
+                )
+                .as_bytes(),
+            ))),
+            _ => panic!("Unexpected query type"),
+        });
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::ReleaseAdmin {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+        assert_eq!(
+            PENDING_UPDATES
+                .load(deps.as_ref().storage, strategy.contract_address.clone())
+                .unwrap()
+                .change,
+            PendingChange::ReleaseAdmin
+        );
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .is_err());
+
+        env.block.time = env.block.time.plus_seconds(86_400);
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&deps.api.addr_make("anyone"), &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::UpdateAdmin {
+                contract_addr: strategy.contract_address.to_string(),
+                admin: owner.to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_paused_manager_blocks_changes_but_allows_owner_pause() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = message_info(&deps.api.addr_make("admin"), &[]);
+        let owner = message_info(&deps.api.addr_make("owner"), &[]);
+
+        CONFIG
+            .save(
+                deps.as_mut().storage,
+                &ManagerConfig {
+                    admin: Some(admin.sender.clone()),
+                    fee_collector: deps.api.addr_make("fee_collector"),
+                    strategy_code_id: 1,
+                    fee_schedule: FeeSchedule::default(),
+                },
+            )
+            .unwrap();
+
+        let strategy = Strategy {
+            id: 1,
+            source: None,
+            owner: owner.sender.clone(),
+            contract_address: Addr::unchecked("contract"),
+            code_id: 1,
+            created_at: env.block.time.seconds(),
+            updated_at: env.block.time.seconds(),
+            label: "Test Strategy".to_string(),
+            status: StrategyStatus::Active,
+        };
+
+        STRATEGIES
+            .save(
+                deps.as_mut().storage,
+                strategy.contract_address.clone(),
+                &strategy,
+            )
+            .unwrap();
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                owner.clone(),
+                ManagerExecuteMsg::SetPaused { paused: true },
+            )
+            .unwrap_err()
+            .to_string(),
+            ContractError::Unauthorized {}.to_string()
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ManagerExecuteMsg::SetPaused { paused: true },
+        )
+        .unwrap();
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&deps.api.addr_make("scheduler"), &[]),
+                ManagerExecuteMsg::Execute {
+                    contract_address: strategy.contract_address.clone(),
+                },
+            )
+            .unwrap_err()
+            .to_string(),
+            ContractError::generic_err("Manager is paused").to_string()
+        );
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner.sender, &[Coin::new(1000_u128, "rune")]),
+            ManagerExecuteMsg::Execute {
+                contract_address: strategy.contract_address.clone(),
+            },
+        )
+        .is_err());
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            ManagerExecuteMsg::Update {
+                contract_address: strategy.contract_address.clone(),
+                nodes: vec![],
+            },
+        )
+        .is_err());
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            ManagerExecuteMsg::UpdateStatus {
+                contract_address: strategy.contract_address.clone(),
+                status: StrategyStatus::Active,
+            },
+        )
+        .is_err());
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            ManagerExecuteMsg::UpdateStatus {
+                contract_address: strategy.contract_address.clone(),
+                status: StrategyStatus::Paused,
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ManagerExecuteMsg::SetPaused { paused: false },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env,
+            owner,
+            ManagerExecuteMsg::UpdateStatus {
+                contract_address: strategy.contract_address,
+                status: StrategyStatus::Active,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_migrate_strategies_only_migrates_manager_administered_strategies() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = message_info(&deps.api.addr_make("admin"), &[]);
+        let owner = deps.api.addr_make("owner");
+        let manager = env.contract.address.clone();
+
+        CONFIG
+            .save(
+                deps.as_mut().storage,
+                &ManagerConfig {
+                    admin: Some(admin.sender.clone()),
+                    fee_collector: deps.api.addr_make("fee_collector"),
+                    strategy_code_id: 2,
+                    fee_schedule: FeeSchedule::default(),
+                },
+            )
+            .unwrap();
+
+        let migratable = deps.api.addr_make("migratable");
+        let owned = deps.api.addr_make("owned");
+
+        for contract_address in [migratable.clone(), owned.clone()] {
+            STRATEGIES
+                .save(
+                    deps.as_mut().storage,
+                    contract_address.clone(),
+                    &Strategy {
+                        id: 1,
+                        source: None,
+                        owner: owner.clone(),
+                        contract_address,
+                        code_id: 0,
+                        created_at: env.block.time.seconds(),
+                        updated_at: env.block.time.seconds(),
+                        label: "Test Strategy".to_string(),
+                        status: StrategyStatus::Active,
+                    },
+                )
+                .unwrap();
+        }
+
+        let migratable_address = migratable.clone();
+        let strategy_owner = owner.clone();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { contract_addr } => {
+                let admin = if *contract_addr == migratable_address.to_string() {
+                    manager.clone()
+                } else {
+                    strategy_owner.clone()
+                };
+
+                SystemResult::Ok(ContractResult::Ok(Binary::from(
+                    format!(
+                        r#"{{"code_id":1,"creator":"{admin}","admin":"{admin}","pinned":false}}"#
+                    )
+                    .as_bytes(),
+                )))
+            }
+            _ => panic!("Unexpected query type"),
+        });
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::MigrateStrategies {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .is_err());
+
+        let response = execute(
+            deps.as_mut(),
+            env,
+            admin,
+            ManagerExecuteMsg::MigrateStrategies {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            response.messages[0].msg,
+            WasmMsg::Migrate {
+                contract_addr: migratable.to_string(),
+                new_code_id: 2,
+                msg: to_json_binary(&Empty {}).unwrap(),
+            }
+            .into()
+        );
+
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, migratable)
+                .unwrap()
+                .code_id,
+            2
+        );
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, owned)
+                .unwrap()
+                .code_id,
+            1
+        );
+    }
+
+    #[test]
+    fn test_migrate_strategies_queues_timelocked_strategies_and_reports_failures() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = message_info(&deps.api.addr_make("admin"), &[]);
+        let owner = deps.api.addr_make("owner");
+        let manager = env.contract.address.clone();
+
+        CONFIG
+            .save(
+                deps.as_mut().storage,
+                &ManagerConfig {
+                    admin: Some(admin.sender.clone()),
+                    fee_collector: deps.api.addr_make("fee_collector"),
+                    strategy_code_id: 2,
+                    fee_schedule: FeeSchedule::default(),
+                },
+            )
+            .unwrap();
+
+        let unlocked = deps.api.addr_make("unlocked");
+        let timelocked = deps.api.addr_make("timelocked");
+
+        for contract_address in [unlocked.clone(), timelocked.clone()] {
+            STRATEGIES
+                .save(
+                    deps.as_mut().storage,
+                    contract_address.clone(),
+                    &Strategy {
+                        id: 1,
+                        source: None,
+                        owner: owner.clone(),
+                        contract_address,
+                        code_id: 1,
+                        created_at: env.block.time.seconds(),
+                        updated_at: env.block.time.seconds(),
+                        label: "Test Strategy".to_string(),
+                        status: StrategyStatus::Active,
+                    },
+                )
+                .unwrap();
+        }
+
+        TIMELOCKS
+            .save(
+                deps.as_mut().storage,
+                timelocked.clone(),
+                &Timelock {
+                    duration: 100,
+                    guardian: None,
+                },
+            )
+            .unwrap();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(Binary::from(
+                format!(
+                    r#"{{"code_id":1,"creator":"{manager}","admin":"{manager}","pinned":false}}"#
+                )
+                .as_bytes(),
+            ))),
+            _ => panic!("Unexpected query type"),
+        });
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ManagerExecuteMsg::MigrateStrategies {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages,
+            vec![SubMsg::reply_on_error(
+                WasmMsg::Migrate {
+                    contract_addr: unlocked.to_string(),
+                    new_code_id: 2,
+                    msg: to_json_binary(&Empty {}).unwrap(),
+                },
+                0,
+            )
+            .with_payload(to_json_binary(&unlocked).unwrap())]
+        );
+
+        let pending_update = PENDING_UPDATES
+            .load(deps.as_ref().storage, timelocked.clone())
+            .unwrap();
+
+        assert_eq!(pending_update.change, PendingChange::CodeId(2));
+        assert_eq!(pending_update.eta, env.block.time.seconds() + 100);
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, timelocked.clone())
+                .unwrap()
+                .code_id,
+            1
+        );
+
+        let response = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: 0,
+                payload: to_json_binary(&unlocked).unwrap(),
+                gas_used: 0,
+                result: SubMsgResult::Err("migration failed".to_string()),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.events,
+            vec![migrate_result_event(&unlocked)
+                .add_attribute("status", "error")
+                .add_attribute("error", "migration failed")]
+        );
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, unlocked)
+                .unwrap()
+                .code_id,
+            1
+        );
+
+        let mut later = env.clone();
+        later.block.time = later.block.time.plus_seconds(100);
+
+        let response = execute(
+            deps.as_mut(),
+            later,
+            message_info(&owner, &[]),
+            ManagerExecuteMsg::ApplyPendingUpdate {
+                contract_address: timelocked.clone(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages,
+            vec![migrate_strategy_msg(&timelocked, 2).unwrap()]
+        );
+        assert_eq!(
+            STRATEGIES
+                .load(deps.as_ref().storage, timelocked)
+                .unwrap()
+                .code_id,
+            2
+        );
+    }
+
+    #[test]
+    fn test_search_strategies_by_source_label_and_assets() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let pair = deps.api.addr_make("pair");
+
+        let swap = Node::Action {
+            action: Action::Swap(Swap {
+                swap_amount: Coin::new(1000_u128, "rune"),
+                minimum_receive_amount: Coin::new(1_u128, "btc-btc"),
+                maximum_slippage_bps: 100,
+                adjustment: SwapAmountAdjustment::Fixed,
+                routes: vec![SwapRoute::Fin(FinRoute {
+                    pair_address: pair.clone(),
+                })],
+                routing: SwapRouting::Best,
+            }),
+            index: 0,
+            next: None,
+        };
+
+        let distribute = |denom: &str| Node::Action {
+            action: Action::Distribute(Distribution {
+                denoms: vec![denom.to_string()],
+                destinations: vec![],
+            }),
+            index: 0,
+            next: None,
+        };
+
+        let strategies = [
+            ("dca-btc", Some("frontend"), "DCA BTC", 100, swap.clone()),
+            ("dca-eth", None, "DCA ETH", 200, distribute("eth-usdc")),
+            ("yield", Some("frontend"), "Yield", 300, distribute("rune")),
+        ]
+        .map(|(address, source, label, updated_at, node)| {
+            let strategy = Strategy {
+                id: 1,
+                source: source.map(|s| s.to_string()),
+                owner: deps.api.addr_make("owner"),
+                contract_address: deps.api.addr_make(address),
+                code_id: 1,
+                created_at: env.block.time.seconds(),
+                updated_at,
+                label: label.to_string(),
+                status: StrategyStatus::Active,
+            };
+
+            STRATEGIES
+                .save(
+                    deps.as_mut().storage,
+                    strategy.contract_address.clone(),
+                    &strategy,
+                )
+                .unwrap();
+
+            index_assets(deps.as_mut(), &strategy.contract_address, &[node]).unwrap();
+
+            strategy
+        });
+
+        let [dca_btc, dca_eth, yield_strategy] = strategies;
+
+        let sorted = |mut addresses: Vec<Addr>| {
+            addresses.sort();
+            addresses
+        };
+
+        let search = |deps: Deps, msg: ManagerQueryMsg| {
+            let mut addresses = from_json::<Vec<Strategy>>(query(deps, mock_env(), msg).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|strategy| strategy.contract_address)
+                .collect::<Vec<_>>();
+            addresses.sort();
+            addresses
+        };
+
+        assert_eq!(
+            from_json::<Vec<Strategy>>(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    ManagerQueryMsg::StrategiesBySource {
+                        source: "frontend".to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+            )
+            .unwrap(),
+            vec![yield_strategy.clone(), dca_btc.clone()]
+        );
+
+        assert_eq!(
+            from_json::<Vec<Strategy>>(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    ManagerQueryMsg::StrategiesByLabel {
+                        prefix: "DCA".to_string(),
+                        start_after: Some(dca_btc.contract_address.clone()),
+                        limit: None,
+                    },
+                )
+                .unwrap()
+            )
+            .unwrap(),
+            vec![dca_eth.clone()]
+        );
+
+        assert_eq!(
+            search(
+                deps.as_ref(),
+                ManagerQueryMsg::StrategiesByLabel {
+                    prefix: "DCA".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            ),
+            sorted(vec![
+                dca_btc.contract_address.clone(),
+                dca_eth.contract_address.clone()
+            ])
+        );
+
+        assert_eq!(
+            search(
+                deps.as_ref(),
+                ManagerQueryMsg::StrategiesByDenom {
+                    denom: "rune".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            ),
+            sorted(vec![
+                dca_btc.contract_address.clone(),
+                yield_strategy.contract_address.clone()
+            ])
+        );
+
+        assert_eq!(
+            search(
+                deps.as_ref(),
+                ManagerQueryMsg::StrategiesByPair {
+                    pair_address: pair.clone(),
+                    start_after: None,
+                    limit: None,
+                },
+            ),
+            vec![dca_btc.contract_address.clone()]
+        );
+
+        index_assets(deps.as_mut(), &dca_eth.contract_address, &[swap]).unwrap();
+
+        assert_eq!(
+            search(
+                deps.as_ref(),
+                ManagerQueryMsg::StrategiesByDenom {
+                    denom: "eth-usdc".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            ),
+            vec![]
+        );
+
+        assert_eq!(
+            search(
+                deps.as_ref(),
+                ManagerQueryMsg::StrategiesByPair {
+                    pair_address: pair,
+                    start_after: None,
+                    limit: None,
+                },
+            ),
+            sorted(vec![dca_btc.contract_address, dca_eth.contract_address])
+        );
+    }
+
+    #[test]
+    fn test_index_assets_resolves_missing_pair_denoms() {
+        let mut deps = mock_dependencies();
+        let pair = deps.api.addr_make("pair");
+        let contract_address = deps.api.addr_make("market-maker");
+
+        let market_maker = |pair_address: &Addr| Node::Action {
+            action: Action::MarketMake(FinMarketMaker {
+                pair_address: pair_address.clone(),
+                pair_denoms: vec![],
+                spread: Decimal::percent(1),
+                size: Amount::Fraction(Decimal::percent(10)),
+                target_base_ratio: Decimal::percent(50),
+                skew: Decimal::one(),
+                min_fill_ratio: None,
+                bid: None,
+                ask: None,
+                bid_stats: LimitOrderStats::default(),
+                ask_stats: LimitOrderStats::default(),
+            }),
+            index: 0,
+            next: None,
+        };
+
+        let queried_pair = pair.clone();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == queried_pair.as_str() => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Ok(ContractResult::Err("Unknown pair".to_string())),
+        });
+
+        // Nodes are indexed before the strategy fills in their pair denoms
+        index_assets(deps.as_mut(), &contract_address, &[market_maker(&pair)]).unwrap();
+
+        assert_eq!(
+            STRATEGY_ASSETS
+                .load(deps.as_ref().storage, contract_address.clone())
+                .unwrap(),
+            StrategyAssets {
+                denoms: vec!["rune".to_string(), "x/ruji".to_string()],
+                pairs: vec![pair.clone()],
+            }
+        );
+
+        assert!(DENOM_STRATEGIES.has(
+            deps.as_ref().storage,
+            ("x/ruji".to_string(), contract_address.clone())
+        ));
+
+        // Pairs that cannot be queried are still indexed by address
+        let unknown = deps.api.addr_make("unknown");
+
+        index_assets(deps.as_mut(), &contract_address, &[market_maker(&unknown)]).unwrap();
+
+        assert_eq!(
+            STRATEGY_ASSETS
+                .load(deps.as_ref().storage, contract_address.clone())
+                .unwrap(),
+            StrategyAssets {
+                denoms: vec![],
+                pairs: vec![unknown],
+            }
+        );
+
+        assert!(!DENOM_STRATEGIES.has(
+            deps.as_ref().storage,
+            ("x/ruji".to_string(), contract_address)
+        ));
+    }
+
+    #[test]
+    fn test_portfolio_aggregates_owner_strategies() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+
+        let strategies =
+            [("rune", 100), ("mixed", 200), ("other", 300)].map(|(address, updated_at)| {
+                let strategy = Strategy {
+                    id: 1,
+                    source: None,
+                    owner: if address == "other" {
+                        deps.api.addr_make("other")
+                    } else {
+                        owner.clone()
+                    },
+                    contract_address: deps.api.addr_make(address),
+                    code_id: 1,
+                    created_at: env.block.time.seconds(),
+                    updated_at,
+                    label: "Test Strategy".to_string(),
+                    status: StrategyStatus::Active,
+                };
+
+                STRATEGIES
+                    .save(
+                        deps.as_mut().storage,
+                        strategy.contract_address.clone(),
+                        &strategy,
+                    )
+                    .unwrap();
+
+                strategy
+            });
+
+        let [rune_strategy, mixed_strategy, _] = strategies;
+        let rune_address = rune_strategy.contract_address.to_string();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, .. } => {
+                let balances = if *contract_addr == rune_address {
+                    vec![Coin::new(1000_u128, "rune")]
+                } else {
+                    vec![Coin::new(500_u128, "rune"), Coin::new(10_u128, "btc-btc")]
+                };
+
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&balances).unwrap()))
+            }
+            _ => panic!("Unexpected query type"),
+        });
+
+        let portfolio = from_json::<Portfolio>(
+            query(
+                deps.as_ref(),
+                env,
+                ManagerQueryMsg::Portfolio {
+                    owner: owner.clone(),
+                    valuation: Some(Valuation {
+                        quote: "rune".to_string(),
+                        oracle: PriceSource::Thorchain,
+                    }),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            portfolio,
+            Portfolio {
+                owner,
+                strategies: vec![
+                    PortfolioStrategy {
+                        strategy: mixed_strategy,
+                        balances: vec![Coin::new(500_u128, "rune"), Coin::new(10_u128, "btc-btc")],
+                        value: None,
+                        error: None,
+                    },
+                    PortfolioStrategy {
+                        strategy: rune_strategy,
+                        balances: vec![Coin::new(1000_u128, "rune")],
+                        value: Some(Uint128::new(1000)),
+                        error: None,
+                    },
+                ],
+                balances: vec![Coin::new(10_u128, "btc-btc"), Coin::new(1500_u128, "rune")],
+                value: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_portfolio_reports_failing_strategies_and_pages_through_ties() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+
+        let strategies =
+            [("first", 200), ("broken", 200), ("last", 100)].map(|(address, updated_at)| {
+                let strategy = Strategy {
+                    id: 1,
+                    source: None,
+                    owner: owner.clone(),
+                    contract_address: deps.api.addr_make(address),
+                    code_id: 1,
+                    created_at: env.block.time.seconds(),
+                    updated_at,
+                    label: "Test Strategy".to_string(),
+                    status: StrategyStatus::Active,
+                };
+
+                STRATEGIES
+                    .save(
+                        deps.as_mut().storage,
+                        strategy.contract_address.clone(),
+                        &strategy,
+                    )
+                    .unwrap();
+
+                strategy
+            });
+
+        let broken_address = strategies[1].contract_address.to_string();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, .. } => {
+                if *contract_addr == broken_address {
+                    SystemResult::Ok(ContractResult::Err("Balances query failed".to_string()))
+                } else {
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&vec![Coin::new(100_u128, "rune")]).unwrap(),
+                    ))
+                }
+            }
+            _ => panic!("Unexpected query type"),
+        });
+
+        let portfolio_page = |start_after: Option<(u64, Addr)>| {
+            from_json::<Portfolio>(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    ManagerQueryMsg::Portfolio {
+                        owner: owner.clone(),
+                        valuation: None,
+                        start_after,
+                        limit: Some(1),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+        };
+
+        let mut start_after = None;
+        let mut portfolio_strategies = vec![];
+
+        loop {
+            let portfolio = portfolio_page(start_after);
+
+            let Some(last) = portfolio.strategies.last() else {
+                break;
+            };
+
+            start_after = Some((
+                last.strategy.updated_at,
+                last.strategy.contract_address.clone(),
+            ));
+
+            portfolio_strategies.extend(portfolio.strategies);
+        }
+
+        let mut addresses = portfolio_strategies
+            .iter()
+            .map(|s| s.strategy.contract_address.clone())
+            .collect::<Vec<_>>();
+
+        addresses.sort();
+
+        let mut expected = strategies
+            .iter()
+            .map(|s| s.contract_address.clone())
+            .collect::<Vec<_>>();
+
+        expected.sort();
+
+        assert_eq!(addresses, expected);
+
+        for portfolio_strategy in portfolio_strategies {
+            if portfolio_strategy.strategy == strategies[1] {
+                assert!(portfolio_strategy.balances.is_empty());
+                assert!(portfolio_strategy
+                    .error
+                    .unwrap()
+                    .contains("Balances query failed"));
+            } else {
+                assert_eq!(
+                    portfolio_strategy.balances,
+                    vec![Coin::new(100_u128, "rune")]
+                );
+                assert_eq!(portfolio_strategy.error, None);
+            }
+        }
+    }
 }
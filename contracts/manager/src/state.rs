@@ -1,15 +1,34 @@
-use calc_rs::manager::{ManagerConfig, Strategy};
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, UniqueIndex};
+use calc_rs::{
+    actions::{
+        action::Action,
+        limit_orders::{
+            fin_limit_order::FinLimitOrder, fin_limit_order_ladder::FinLimitOrderLadder,
+            fin_market_maker::FinMarketMaker,
+        },
+    },
+    manager::{AddressBook, ManagerConfig, PendingAddressBook, PendingUpdate, Strategy, Timelock},
+    rujira::get_pair_denoms,
+    strategy::Node,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Deps, DepsMut, StdResult};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, UniqueIndex};
 
 pub const CONFIG: Item<ManagerConfig> = Item::new("config");
 pub const STRATEGY_COUNTER: Item<u64> = Item::new("strategy_counter");
+pub const TIMELOCKS: Map<Addr, Timelock> = Map::new("timelocks");
+pub const PENDING_UPDATES: Map<Addr, PendingUpdate> = Map::new("pending_updates");
+pub const ADDRESS_BOOKS: Map<Addr, AddressBook> = Map::new("address_books");
+pub const PENDING_ADDRESS_BOOKS: Map<Addr, PendingAddressBook> = Map::new("pending_address_books");
+pub const PAUSED: Item<bool> = Item::new("paused");
 
 pub struct StrategyIndexes<'a> {
     pub updated_at: UniqueIndex<'a, String, Strategy, Addr>,
     pub owner_updated_at: UniqueIndex<'a, (Addr, String), Strategy, Addr>,
     pub status_updated_at: UniqueIndex<'a, (u8, String), Strategy, Addr>,
     pub owner_status_updated_at: UniqueIndex<'a, (Addr, u8, String), Strategy, Addr>,
+    pub source_updated_at: UniqueIndex<'a, (String, String), Strategy, Addr>,
+    pub label: UniqueIndex<'a, String, Strategy, Addr>,
 }
 
 impl<'a> IndexList<Strategy> for StrategyIndexes<'a> {
@@ -19,6 +38,8 @@ impl<'a> IndexList<Strategy> for StrategyIndexes<'a> {
             &self.owner_updated_at,
             &self.status_updated_at,
             &self.owner_status_updated_at,
+            &self.source_updated_at,
+            &self.label,
         ];
         Box::new(s.into_iter())
     }
@@ -31,6 +52,12 @@ pub fn updated_at_cursor(updated_at: u64, contract_address: Option<&Addr>) -> St
     }
 }
 
+// Labels are not unique, so the address breaks ties while keeping the label
+// at the start of the key for prefix searches
+pub fn label_cursor(label: &str, contract_address: &Addr) -> String {
+    format!("{label}\u{0}{contract_address}")
+}
+
 pub const STRATEGIES: IndexedMap<Addr, Strategy, StrategyIndexes<'static>> = IndexedMap::new(
     "strategies",
     StrategyIndexes {
@@ -66,5 +93,102 @@ pub const STRATEGIES: IndexedMap<Addr, Strategy, StrategyIndexes<'static>> = Ind
             },
             "strategies_owner_status_updated_at",
         ),
+        source_updated_at: UniqueIndex::new(
+            |s| {
+                (
+                    s.source.clone().unwrap_or_default(),
+                    updated_at_cursor(s.updated_at, Some(&s.contract_address)),
+                )
+            },
+            "strategies_source_updated_at",
+        ),
+        label: UniqueIndex::new(
+            |s| label_cursor(&s.label, &s.contract_address),
+            "strategies_label",
+        ),
     },
 );
+
+#[cw_serde]
+#[derive(Default)]
+pub struct StrategyAssets {
+    pub denoms: Vec<String>,
+    pub pairs: Vec<Addr>,
+}
+
+pub const STRATEGY_ASSETS: Map<Addr, StrategyAssets> = Map::new("strategy_assets");
+pub const DENOM_STRATEGIES: Map<(String, Addr), ()> = Map::new("denom_strategies");
+pub const PAIR_STRATEGIES: Map<(Addr, Addr), ()> = Map::new("pair_strategies");
+
+// Nodes are indexed as submitted, before the strategy resolves the denoms of
+// their Fin pairs on init (and version 0 strategies never stored them), so any
+// missing pair denoms are read from the pair config. Pairs that cannot be
+// queried are left for the strategy to reject on init.
+fn missing_pair_denoms(deps: Deps, node: &Node) -> Vec<String> {
+    match node {
+        Node::Action {
+            action:
+                Action::LimitOrder(FinLimitOrder {
+                    pair_address,
+                    pair_denoms,
+                    ..
+                })
+                | Action::LimitOrderLadder(FinLimitOrderLadder {
+                    pair_address,
+                    pair_denoms,
+                    ..
+                })
+                | Action::MarketMake(FinMarketMaker {
+                    pair_address,
+                    pair_denoms,
+                    ..
+                }),
+            ..
+        } if pair_denoms.is_empty() => get_pair_denoms(deps, pair_address, &[]).unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+// Replaces the denoms and Fin pairs a strategy is indexed under with those its nodes use
+pub fn index_assets(deps: DepsMut, contract_address: &Addr, nodes: &[Node]) -> StdResult<()> {
+    let mut denoms = vec![];
+
+    for node in nodes {
+        denoms.extend(node.denoms());
+        denoms.extend(missing_pair_denoms(deps.as_ref(), node));
+    }
+
+    let storage = deps.storage;
+
+    let existing = STRATEGY_ASSETS
+        .may_load(storage, contract_address.clone())?
+        .unwrap_or_default();
+
+    for denom in existing.denoms {
+        DENOM_STRATEGIES.remove(storage, (denom, contract_address.clone()));
+    }
+
+    for pair in existing.pairs {
+        PAIR_STRATEGIES.remove(storage, (pair, contract_address.clone()));
+    }
+
+    let mut assets = StrategyAssets {
+        denoms,
+        pairs: nodes.iter().flat_map(|node| node.pairs()).collect(),
+    };
+
+    assets.denoms.sort();
+    assets.denoms.dedup();
+    assets.pairs.sort();
+    assets.pairs.dedup();
+
+    for denom in assets.denoms.iter() {
+        DENOM_STRATEGIES.save(storage, (denom.clone(), contract_address.clone()), &())?;
+    }
+
+    for pair in assets.pairs.iter() {
+        PAIR_STRATEGIES.save(storage, (pair.clone(), contract_address.clone()), &())?;
+    }
+
+    STRATEGY_ASSETS.save(storage, contract_address.clone(), &assets)
+}
@@ -3,25 +3,28 @@ use std::vec;
 use calc_rs::{
     conditions::condition::Condition,
     core::{Contract, ContractError, ContractResult},
+    manager::ManagerQueryMsg,
     scheduler::{SchedulerExecuteMsg, SchedulerInstantiateMsg, SchedulerQueryMsg, Trigger},
+    version::{migrate_state, set_contract_version},
 };
 use cosmwasm_schema::cw_serde;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, BankMsg, Binary, Coins, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult, SubMsg, SubMsgResult,
+    to_json_binary, Addr, BankMsg, Binary, Coins, Deps, DepsMut, Env, Event, MessageInfo, Reply,
+    Response, StdResult, SubMsg, SubMsgResult,
 };
 
-use crate::state::TRIGGERS;
+use crate::{migrations::MIGRATIONS, state::TRIGGERS};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
     _msg: SchedulerInstantiateMsg,
 ) -> ContractResult {
+    set_contract_version(deps.storage, env!("CARGO_PKG_NAME"), MIGRATIONS)?;
     Ok(Response::new())
 }
 
@@ -29,8 +32,14 @@ pub fn instantiate(
 pub struct MigrateMsg {}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
-    Ok(Response::new())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
+    let (from, to) = migrate_state(deps.storage, env!("CARGO_PKG_NAME"), MIGRATIONS)?;
+
+    Ok(Response::new().add_event(
+        Event::new(format!("{}/migrate", env!("CARGO_PKG_NAME")))
+            .add_attribute("from_version", from.to_string())
+            .add_attribute("to_version", to.to_string()),
+    ))
 }
 
 const MAX_EXECUTORS: usize = 10;
@@ -114,6 +123,11 @@ pub fn execute(
                     _ => continue,
                 }
 
+                // Triggers stay in place until the manager is resumed
+                if is_paused(deps.as_ref(), &trigger.contract_address) {
+                    continue;
+                }
+
                 TRIGGERS.delete(deps.storage, trigger.id.into())?;
 
                 let execute_trigger_msg = SubMsg::reply_on_error(
@@ -161,6 +175,13 @@ pub fn reply(_deps: DepsMut, _env: Env, reply: Reply) -> ContractResult {
     }
 }
 
+fn is_paused(deps: Deps, contract_address: &Addr) -> bool {
+    // Contracts that are not a manager cannot answer the query and are never paused
+    deps.querier
+        .query_wasm_smart::<bool>(contract_address, &ManagerQueryMsg::Paused {})
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod create_trigger_tests {
     use super::*;
@@ -375,7 +396,7 @@ mod execute_trigger_tests {
     use cosmwasm_std::testing::message_info;
     use cosmwasm_std::{
         testing::{mock_dependencies, mock_env},
-        Coin, SubMsg,
+        Coin, ContractResult, SubMsg, SystemResult,
     };
     use cosmwasm_std::{Uint64, WasmMsg};
 
@@ -545,6 +566,50 @@ mod execute_trigger_tests {
             })));
     }
 
+    #[test]
+    fn keeps_trigger_and_rebate_while_manager_is_paused() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let manager = deps.api.addr_make("manager");
+        let owner = deps.api.addr_make("creator");
+        let executor = deps.api.addr_make("executor");
+
+        deps.querier
+            .update_wasm(|_| SystemResult::Ok(ContractResult::Ok(to_json_binary(&true).unwrap())));
+
+        let create_trigger_msg = CreateTriggerMsg {
+            condition: Condition::BlocksCompleted(env.block.height - 10),
+            contract_address: manager.clone(),
+            msg: to_json_binary(&ManagerExecuteMsg::Execute {
+                contract_address: owner.clone(),
+            })
+            .unwrap(),
+            executors: vec![],
+            jitter: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[Coin::new(235463u128, "rune")]),
+            SchedulerExecuteMsg::Create(Box::new(create_trigger_msg.clone())),
+        )
+        .unwrap();
+
+        let trigger_id = create_trigger_msg.id(&owner).unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&executor, &[]),
+            SchedulerExecuteMsg::Execute(vec![trigger_id]),
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+        assert!(TRIGGERS.load(deps.as_ref().storage, trigger_id).is_ok());
+    }
+
     #[test]
     fn deletes_trigger_if_trigger_can_execute() {
         let mut deps = mock_dependencies();
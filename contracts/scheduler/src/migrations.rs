@@ -0,0 +1,150 @@
+use calc_rs::{v0, version::Migration};
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::Map;
+
+use crate::state::TRIGGERS;
+
+pub const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+// Version 0 schedulers were deployed before version tracking. Triggers are read
+// with the frozen version 0 types and saved with the current ones.
+fn migrate_v0_to_v1(storage: &mut dyn Storage) -> StdResult<()> {
+    const V0_TRIGGERS: Map<u64, v0::Trigger> = Map::new("triggers_v1");
+
+    let triggers = V0_TRIGGERS
+        .range(storage, None, None, Order::Ascending)
+        .map(|result| result.map(|(_, trigger)| trigger))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for trigger in triggers {
+        // Removing the stored trigger first keeps the index update from reading
+        // it with the current type. Both versions index the same conditions, so
+        // the existing index entries are rewritten unchanged.
+        V0_TRIGGERS.remove(storage, trigger.id.u64());
+        TRIGGERS.save(storage, &trigger.migrate()?)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use calc_rs::{
+        conditions::condition::Condition,
+        scheduler::{ConditionFilter, Trigger},
+        version::{migrate_state, ContractVersion, CONTRACT_VERSION},
+    };
+    use cosmwasm_std::{
+        testing::MockStorage, Addr, Binary, Coin, Decimal, Storage, Timestamp, Uint64,
+    };
+    use cw_storage_plus::Map;
+    use rujira_rs::fin::Side;
+
+    use crate::{migrations::MIGRATIONS, state::TRIGGERS};
+
+    // Triggers as serialized by schedulers deployed before version tracking
+    const V0_TRIGGERS: [&str; 3] = [
+        r#"{"id":"1","owner":"owner","condition":{"blocks_completed":100},"msg":"e30=","contract_address":"manager","executors":[],"execution_rebate":[{"denom":"rune","amount":"1000"}],"jitter":null}"#,
+        r#"{"id":"2","owner":"owner","condition":{"timestamp_elapsed":"1700000000000000000"},"msg":"e30=","contract_address":"manager","executors":[],"execution_rebate":[],"jitter":null}"#,
+        r#"{"id":"3","owner":"owner","condition":{"fin_limit_order_filled":{"owner":null,"pair_address":"pair","side":"base","price":"1.5"}},"msg":"e30=","contract_address":"manager","executors":["executor"],"execution_rebate":[],"jitter":{"secs":60,"nanos":0}}"#,
+    ];
+
+    fn trigger(id: u64, condition: Condition) -> Trigger {
+        Trigger {
+            id: Uint64::new(id),
+            owner: Addr::unchecked("owner"),
+            condition,
+            msg: Binary::from(b"{}".as_slice()),
+            contract_address: Addr::unchecked("manager"),
+            executors: vec![],
+            execution_rebate: vec![],
+            jitter: None,
+        }
+    }
+
+    #[test]
+    fn migrates_v0_triggers() {
+        let mut storage = MockStorage::default();
+
+        let triggers = vec![
+            Trigger {
+                execution_rebate: vec![Coin::new(1000_u128, "rune")],
+                ..trigger(1, Condition::BlocksCompleted(100))
+            },
+            trigger(
+                2,
+                Condition::TimestampElapsed(Timestamp::from_seconds(1_700_000_000)),
+            ),
+            Trigger {
+                executors: vec![Addr::unchecked("executor")],
+                jitter: Some(std::time::Duration::from_secs(60)),
+                ..trigger(
+                    3,
+                    Condition::FinLimitOrderFilled {
+                        owner: None,
+                        pair_address: Addr::unchecked("pair"),
+                        side: Side::Base,
+                        price: Decimal::percent(150),
+                        oracle_offset: None,
+                    },
+                )
+            },
+        ];
+
+        // Version 0 indexed triggers under the same keys, so its index entries
+        // are written with the current store before the stored values are
+        // replaced with their version 0 layout
+        for (trigger, v0_trigger) in triggers.iter().zip(V0_TRIGGERS) {
+            TRIGGERS.save(&mut storage, trigger).unwrap();
+            storage.set(
+                &Map::<u64, Trigger>::new("triggers_v1").key(trigger.id.u64()),
+                v0_trigger.as_bytes(),
+            );
+        }
+
+        assert_eq!(
+            migrate_state(&mut storage, "calc-scheduler", MIGRATIONS).unwrap(),
+            (0, 1)
+        );
+
+        for trigger in triggers.iter() {
+            assert_eq!(&TRIGGERS.load(&storage, trigger.id).unwrap(), trigger);
+        }
+
+        assert_eq!(
+            TRIGGERS
+                .filtered(
+                    &storage,
+                    ConditionFilter::BlockHeight {
+                        start: None,
+                        end: Some(100),
+                    },
+                    None,
+                )
+                .unwrap(),
+            vec![triggers[0].clone()]
+        );
+
+        assert_eq!(
+            TRIGGERS
+                .filtered(
+                    &storage,
+                    ConditionFilter::Timestamp {
+                        start: None,
+                        end: Some(Timestamp::from_seconds(1_700_000_000)),
+                    },
+                    None,
+                )
+                .unwrap(),
+            vec![triggers[1].clone()]
+        );
+
+        assert_eq!(
+            CONTRACT_VERSION.load(&storage).unwrap(),
+            ContractVersion {
+                contract: "calc-scheduler".to_string(),
+                version: 1,
+            }
+        );
+    }
+}
@@ -1,26 +1,41 @@
 use std::cmp::min;
 
 use calc_rs::{
+    actions::action::Action,
+    conditions::asset_value_ratio::PriceSource,
     core::{Contract, ContractError, ContractResult},
+    manager::Valuation,
     operation::{Operation, StatefulOperation},
+    oracle::{value_in, value_priceable_in},
+    performance::HighWaterMark,
     strategy::{
-        StrategyConfig, StrategyExecuteMsg, StrategyInstantiateMsg, StrategyOperation,
-        StrategyQueryMsg,
+        LedgerEvent, Node, Performance, PerformanceTotals, StrategyConfig, StrategyExecuteMsg,
+        StrategyInstantiateMsg, StrategyOperation, StrategyQueryMsg, UpdatePreview,
     },
+    version::{migrate_state, set_contract_version},
 };
 use cosmwasm_schema::cw_serde;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_json_binary, to_json_string, BankMsg, Binary, Coin, Coins, Decimal, Deps, DepsMut, Env,
-    Event, MessageInfo, Reply, Response, StdResult, SubMsg, SubMsgResult,
+    Event, MessageInfo, Order, Reply, Response, StdError, StdResult, SubMsg, SubMsgResult, Uint128,
+};
+use cw_storage_plus::Bound;
+
+use crate::{
+    migrations::MIGRATIONS,
+    state::{
+        check_outflows, fold_unvalued_outflows, load_address_book, record_ledger_entry,
+        record_outflows, Path, AFFILIATES, CHECKPOINT_INTERVAL, DEPOSITS, DISABLED_NODES,
+        HIGH_WATER_MARK, LEDGER, MANAGER, NODES, OWNER, PATH, PERFORMANCE, RESTRICT_OUTFLOWS,
+        THORCHAIN_AFFILIATE, VALUATION, WITHDRAWALS,
+    },
 };
-
-use crate::state::{AFFILIATES, DEPOSITS, MANAGER, NODES, OWNER, PATH, WITHDRAWALS};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: StrategyInstantiateMsg,
@@ -32,12 +47,54 @@ pub fn instantiate(
         )));
     }
 
+    if let Some(checkpoint_interval) = msg.checkpoint_interval {
+        if checkpoint_interval == 0 {
+            return Err(ContractError::generic_err(
+                "Checkpoint interval must be greater than zero",
+            ));
+        }
+
+        CHECKPOINT_INTERVAL.save(deps.storage, &checkpoint_interval)?;
+    }
+
+    set_contract_version(deps.storage, env!("CARGO_PKG_NAME"), MIGRATIONS)?;
+
     MANAGER.save(deps.storage, &info.sender)?;
     OWNER.save(deps.storage, &msg.owner)?;
+    RESTRICT_OUTFLOWS.save(deps.storage, &msg.restrict_outflows)?;
+    THORCHAIN_AFFILIATE.save(deps.storage, &msg.thorchain_affiliate)?;
     AFFILIATES.save(deps.storage, &msg.affiliates)?;
     DEPOSITS.save(deps.storage, &vec![])?;
     WITHDRAWALS.save(deps.storage, &vec![])?;
 
+    // Ledger entries are valued in the fee denom when no valuation is given
+    let valuation = msg
+        .valuation
+        .or(msg.performance_fee.as_ref().map(|fee| Valuation {
+            quote: fee.denom.clone(),
+            oracle: PriceSource::Thorchain,
+        }));
+
+    if let Some(valuation) = valuation {
+        VALUATION.save(deps.storage, &valuation)?;
+    }
+
+    if let Some(performance_fee) = msg.performance_fee {
+        let mut high_water_mark = HighWaterMark::new(performance_fee);
+        high_water_mark.deposit(value_deposit(deps.as_ref(), &high_water_mark, &info.funds)?);
+        HIGH_WATER_MARK.save(deps.storage, &high_water_mark)?;
+    }
+
+    if !info.funds.is_empty() {
+        record_ledger_entry(
+            deps.branch(),
+            &env,
+            LedgerEvent::Deposit,
+            info.funds.clone(),
+            |_| Ok(Coins::default()),
+        )?;
+    }
+
     let init_msg = Contract(env.contract.address.clone()).call(
         to_json_binary(&StrategyExecuteMsg::Init(msg.nodes))?,
         vec![],
@@ -50,13 +107,19 @@ pub fn instantiate(
 pub struct MigrateMsg {}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
-    Ok(Response::new())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> ContractResult {
+    let (from, to) = migrate_state(deps.storage, env!("CARGO_PKG_NAME"), MIGRATIONS)?;
+
+    Ok(Response::new().add_event(
+        Event::new(format!("{}/migrate", env!("CARGO_PKG_NAME")))
+            .add_attribute("from_version", from.to_string())
+            .add_attribute("to_version", to.to_string()),
+    ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: StrategyExecuteMsg,
@@ -71,6 +134,36 @@ pub fn execute(
 
             Ok(deposits.to_vec())
         })?;
+
+        if let Some(mut high_water_mark) = HIGH_WATER_MARK.may_load(deps.storage)? {
+            high_water_mark.deposit(value_deposit(deps.as_ref(), &high_water_mark, &info.funds)?);
+            HIGH_WATER_MARK.save(deps.storage, &high_water_mark)?;
+        }
+
+        // Funds are already in the bank balance by the time we execute
+        let funds = Coins::try_from(info.funds.clone())?;
+
+        record_ledger_entry(
+            deps.branch(),
+            &env,
+            LedgerEvent::Deposit,
+            info.funds.clone(),
+            |deps| {
+                let mut balances = Coins::default();
+
+                for balance in strategy_balances(deps, &env)? {
+                    let amount = balance
+                        .amount
+                        .saturating_sub(funds.amount_of(&balance.denom));
+
+                    if !amount.is_zero() {
+                        balances.add(Coin::new(amount, balance.denom))?;
+                    }
+                }
+
+                Ok(balances)
+            },
+        )?;
     };
 
     match msg {
@@ -79,6 +172,8 @@ pub fn execute(
                 return Err(ContractError::Unauthorized {});
             }
 
+            PATH.remove(deps.storage);
+            DISABLED_NODES.clear(deps.storage);
             NODES.init(deps, &env, nodes)?;
 
             let execute_actions_msg = Contract(env.contract.address.clone()).call(
@@ -110,6 +205,18 @@ pub fn execute(
                 .add_event(Event::new(format!("{}/execute", env!("CARGO_PKG_NAME"))))
                 .add_message(execute_actions_msg))
         }
+        StrategyExecuteMsg::Deposit {} => {
+            if info.funds.is_empty() {
+                return Err(ContractError::generic_err("No funds sent with deposit"));
+            }
+
+            // Funds were recorded as a deposit above, without triggering an execution
+            Ok(Response::new().add_event(
+                Event::new(format!("{}/deposit", env!("CARGO_PKG_NAME")))
+                    .add_attribute("sender", info.sender)
+                    .add_attribute("amount", Coins::try_from(info.funds)?.to_string()),
+            ))
+        }
         StrategyExecuteMsg::Update(nodes) => {
             if info.sender != MANAGER.load(deps.storage)? {
                 return Err(ContractError::Unauthorized {});
@@ -161,23 +268,78 @@ pub fn execute(
                 return Ok(Response::new());
             }
 
+            let balances = strategy_balances(deps.as_ref(), &env)?;
             let affiliates = AFFILIATES.load(deps.storage)?;
 
             let mut affiliate_amounts = Vec::with_capacity(affiliates.len());
+            let mut uncharged_denoms = vec![];
+
+            if let Some(mut high_water_mark) = HIGH_WATER_MARK.may_load(deps.storage)? {
+                let denom = high_water_mark.fee.denom.clone();
+
+                // Deposits must be priced in full, so denoms without a price can
+                // only be held through trading. They count towards neither the
+                // strategy's value nor the gains above the mark, so withdrawals
+                // of them are not charged either.
+                let nav = value_priceable_in(
+                    deps.as_ref(),
+                    &balances.to_vec(),
+                    &denom,
+                    &PriceSource::Thorchain,
+                );
+
+                let mut withdrawn = Uint128::zero();
+
+                for coin in withdrawals.iter() {
+                    match value_in(
+                        deps.as_ref(),
+                        std::slice::from_ref(coin),
+                        &denom,
+                        &PriceSource::Thorchain,
+                    ) {
+                        Ok(value) => withdrawn += value,
+                        Err(_) => uncharged_denoms.push(coin.denom.clone()),
+                    }
+                }
 
-            for affiliate in &affiliates {
-                affiliate_amounts.push((affiliate, Coins::default()));
+                let fee_rate = high_water_mark.withdraw(nav, withdrawn);
+                HIGH_WATER_MARK.save(deps.storage, &high_water_mark)?;
+
+                // Performance fees replace flow fees, split pro-rata by affiliate bps
+                let total_bps = affiliates
+                    .iter()
+                    .map(|affiliate| affiliate.bps)
+                    .sum::<u64>();
+
+                for affiliate in &affiliates {
+                    affiliate_amounts.push((
+                        affiliate,
+                        fee_rate * Decimal::from_ratio(affiliate.bps, total_bps.max(1)),
+                        Coins::default(),
+                    ));
+                }
+            } else {
+                for affiliate in &affiliates {
+                    affiliate_amounts.push((
+                        affiliate,
+                        Decimal::from_ratio(affiliate.bps, 10_000_u128),
+                        Coins::default(),
+                    ));
+                }
             }
 
             let mut final_withdrawals = Coins::default();
 
             for amount in withdrawals {
+                if uncharged_denoms.contains(&amount.denom) {
+                    final_withdrawals.add(amount)?;
+                    continue;
+                }
+
                 let mut working_amount = amount.amount;
 
-                for (affiliate, ref mut amounts) in affiliate_amounts.iter_mut() {
-                    let fee = amount
-                        .amount
-                        .mul_floor(Decimal::from_ratio(affiliate.bps, 10_000_u128));
+                for (_, fee_rate, ref mut amounts) in affiliate_amounts.iter_mut() {
+                    let fee = amount.amount.mul_floor(*fee_rate);
 
                     amounts.add(Coin::new(fee, amount.denom.clone()))?;
                     working_amount = working_amount.saturating_sub(fee);
@@ -186,6 +348,14 @@ pub fn execute(
                 final_withdrawals.add(Coin::new(working_amount, amount.denom))?;
             }
 
+            let mut fees = Coins::default();
+
+            for (_, _, amounts) in affiliate_amounts.iter() {
+                for fee in amounts.to_vec() {
+                    fees.add(fee)?;
+                }
+            }
+
             let withdrawal_msg = BankMsg::Send {
                 to_address: OWNER.load(deps.storage)?.to_string(),
                 amount: final_withdrawals.to_vec(),
@@ -193,7 +363,7 @@ pub fn execute(
 
             let fee_msgs = affiliate_amounts
                 .into_iter()
-                .filter_map(|(affiliate, amounts)| {
+                .filter_map(|(affiliate, _, amounts)| {
                     if amounts.is_empty() {
                         None
                     } else {
@@ -205,6 +375,30 @@ pub fn execute(
                 })
                 .collect::<Vec<_>>();
 
+            let mut remaining = balances.clone();
+
+            for withdrawal in final_withdrawals.to_vec() {
+                remaining.sub(withdrawal)?;
+            }
+
+            record_ledger_entry(
+                deps.branch(),
+                &env,
+                LedgerEvent::Withdrawal,
+                final_withdrawals.to_vec(),
+                |_| Ok(balances),
+            )?;
+
+            if !fees.is_empty() {
+                record_ledger_entry(
+                    deps.branch(),
+                    &env,
+                    LedgerEvent::Fee,
+                    fees.into_vec(),
+                    |_| Ok(remaining),
+                )?;
+            }
+
             WITHDRAWALS.update(deps.storage, |existing| -> StdResult<_> {
                 for withdrawal in existing {
                     final_withdrawals.add(withdrawal)?;
@@ -234,6 +428,54 @@ pub fn execute(
                 .add_event(Event::new(format!("{}/cancel", env!("CARGO_PKG_NAME"))))
                 .add_message(cancel_actions_msg))
         }
+        StrategyExecuteMsg::SetDisabledNodes(disabled_nodes) => {
+            if info.sender != MANAGER.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            DISABLED_NODES.clear(deps.storage);
+
+            for disabled_node in disabled_nodes.iter() {
+                let node = NODES.load(deps.storage, disabled_node.index).map_err(|_| {
+                    ContractError::generic_err(format!(
+                        "Node index {} does not exist",
+                        disabled_node.index
+                    ))
+                })?;
+
+                match node {
+                    Node::Action { .. } => {
+                        if disabled_node.branch.is_some() {
+                            return Err(ContractError::generic_err(format!(
+                                "Disabled action node {} cannot specify a branch",
+                                disabled_node.index
+                            )));
+                        }
+                    }
+                    Node::Condition { .. } => {
+                        if disabled_node.branch.is_none() {
+                            return Err(ContractError::generic_err(format!(
+                                "Disabled condition node {} must specify a default branch",
+                                disabled_node.index
+                            )));
+                        }
+                    }
+                }
+
+                DISABLED_NODES.save(deps.storage, disabled_node.index, disabled_node)?;
+            }
+
+            Ok(Response::new().add_event(
+                Event::new(format!("{}/set-disabled-nodes", env!("CARGO_PKG_NAME"))).add_attribute(
+                    "nodes",
+                    disabled_nodes
+                        .iter()
+                        .map(|node| node.index.to_string())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+            ))
+        }
         StrategyExecuteMsg::Process {
             operation,
             previous,
@@ -253,21 +495,68 @@ pub fn execute(
 
                 next_node
             } else {
-                NODES.load(deps.storage, 0).ok()
+                let checkpoint = match operation {
+                    StrategyOperation::Execute => PATH
+                        .may_load(deps.storage)?
+                        .and_then(|path| path.checkpoint),
+                    StrategyOperation::Cancel => None,
+                };
+
+                NODES.load(deps.storage, checkpoint.unwrap_or(0)).ok()
             };
 
             let mut path = if previous.is_some() {
                 PATH.load(deps.storage)?
             } else {
-                vec![]
+                Path::default()
+            };
+
+            if let (StrategyOperation::Execute, Some(checkpoint_interval), Some(node)) = (
+                &operation,
+                CHECKPOINT_INTERVAL.may_load(deps.storage)?,
+                &next_node,
+            ) {
+                if path.emitted >= checkpoint_interval {
+                    path.checkpoint = Some(node.index());
+                    PATH.save(deps.storage, &path)?;
+
+                    return Ok(Response::new().add_event(
+                        Event::new(format!("{}/process.checkpoint", env!("CARGO_PKG_NAME")))
+                            .add_attribute("operation", operation.as_str())
+                            .add_attribute("path", path.nodes.join(","))
+                            .add_attribute("checkpoint", node.index().to_string()),
+                    ));
+                }
+            }
+
+            let address_book = match operation {
+                StrategyOperation::Execute => load_address_book(deps.as_ref(), &env)?,
+                StrategyOperation::Cancel => None,
             };
 
             while let Some(current_node) = next_node {
                 let index = current_node.index();
-                path.push(index.to_string());
+
+                if operation == StrategyOperation::Execute {
+                    if let Some(disabled_node) = DISABLED_NODES.may_load(deps.storage, index)? {
+                        next_node = current_node
+                            .disabled_next_index(disabled_node.branch.as_ref())
+                            .map(|next| NODES.load(deps.storage, next))
+                            .transpose()?;
+
+                        continue;
+                    }
+                }
+
+                path.nodes.push(index.to_string());
 
                 let result = match operation {
-                    StrategyOperation::Execute => current_node.clone().execute(deps.as_ref(), &env),
+                    StrategyOperation::Execute => address_book
+                        .as_ref()
+                        .map_or(Ok(()), |address_book| {
+                            check_outflows(address_book, &current_node)
+                        })
+                        .and_then(|_| current_node.clone().execute(deps.as_ref(), &env)),
                     StrategyOperation::Cancel => current_node.clone().cancel(deps.as_ref(), &env),
                 };
 
@@ -275,7 +564,27 @@ pub fn execute(
                     Ok((messages, node)) => {
                         NODES.save(deps.storage, &node)?;
 
+                        if let (
+                            Node::Action {
+                                action: Action::Distribute(previous_distribution),
+                                ..
+                            },
+                            Node::Action {
+                                action: Action::Distribute(distribution),
+                                ..
+                            },
+                        ) = (&current_node, &node)
+                        {
+                            let outflows = distribution.distributed_since(
+                                previous_distribution,
+                                &AFFILIATES.load(deps.storage)?,
+                            )?;
+
+                            record_outflows(deps.storage, &env, outflows)?;
+                        }
+
                         if !messages.is_empty() {
+                            path.emitted += 1;
                             PATH.save(deps.storage, &path)?;
 
                             return Ok(Response::new()
@@ -305,6 +614,13 @@ pub fn execute(
                         }
 
                         next_node = NODES.get_next(deps.as_ref(), &env, &operation, &node).ok();
+
+                        // Message-less conditions have no continuation to commit them in
+                        if operation == StrategyOperation::Execute
+                            && matches!(node, Node::Condition { .. })
+                        {
+                            NODES.save(deps.storage, &node.commit(deps.as_ref(), &env)?)?;
+                        }
                     }
                     Err(err) => {
                         PATH.save(deps.storage, &path)?;
@@ -339,12 +655,26 @@ pub fn execute(
                 }
             }
 
+            PATH.save(deps.storage, &path)?;
+
             Ok(Response::new().add_event(
                 Event::new(format!("{}/process", env!("CARGO_PKG_NAME")))
                     .add_attribute("operation", operation.as_str())
-                    .add_attribute("path", path.join(",")),
+                    .add_attribute("path", path.nodes.join(",")),
             ))
         }
+        StrategyExecuteMsg::SwapFinHops(swap) => {
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            Ok(Response::new()
+                .add_event(Event::new(format!(
+                    "{}/swap-fin-hops",
+                    env!("CARGO_PKG_NAME")
+                )))
+                .add_messages(swap.execute(deps.as_ref(), &env)?))
+        }
     }
 }
 
@@ -374,26 +704,154 @@ pub fn query(deps: Deps, env: Env, msg: StrategyQueryMsg) -> StdResult<Binary> {
             nodes: NODES.all(deps.storage)?,
             withdrawals: WITHDRAWALS.load(deps.storage)?,
         }),
-        StrategyQueryMsg::Balances {} => {
-            let mut balances = NODES.all(deps.storage)?.iter().try_fold(
-                Coins::default(),
-                |mut acc, node| -> StdResult<Coins> {
-                    let node_balances = node.balances(deps, &env)?;
-                    for balance in node_balances {
-                        acc.add(balance)?;
-                    }
-                    Ok(acc)
-                },
-            )?;
+        StrategyQueryMsg::Balances {} => to_json_binary(&strategy_balances(deps, &env)?.to_vec()),
+        StrategyQueryMsg::Checkpoint {} => to_json_binary(
+            &PATH
+                .may_load(deps.storage)?
+                .and_then(|path| path.checkpoint),
+        ),
+        StrategyQueryMsg::DisabledNodes {} => to_json_binary(
+            &DISABLED_NODES
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|result| result.map(|(_, disabled_node)| disabled_node))
+                .collect::<StdResult<Vec<_>>>()?,
+        ),
+        StrategyQueryMsg::UpdatePreview { nodes } => to_json_binary(&UpdatePreview::new(
+            deps,
+            &env,
+            &NODES.all(deps.storage)?,
+            &nodes,
+        )?),
+        StrategyQueryMsg::HighWaterMark {} => {
+            to_json_binary(&HIGH_WATER_MARK.may_load(deps.storage)?)
+        }
+        StrategyQueryMsg::Performance { quote, oracle } => {
+            let stored_valuation = VALUATION.may_load(deps.storage)?;
+
+            let quote = quote
+                .or(stored_valuation
+                    .as_ref()
+                    .map(|valuation| valuation.quote.clone()))
+                .ok_or_else(|| {
+                    StdError::generic_err(
+                        "Strategy has no valuation, a quote is needed to measure performance in",
+                    )
+                })?;
+
+            let oracle = oracle.unwrap_or_else(|| match &stored_valuation {
+                Some(valuation) if valuation.quote == quote => valuation.oracle.clone(),
+                _ => PriceSource::Thorchain,
+            });
+
+            let valuation = Valuation { quote, oracle };
+
+            let nav = value_priceable_in(
+                deps,
+                &strategy_balances(deps, &env)?.into_vec(),
+                &valuation.quote,
+                &valuation.oracle,
+            );
 
-            #[allow(deprecated)]
-            for balance in deps.querier.query_all_balances(&env.contract.address)? {
-                balances.add(balance)?;
-            }
+            let totals = if stored_valuation.as_ref() == Some(&valuation) {
+                let mut totals = PERFORMANCE.may_load(deps.storage)?.unwrap_or_default();
+                fold_unvalued_outflows(deps, &valuation, &mut totals, nav)?;
+                totals
+            } else {
+                held_performance_totals(deps, &valuation, nav)?
+            };
 
-            to_json_binary(&balances.to_vec())
+            to_json_binary(&Performance::new(valuation.quote, &totals, nav)?)
         }
+        StrategyQueryMsg::Ledger { start_after, limit } => to_json_binary(
+            &LEDGER
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit.unwrap_or(30) as usize)
+                .map(|result| result.map(|(_, entry)| entry))
+                .collect::<StdResult<Vec<_>>>()?,
+        ),
+        StrategyQueryMsg::LimitOrderStats {} => to_json_binary(
+            &NODES
+                .all(deps.storage)?
+                .iter()
+                .flat_map(Node::limit_order_stats)
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
+// Running totals are only kept in the strategy's own valuation, so any other
+// valuation (or a strategy without one) measures the lifetime deposits and
+// withdrawals at current prices, as though everything withdrawn left just now
+fn held_performance_totals(
+    deps: Deps,
+    valuation: &Valuation,
+    nav: Uint128,
+) -> StdResult<PerformanceTotals> {
+    let deposited = value_priceable_in(
+        deps,
+        &DEPOSITS.may_load(deps.storage)?.unwrap_or_default(),
+        &valuation.quote,
+        &valuation.oracle,
+    );
+
+    let withdrawn = value_priceable_in(
+        deps,
+        &WITHDRAWALS.may_load(deps.storage)?.unwrap_or_default(),
+        &valuation.quote,
+        &valuation.oracle,
+    );
+
+    let mut totals = PerformanceTotals::default();
+    totals.record(&LedgerEvent::Deposit, deposited, Uint128::zero())?;
+    totals.record(&LedgerEvent::Withdrawal, withdrawn, nav + withdrawn)?;
+
+    Ok(totals)
+}
+
+// Deposits must be valued in full, otherwise any principal the oracle cannot
+// price would be charged the performance fee as gains once swapped into a
+// denom it can price
+fn value_deposit(
+    deps: Deps,
+    high_water_mark: &HighWaterMark,
+    funds: &[Coin],
+) -> StdResult<Uint128> {
+    value_in(
+        deps,
+        funds,
+        &high_water_mark.fee.denom,
+        &PriceSource::Thorchain,
+    )
+    .map_err(|e| {
+        StdError::generic_err(format!(
+            "Failed to value deposit for the performance fee, error: {e}"
+        ))
+    })
+}
+
+fn strategy_balances(deps: Deps, env: &Env) -> StdResult<Coins> {
+    let mut balances = NODES.all(deps.storage)?.iter().try_fold(
+        Coins::default(),
+        |mut acc, node| -> StdResult<Coins> {
+            let node_balances = node.balances(deps, env)?;
+            for balance in node_balances {
+                acc.add(balance)?;
+            }
+            Ok(acc)
+        },
+    )?;
+
+    #[allow(deprecated)]
+    for balance in deps.querier.query_all_balances(&env.contract.address)? {
+        balances.add(balance)?;
     }
+
+    Ok(balances)
 }
 
 #[cfg(test)]
@@ -404,12 +862,18 @@ mod tests {
             action::Action,
             distribution::{Destination, Distribution, Recipient},
         },
-        strategy::Node,
+        conditions::condition::Condition,
+        constants::MAX_LEDGER_ENTRIES,
+        manager::{AddressBook, Affiliate},
+        performance::PerformanceFee,
+        strategy::{Branch, DisabledNode, LedgerEntry},
     };
     use cosmwasm_std::{
+        from_json,
         testing::{message_info, mock_dependencies, mock_env},
-        Addr, Uint128,
+        Addr, ContractResult, CosmosMsg, Int128, SignedDecimal, SystemResult, Uint128,
     };
+    use cw_storage_plus::Item;
 
     #[test]
     fn test_only_manager_can_invoke_update() {
@@ -674,4 +1138,994 @@ mod tests {
             Err(ContractError::Unauthorized {})
         );
     }
+
+    #[test]
+    fn test_process_checkpoints_after_interval_and_resumes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+        CHECKPOINT_INTERVAL.save(deps.as_mut().storage, &1).unwrap();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![
+                Coin::new(1_000_u128, "rune"),
+                Coin::new(1_000_u128, "x/ruji"),
+            ],
+        );
+
+        let nodes = ["rune", "x/ruji"]
+            .iter()
+            .enumerate()
+            .map(|(i, denom)| Node::Action {
+                action: Action::Distribute(Distribution {
+                    denoms: vec![denom.to_string()],
+                    destinations: vec![Destination {
+                        shares: Uint128::new(10_000),
+                        recipient: Recipient::Bank {
+                            address: owner.clone(),
+                        },
+                        label: None,
+                        distributions: None,
+                    }],
+                }),
+                index: i as u16,
+                next: if i == 0 { Some(1) } else { None },
+            })
+            .collect::<Vec<_>>();
+
+        NODES.init(deps.as_mut(), &env, nodes).unwrap();
+
+        let process = |previous: Option<u16>| StrategyExecuteMsg::Process {
+            operation: StrategyOperation::Execute,
+            previous,
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            process(None),
+        )
+        .unwrap();
+
+        assert_eq!(response.messages.len(), 2);
+        assert_eq!(PATH.load(deps.as_ref().storage).unwrap().emitted, 1);
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            process(Some(0)),
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+        assert!(response
+            .events
+            .iter()
+            .any(|event| event.ty.ends_with("/process.checkpoint")));
+
+        assert_eq!(
+            from_json::<Option<u16>>(
+                query(deps.as_ref(), env.clone(), StrategyQueryMsg::Checkpoint {}).unwrap()
+            )
+            .unwrap(),
+            Some(1)
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            process(None),
+        )
+        .unwrap();
+
+        assert_eq!(response.messages.len(), 2);
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(1_000_u128, "x/ruji")],
+            })
+        );
+        assert_eq!(PATH.load(deps.as_ref().storage).unwrap().checkpoint, None);
+    }
+
+    #[test]
+    fn test_process_runs_after_migrating_v0_path() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+        DEPOSITS.save(deps.as_mut().storage, &vec![]).unwrap();
+        WITHDRAWALS.save(deps.as_mut().storage, &vec![]).unwrap();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000_u128, "rune")]);
+
+        NODES
+            .init(
+                deps.as_mut(),
+                &env,
+                vec![Node::Action {
+                    action: Action::Distribute(Distribution {
+                        denoms: vec!["rune".to_string()],
+                        destinations: vec![Destination {
+                            shares: Uint128::new(10_000),
+                            recipient: Recipient::Bank {
+                                address: owner.clone(),
+                            },
+                            label: None,
+                            distributions: None,
+                        }],
+                    }),
+                    index: 0,
+                    next: None,
+                }],
+            )
+            .unwrap();
+
+        // Version 0 strategies stored the path as a plain list of node indexes
+        Item::<Vec<String>>::new("path")
+            .save(deps.as_mut().storage, &vec!["0".to_string()])
+            .unwrap();
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            StrategyExecuteMsg::Process {
+                operation: StrategyOperation::Execute,
+                previous: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(1_000_u128, "rune")],
+            })
+        );
+        assert_eq!(
+            PATH.load(deps.as_ref().storage).unwrap().nodes,
+            vec!["0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_once_condition_only_succeeds_on_first_run() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![
+                Coin::new(1_000_u128, "rune"),
+                Coin::new(1_000_u128, "x/ruji"),
+            ],
+        );
+
+        let distribute = |denom: &str, index: u16| Node::Action {
+            action: Action::Distribute(Distribution {
+                denoms: vec![denom.to_string()],
+                destinations: vec![Destination {
+                    shares: Uint128::new(10_000),
+                    recipient: Recipient::Bank {
+                        address: owner.clone(),
+                    },
+                    label: None,
+                    distributions: None,
+                }],
+            }),
+            index,
+            next: None,
+        };
+
+        let nodes = vec![
+            Node::Condition {
+                condition: Condition::Once { completed: false },
+                index: 0,
+                on_success: Some(1),
+                on_failure: Some(2),
+            },
+            distribute("rune", 1),
+            distribute("x/ruji", 2),
+        ];
+
+        NODES.init(deps.as_mut(), &env, nodes).unwrap();
+
+        let process = StrategyExecuteMsg::Process {
+            operation: StrategyOperation::Execute,
+            previous: None,
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            process.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(1_000_u128, "rune")],
+            })
+        );
+
+        assert_eq!(
+            NODES.load(deps.as_ref().storage, 0).unwrap(),
+            Node::Condition {
+                condition: Condition::Once { completed: true },
+                index: 0,
+                on_success: Some(1),
+                on_failure: Some(2),
+            }
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            process,
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(1_000_u128, "x/ruji")],
+            })
+        );
+    }
+
+    #[test]
+    fn test_disabled_nodes_are_skipped() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![
+                Coin::new(1_000_u128, "rune"),
+                Coin::new(1_000_u128, "x/ruji"),
+            ],
+        );
+
+        let distribute = |denom: &str, index: u16, next: Option<u16>| Node::Action {
+            action: Action::Distribute(Distribution {
+                denoms: vec![denom.to_string()],
+                destinations: vec![Destination {
+                    shares: Uint128::new(10_000),
+                    recipient: Recipient::Bank {
+                        address: owner.clone(),
+                    },
+                    label: None,
+                    distributions: None,
+                }],
+            }),
+            index,
+            next,
+        };
+
+        let nodes = vec![
+            Node::Condition {
+                condition: Condition::BlocksCompleted(0),
+                index: 0,
+                on_success: Some(1),
+                on_failure: Some(2),
+            },
+            distribute("rune", 1, Some(2)),
+            distribute("x/ruji", 2, None),
+        ];
+
+        NODES.init(deps.as_mut(), &env, nodes).unwrap();
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[]),
+            StrategyExecuteMsg::SetDisabledNodes(vec![DisabledNode {
+                index: 0,
+                branch: None,
+            }]),
+        )
+        .is_err());
+
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[]),
+            StrategyExecuteMsg::SetDisabledNodes(vec![DisabledNode {
+                index: 3,
+                branch: None,
+            }]),
+        )
+        .is_err());
+
+        assert_eq!(
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                message_info(&owner, &[]),
+                StrategyExecuteMsg::SetDisabledNodes(vec![]),
+            ),
+            Err(ContractError::Unauthorized {})
+        );
+
+        let disabled_nodes = vec![
+            DisabledNode {
+                index: 0,
+                branch: Some(Branch::OnFailure),
+            },
+            DisabledNode {
+                index: 2,
+                branch: None,
+            },
+        ];
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[]),
+            StrategyExecuteMsg::SetDisabledNodes(disabled_nodes.clone()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            from_json::<Vec<DisabledNode>>(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    StrategyQueryMsg::DisabledNodes {}
+                )
+                .unwrap()
+            )
+            .unwrap(),
+            disabled_nodes
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            StrategyExecuteMsg::Process {
+                operation: StrategyOperation::Execute,
+                previous: None,
+            },
+        )
+        .unwrap();
+
+        assert!(response.messages.is_empty());
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[]),
+            StrategyExecuteMsg::SetDisabledNodes(vec![DisabledNode {
+                index: 0,
+                branch: Some(Branch::OnSuccess),
+            }]),
+        )
+        .unwrap();
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            StrategyExecuteMsg::Process {
+                operation: StrategyOperation::Execute,
+                previous: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(1_000_u128, "rune")],
+            })
+        );
+    }
+
+    #[test]
+    fn test_restricted_outflows_only_reach_address_book() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let allowed = deps.api.addr_make("allowed");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+        WITHDRAWALS.save(deps.as_mut().storage, &vec![]).unwrap();
+        RESTRICT_OUTFLOWS
+            .save(deps.as_mut().storage, &true)
+            .unwrap();
+
+        let address_book = AddressBook {
+            addresses: vec![allowed.clone()],
+            memos: vec![],
+        };
+
+        deps.querier.update_wasm(move |_| {
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&address_book).unwrap()))
+        });
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000_u128, "rune")]);
+
+        let distribute = |recipient: Recipient| Node::Action {
+            action: Action::Distribute(Distribution {
+                denoms: vec!["rune".to_string()],
+                destinations: vec![Destination {
+                    shares: Uint128::new(10_000),
+                    recipient,
+                    label: None,
+                    distributions: None,
+                }],
+            }),
+            index: 0,
+            next: None,
+        };
+
+        assert!(NODES
+            .init(
+                deps.as_mut(),
+                &env,
+                vec![distribute(Recipient::Bank {
+                    address: deps.api.addr_make("stranger"),
+                })],
+            )
+            .is_err());
+
+        assert!(NODES
+            .init(
+                deps.as_mut(),
+                &env,
+                vec![distribute(Recipient::Deposit {
+                    memo: "=:ETH.ETH:0xstranger".to_string(),
+                })],
+            )
+            .is_err());
+
+        NODES
+            .init(
+                deps.as_mut(),
+                &env,
+                vec![distribute(Recipient::Bank {
+                    address: allowed.clone(),
+                })],
+            )
+            .unwrap();
+
+        // Withdrawals only reach the owner, who is always allowed
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            StrategyExecuteMsg::Withdraw(vec![Coin::new(100_u128, "rune")]),
+        )
+        .is_ok());
+
+        let address_book = AddressBook {
+            addresses: vec![owner.clone()],
+            memos: vec![],
+        };
+
+        deps.querier.update_wasm(move |_| {
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&address_book).unwrap()))
+        });
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            StrategyExecuteMsg::Process {
+                operation: StrategyOperation::Execute,
+                previous: None,
+            },
+        )
+        .unwrap();
+
+        assert!(response.events.iter().any(|event| event
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "error"
+                && attribute.value.contains("not in the owner's address book"))));
+    }
+
+    #[test]
+    fn test_performance_fee_only_charges_gains_above_high_water_mark() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let fee_collector = deps.api.addr_make("fee_collector");
+        let partner = deps.api.addr_make("partner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES
+            .save(
+                deps.as_mut().storage,
+                &vec![
+                    Affiliate {
+                        label: "CALC".to_string(),
+                        address: fee_collector.clone(),
+                        bps: 15,
+                    },
+                    Affiliate {
+                        label: "Partner".to_string(),
+                        address: partner.clone(),
+                        bps: 45,
+                    },
+                ],
+            )
+            .unwrap();
+        DEPOSITS.save(deps.as_mut().storage, &vec![]).unwrap();
+        WITHDRAWALS.save(deps.as_mut().storage, &vec![]).unwrap();
+        HIGH_WATER_MARK
+            .save(
+                deps.as_mut().storage,
+                &HighWaterMark::new(PerformanceFee {
+                    bps: 2_000,
+                    denom: "usdc".to_string(),
+                }),
+            )
+            .unwrap();
+
+        NODES.init(deps.as_mut(), &env, vec![]).unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[Coin::new(1_000_u128, "usdc")]),
+            StrategyExecuteMsg::Execute {},
+        )
+        .unwrap();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(2_000_u128, "usdc")]);
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            StrategyExecuteMsg::Withdraw(vec![Coin::new(400_u128, "usdc")]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|message| message.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: owner.to_string(),
+                    amount: vec![Coin::new(200_u128, "usdc")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: fee_collector.to_string(),
+                    amount: vec![Coin::new(50_u128, "usdc")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: partner.to_string(),
+                    amount: vec![Coin::new(150_u128, "usdc")],
+                }),
+            ]
+        );
+
+        let high_water_mark = from_json::<Option<HighWaterMark>>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                StrategyQueryMsg::HighWaterMark {},
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+
+        // The mark is raised to the value the fee was paid at, less the withdrawal
+        assert_eq!(high_water_mark.value, Uint128::new(1_600));
+
+        // Deposits the oracle cannot price would be charged as gains later
+        assert!(execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(
+                &deps.api.addr_make("anyone"),
+                &[Coin::new(200_u128, "usdc"), Coin::new(5_u128, "x/junk")],
+            ),
+            StrategyExecuteMsg::Deposit {},
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("Failed to value deposit for the performance fee"));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(
+                &deps.api.addr_make("anyone"),
+                &[Coin::new(200_u128, "usdc")],
+            ),
+            StrategyExecuteMsg::Deposit {},
+        )
+        .unwrap();
+
+        assert_eq!(
+            HIGH_WATER_MARK.load(deps.as_ref().storage).unwrap().value,
+            Uint128::new(1_800)
+        );
+
+        // Gains already charged are not charged again, and denoms the oracle
+        // cannot price add nothing to the strategy's value
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(1_800_u128, "usdc"), Coin::new(5_u128, "x/junk")],
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            StrategyExecuteMsg::Withdraw(vec![Coin::new(400_u128, "usdc")]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|message| message.msg)
+                .collect::<Vec<_>>(),
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: vec![Coin::new(400_u128, "usdc")],
+            })]
+        );
+
+        // Denoms without a price were never valued, so only the priced part of
+        // a withdrawal is charged for the 1,000 of gains above the 1,400 mark
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(2_400_u128, "usdc"), Coin::new(5_u128, "x/junk")],
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            StrategyExecuteMsg::Withdraw(vec![
+                Coin::new(400_u128, "usdc"),
+                Coin::new(5_u128, "x/junk"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            response
+                .messages
+                .into_iter()
+                .map(|message| message.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: owner.to_string(),
+                    amount: vec![Coin::new(200_u128, "usdc"), Coin::new(5_u128, "x/junk")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: fee_collector.to_string(),
+                    amount: vec![Coin::new(50_u128, "usdc")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: partner.to_string(),
+                    amount: vec![Coin::new(150_u128, "usdc")],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ledger_records_deposits_and_withdrawals() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+        DEPOSITS.save(deps.as_mut().storage, &vec![]).unwrap();
+        WITHDRAWALS.save(deps.as_mut().storage, &vec![]).unwrap();
+        VALUATION
+            .save(
+                deps.as_mut().storage,
+                &Valuation {
+                    quote: "usdc".to_string(),
+                    oracle: PriceSource::Thorchain,
+                },
+            )
+            .unwrap();
+
+        NODES.init(deps.as_mut(), &env, vec![]).unwrap();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000_u128, "usdc")]);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&manager, &[Coin::new(1_000_u128, "usdc")]),
+            StrategyExecuteMsg::Execute {},
+        )
+        .unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100);
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_200_u128, "usdc")]);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[]),
+            StrategyExecuteMsg::Withdraw(vec![Coin::new(600_u128, "usdc")]),
+        )
+        .unwrap();
+
+        let ledger = from_json::<Vec<LedgerEntry>>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                StrategyQueryMsg::Ledger {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            ledger,
+            vec![
+                LedgerEntry {
+                    id: 0,
+                    time: env.block.time.seconds() - 100,
+                    event: LedgerEvent::Deposit,
+                    amounts: vec![Coin::new(1_000_u128, "usdc")],
+                    value: Some(Uint128::new(1_000)),
+                    nav: Some(Uint128::zero()),
+                },
+                LedgerEntry {
+                    id: 1,
+                    time: env.block.time.seconds(),
+                    event: LedgerEvent::Withdrawal,
+                    amounts: vec![Coin::new(600_u128, "usdc")],
+                    value: Some(Uint128::new(600)),
+                    nav: Some(Uint128::new(1_200)),
+                },
+            ]
+        );
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(600_u128, "usdc")]);
+
+        let performance = from_json::<Performance>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                StrategyQueryMsg::Performance {
+                    quote: None,
+                    oracle: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(performance.nav, Uint128::new(600));
+        assert_eq!(performance.net_deposits, Int128::new(400));
+        assert_eq!(performance.realised_pnl, Int128::new(100));
+        assert_eq!(performance.unrealised_pnl, Int128::new(100));
+        assert_eq!(performance.time_weighted_return, SignedDecimal::percent(20));
+
+        // Strategies without a valuation are measured in the quote of the query
+        VALUATION.remove(deps.as_mut().storage);
+
+        assert!(query(
+            deps.as_ref(),
+            env.clone(),
+            StrategyQueryMsg::Performance {
+                quote: None,
+                oracle: None,
+            },
+        )
+        .is_err());
+
+        let performance = from_json::<Performance>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                StrategyQueryMsg::Performance {
+                    quote: Some("usdc".to_string()),
+                    oracle: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(performance.quote, "usdc");
+        assert_eq!(performance.nav, Uint128::new(600));
+        assert_eq!(performance.deposited, Uint128::new(1_000));
+        assert_eq!(performance.withdrawn, Uint128::new(600));
+        assert_eq!(performance.net_deposits, Int128::new(400));
+        assert_eq!(performance.realised_pnl, Int128::new(100));
+        assert_eq!(performance.unrealised_pnl, Int128::new(100));
+        assert_eq!(performance.time_weighted_return, SignedDecimal::percent(20));
+    }
+
+    #[test]
+    fn test_ledger_values_distributions_lazily_and_drops_oldest_entries() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = deps.api.addr_make("owner");
+        let manager = Addr::unchecked("manager");
+
+        MANAGER.save(deps.as_mut().storage, &manager).unwrap();
+        OWNER.save(deps.as_mut().storage, &owner).unwrap();
+        AFFILIATES.save(deps.as_mut().storage, &vec![]).unwrap();
+        DEPOSITS.save(deps.as_mut().storage, &vec![]).unwrap();
+        WITHDRAWALS.save(deps.as_mut().storage, &vec![]).unwrap();
+        VALUATION
+            .save(
+                deps.as_mut().storage,
+                &Valuation {
+                    quote: "usdc".to_string(),
+                    oracle: PriceSource::Thorchain,
+                },
+            )
+            .unwrap();
+
+        NODES
+            .init(
+                deps.as_mut(),
+                &env,
+                vec![Node::Action {
+                    action: Action::Distribute(Distribution {
+                        denoms: vec!["usdc".to_string()],
+                        destinations: vec![Destination {
+                            shares: Uint128::new(10_000),
+                            recipient: Recipient::Bank {
+                                address: owner.clone(),
+                            },
+                            label: None,
+                            distributions: None,
+                        }],
+                    }),
+                    index: 0,
+                    next: None,
+                }],
+            )
+            .unwrap();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000_u128, "usdc")]);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&owner, &[Coin::new(1_000_u128, "usdc")]),
+            StrategyExecuteMsg::Deposit {},
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&env.contract.address, &[]),
+            StrategyExecuteMsg::Process {
+                operation: StrategyOperation::Execute,
+                previous: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            LEDGER.load(deps.as_ref().storage, 1).unwrap(),
+            LedgerEntry {
+                id: 1,
+                time: env.block.time.seconds(),
+                event: LedgerEvent::Distribution,
+                amounts: vec![Coin::new(1_000_u128, "usdc")],
+                value: None,
+                nav: None,
+            }
+        );
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![]);
+
+        let performance = from_json::<Performance>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                StrategyQueryMsg::Performance {
+                    quote: None,
+                    oracle: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(performance.nav, Uint128::zero());
+        assert_eq!(performance.distributed, Uint128::new(1_000));
+        assert_eq!(performance.net_deposits, Int128::zero());
+        assert_eq!(performance.realised_pnl, Int128::zero());
+        assert_eq!(performance.time_weighted_return, SignedDecimal::zero());
+
+        for _ in 0..MAX_LEDGER_ENTRIES {
+            record_outflows(
+                deps.as_mut().storage,
+                &env,
+                (vec![Coin::new(1_u128, "usdc")], vec![]),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            LEDGER
+                .keys(deps.as_ref().storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap(),
+            (2..MAX_LEDGER_ENTRIES + 2).collect::<Vec<_>>()
+        );
+    }
 }
@@ -0,0 +1,256 @@
+use calc_rs::{manager::FeeSchedule, v0, version::Migration};
+use cosmwasm_std::{Order, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+
+use crate::state::{
+    Path, HIGH_WATER_MARK, LEDGER, NODES, PATH, RESTRICT_OUTFLOWS, THORCHAIN_AFFILIATE, VALUATION,
+};
+
+pub const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+// Version 0 strategies were deployed before version tracking. Their nodes are
+// read with the frozen version 0 types and converted to the current ones, and
+// the state added since is set to what those strategies ran with.
+fn migrate_v0_to_v1(storage: &mut dyn Storage) -> StdResult<()> {
+    const V0_NODES: Map<u16, v0::Node> = Map::new("nodes");
+    const V0_PATH: Item<Vec<String>> = Item::new("path");
+
+    let nodes = V0_NODES
+        .range(storage, None, None, Order::Ascending)
+        .map(|result| result.map(|(_, node)| node))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for node in nodes {
+        NODES.save(storage, &node.migrate()?)?;
+    }
+
+    if let Some(nodes) = V0_PATH.may_load(storage)? {
+        PATH.save(
+            storage,
+            &Path {
+                nodes,
+                ..Path::default()
+            },
+        )?;
+
+        V0_PATH.remove(storage);
+    }
+
+    RESTRICT_OUTFLOWS.save(storage, &false)?;
+    THORCHAIN_AFFILIATE.save(storage, &FeeSchedule::default().thorchain_affiliate)?;
+
+    // Version 0 had no performance fees, valuations or ledger, so the strategy
+    // starts without a high water mark or valuation and with an empty ledger
+    HIGH_WATER_MARK.remove(storage);
+    VALUATION.remove(storage);
+    LEDGER.clear(storage);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use calc_rs::{
+        actions::{
+            action::Action,
+            distribution::{Destination, Distribution, Recipient},
+            limit_orders::fin_limit_order::{
+                Direction, FinLimitOrder, LimitOrderStats, Offset, PriceStrategy, StaleOrder,
+            },
+            swaps::{
+                fin::FinRoute,
+                swap::{Swap, SwapAmountAdjustment, SwapRoute, SwapRouting},
+            },
+        },
+        conditions::condition::Condition,
+        core::Amount,
+        manager::FeeSchedule,
+        strategy::Node,
+        version::{migrate_state, ContractVersion, CONTRACT_VERSION},
+    };
+    use cosmwasm_std::{
+        testing::MockStorage, to_json_string, Addr, Coin, Decimal, Storage, Uint128,
+    };
+    use cw_storage_plus::{Item, Map};
+    use rujira_rs::fin::Side;
+
+    use crate::{
+        migrations::MIGRATIONS,
+        state::{
+            Path, HIGH_WATER_MARK, LEDGER, NODES, PATH, RESTRICT_OUTFLOWS, THORCHAIN_AFFILIATE,
+            VALUATION,
+        },
+    };
+
+    const V0_LATEST_SWAP: &str = r#"{"swap_amount":{"denom":"rune","amount":"1000"},"expected_receive_amount":{"denom":"x/ruji","amount":"950"},"starting_block":100,"streaming_swap_blocks":10,"memo":"=:x/ruji:strategy"}"#;
+
+    // Nodes as serialized by strategies deployed before version tracking
+    fn v0_nodes() -> [String; 4] {
+        [
+            format!(
+                r#"{{"action":{{"action":{{"swap":{{"swap_amount":{{"denom":"rune","amount":"1000"}},"minimum_receive_amount":{{"denom":"x/ruji","amount":"900"}},"maximum_slippage_bps":100,"adjustment":"fixed","routes":[{{"fin":{{"pair_address":"pair"}}}},{{"thorchain":{{"streaming_interval":null,"max_streaming_quantity":null,"affiliate_code":null,"affiliate_bps":null,"latest_swap":{V0_LATEST_SWAP}}}}}]}}}},"index":0,"next":1}}}}"#
+            ),
+            r#"{"action":{"action":{"limit_order":{"pair_address":"pair","bid_denom":"rune","bid_amount":{"fixed":"1000"},"side":"base","strategy":{"offset":{"side":"quote","direction":"below","offset":{"percent":5},"tolerance":null}},"min_fill_ratio":null,"current_order":{"price":"1.5"}}},"index":1,"next":2}}"#.to_string(),
+            r#"{"condition":{"condition":{"fin_limit_order_filled":{"owner":null,"pair_address":"pair","side":"base","price":"1.5"}},"index":2,"on_success":3,"on_failure":null}}"#.to_string(),
+            r#"{"action":{"action":{"distribute":{"denoms":["rune"],"destinations":[{"shares":"10000","recipient":{"bank":{"address":"owner"}},"label":null,"distributions":null}]}},"index":3,"next":null}}"#.to_string(),
+        ]
+    }
+
+    #[test]
+    fn migrates_v0_strategies() {
+        let mut storage = MockStorage::default();
+
+        for (index, node) in v0_nodes().iter().enumerate() {
+            storage.set(
+                &Map::<u16, Node>::new("nodes").key(index as u16),
+                node.as_bytes(),
+            );
+        }
+
+        storage.set(Item::<Vec<String>>::new("path").as_slice(), br#"["0","1"]"#);
+
+        assert_eq!(
+            migrate_state(&mut storage, "calc-strategy", MIGRATIONS).unwrap(),
+            (0, 1)
+        );
+
+        let Node::Action {
+            action: Action::Swap(swap),
+            index: 0,
+            next: Some(1),
+        } = NODES.load(&storage, 0).unwrap()
+        else {
+            panic!("Expected the swap node to migrate in place");
+        };
+
+        assert_eq!(swap.adjustment, SwapAmountAdjustment::Fixed);
+        assert_eq!(swap.routing, SwapRouting::Best);
+        assert_eq!(
+            swap.routes[0],
+            SwapRoute::Fin(FinRoute {
+                pair_address: Addr::unchecked("pair"),
+            })
+        );
+
+        let SwapRoute::Thorchain(thorchain_route) = &swap.routes[1] else {
+            panic!("Expected the thorchain route to migrate in place");
+        };
+
+        // The cached streaming swap is carried over untouched
+        assert_eq!(
+            to_json_string(&thorchain_route.latest_swap).unwrap(),
+            V0_LATEST_SWAP
+        );
+
+        assert_eq!(
+            Swap {
+                routes: vec![],
+                ..swap
+            },
+            Swap {
+                swap_amount: Coin::new(1_000u128, "rune"),
+                minimum_receive_amount: Coin::new(900u128, "x/ruji"),
+                maximum_slippage_bps: 100,
+                adjustment: SwapAmountAdjustment::Fixed,
+                routes: vec![],
+                routing: SwapRouting::Best,
+            }
+        );
+
+        assert_eq!(
+            NODES.load(&storage, 1).unwrap(),
+            Node::Action {
+                action: Action::LimitOrder(FinLimitOrder {
+                    pair_address: Addr::unchecked("pair"),
+                    pair_denoms: vec![],
+                    bid_denom: "rune".to_string(),
+                    bid_amount: Amount::Fixed(Uint128::new(1_000)),
+                    side: Side::Base,
+                    strategy: PriceStrategy::Offset {
+                        side: Side::Quote,
+                        direction: Direction::Below,
+                        offset: Offset::Percent(5),
+                        tolerance: None,
+                    },
+                    min_fill_ratio: None,
+                    time_in_force: None,
+                    fallback: None,
+                    budget: None,
+                    stats: LimitOrderStats::default(),
+                    current_order: Some(StaleOrder {
+                        price: Decimal::percent(150),
+                        oracle_offset: None,
+                        expires: None,
+                    }),
+                }),
+                index: 1,
+                next: Some(2),
+            }
+        );
+
+        assert_eq!(
+            NODES.load(&storage, 2).unwrap(),
+            Node::Condition {
+                condition: Condition::FinLimitOrderFilled {
+                    owner: None,
+                    pair_address: Addr::unchecked("pair"),
+                    side: Side::Base,
+                    price: Decimal::percent(150),
+                    oracle_offset: None,
+                },
+                index: 2,
+                on_success: Some(3),
+                on_failure: None,
+            }
+        );
+
+        assert_eq!(
+            NODES.load(&storage, 3).unwrap(),
+            Node::Action {
+                action: Action::Distribute(Distribution {
+                    denoms: vec!["rune".to_string()],
+                    destinations: vec![Destination {
+                        shares: Uint128::new(10_000),
+                        recipient: Recipient::Bank {
+                            address: Addr::unchecked("owner"),
+                        },
+                        label: None,
+                        distributions: None,
+                    }],
+                }),
+                index: 3,
+                next: None,
+            }
+        );
+
+        // The path moves to its current layout and key
+        assert_eq!(
+            PATH.load(&storage).unwrap(),
+            Path {
+                nodes: vec!["0".to_string(), "1".to_string()],
+                emitted: 0,
+                checkpoint: None,
+            }
+        );
+        assert!(storage
+            .get(Item::<Vec<String>>::new("path").as_slice())
+            .is_none());
+
+        assert!(!RESTRICT_OUTFLOWS.load(&storage).unwrap());
+        assert_eq!(
+            THORCHAIN_AFFILIATE.load(&storage).unwrap(),
+            FeeSchedule::default().thorchain_affiliate
+        );
+        assert_eq!(HIGH_WATER_MARK.may_load(&storage).unwrap(), None);
+        assert_eq!(VALUATION.may_load(&storage).unwrap(), None);
+        assert!(LEDGER.is_empty(&storage));
+
+        assert_eq!(
+            CONTRACT_VERSION.load(&storage).unwrap(),
+            ContractVersion {
+                contract: "calc-strategy".to_string(),
+                version: 1,
+            }
+        );
+    }
+}
@@ -1,11 +1,20 @@
 use calc_rs::{
     conditions::condition::Condition,
-    constants::MAX_STRATEGY_SIZE,
-    manager::Affiliate,
+    constants::{MAX_LEDGER_ENTRIES, MAX_STRATEGY_SIZE},
+    manager::{
+        AddressBook, Affiliate, FeeSchedule, ManagerQueryMsg, ThorchainAffiliate, Valuation,
+    },
     operation::Operation,
-    strategy::{Node, StrategyOperation},
+    oracle::value_priceable_in,
+    performance::HighWaterMark,
+    strategy::{
+        DisabledNode, LedgerEntry, LedgerEvent, Node, PerformanceTotals, StrategyOperation,
+    },
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Coin, Coins, Deps, DepsMut, Env, Order, StdError, StdResult, Storage, Uint128,
 };
-use cosmwasm_std::{Addr, Coin, Deps, DepsMut, Env, Order, StdError, StdResult, Storage};
 use cw_storage_plus::{Item, Map};
 
 pub const MANAGER: Item<Addr> = Item::new("manager");
@@ -13,6 +22,51 @@ pub const OWNER: Item<Addr> = Item::new("owner");
 pub const AFFILIATES: Item<Vec<Affiliate>> = Item::new("affiliates");
 pub const DEPOSITS: Item<Vec<Coin>> = Item::new("deposits");
 pub const WITHDRAWALS: Item<Vec<Coin>> = Item::new("withdrawals");
+pub const RESTRICT_OUTFLOWS: Item<bool> = Item::new("restrict_outflows");
+pub const HIGH_WATER_MARK: Item<HighWaterMark> = Item::new("high_water_mark");
+pub const THORCHAIN_AFFILIATE: Item<Option<ThorchainAffiliate>> = Item::new("thorchain_affiliate");
+pub const VALUATION: Item<Valuation> = Item::new("valuation");
+pub const PERFORMANCE: Item<PerformanceTotals> = Item::new("performance");
+
+pub fn load_address_book(deps: Deps, env: &Env) -> StdResult<Option<AddressBook>> {
+    if !RESTRICT_OUTFLOWS.may_load(deps.storage)?.unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let mut address_book = deps.querier.query_wasm_smart::<AddressBook>(
+        MANAGER.load(deps.storage)?,
+        &ManagerQueryMsg::AddressBook {
+            owner: OWNER.load(deps.storage)?,
+        },
+    )?;
+
+    // Fee recipients, the owner (who receives withdrawals) and the strategy
+    // itself are always allowed
+    address_book.addresses.extend(
+        AFFILIATES
+            .load(deps.storage)?
+            .into_iter()
+            .map(|affiliate| affiliate.address),
+    );
+
+    address_book.addresses.push(OWNER.load(deps.storage)?);
+    address_book.addresses.push(env.contract.address.clone());
+
+    Ok(Some(address_book))
+}
+
+pub fn check_outflows(address_book: &AddressBook, node: &Node) -> StdResult<()> {
+    for recipient in node.recipients() {
+        if !address_book.allows(&recipient) {
+            return Err(StdError::generic_err(format!(
+                "Recipient {} is not in the owner's address book",
+                recipient.key()
+            )));
+        }
+    }
+
+    Ok(())
+}
 
 pub struct NodeStore {
     store: Map<u16, Node>,
@@ -21,6 +75,12 @@ pub struct NodeStore {
 impl NodeStore {
     pub fn init(&self, deps: DepsMut, env: &Env, nodes: Vec<Node>) -> StdResult<()> {
         let affiliates = AFFILIATES.load(deps.storage)?;
+        let address_book = load_address_book(deps.as_ref(), env)?;
+
+        // Strategies created before fee schedules keep the default Thorchain affiliate
+        let thorchain_affiliate = THORCHAIN_AFFILIATE
+            .may_load(deps.storage)?
+            .unwrap_or_else(|| FeeSchedule::default().thorchain_affiliate);
         let mut strategy_size = 0;
 
         let node_count = nodes.len();
@@ -97,7 +157,13 @@ impl NodeStore {
                 }
             }
 
-            let initialised_node = node.init(deps.as_ref(), env, &affiliates)?;
+            if let Some(address_book) = &address_book {
+                check_outflows(address_book, &node)?;
+            }
+
+            let initialised_node = node
+                .with_thorchain_affiliate(thorchain_affiliate.as_ref())
+                .init(deps.as_ref(), env, &affiliates)?;
             self.save(deps.storage, &initialised_node)?;
 
             strategy_size += initialised_node.size();
@@ -181,4 +247,180 @@ pub const NODES: NodeStore = NodeStore {
     store: Map::new("nodes"),
 };
 
-pub const PATH: Item<Vec<String>> = Item::new("path");
+#[cw_serde]
+#[derive(Default)]
+pub struct Path {
+    pub nodes: Vec<String>,
+    pub emitted: u16,
+    pub checkpoint: Option<u16>,
+}
+
+// Version 0 strategies stored the path as a list of node indexes under "path"
+pub const PATH: Item<Path> = Item::new("path_v2");
+pub const CHECKPOINT_INTERVAL: Item<u16> = Item::new("checkpoint_interval");
+
+pub const DISABLED_NODES: Map<u16, DisabledNode> = Map::new("disabled_nodes");
+
+pub const LEDGER: Map<u64, LedgerEntry> = Map::new("ledger");
+pub const UNVALUED_OUTFLOWS: Item<UnvaluedOutflows> = Item::new("unvalued_outflows");
+
+// Distributions and their fees recorded since the strategy was last valued
+#[cw_serde]
+#[derive(Default)]
+pub struct UnvaluedOutflows {
+    pub distributed: Vec<Coin>,
+    pub fees: Vec<Coin>,
+}
+
+// Appends an entry to the ledger, dropping the oldest once it is full
+fn append_ledger_entry(
+    storage: &mut dyn Storage,
+    env: &Env,
+    event: LedgerEvent,
+    amounts: Vec<Coin>,
+    value: Option<Uint128>,
+    nav: Option<Uint128>,
+) -> StdResult<()> {
+    let id = LEDGER
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |id| id + 1);
+
+    if id >= MAX_LEDGER_ENTRIES {
+        LEDGER.remove(storage, id - MAX_LEDGER_ENTRIES);
+    }
+
+    LEDGER.save(
+        storage,
+        id,
+        &LedgerEntry {
+            id,
+            time: env.block.time.seconds(),
+            event,
+            amounts,
+            value,
+            nav,
+        },
+    )
+}
+
+// Values a deposit or withdrawal and folds it into the running performance
+// totals, when the strategy has a valuation. Balances are only fetched then,
+// and are those of the strategy just before the event.
+pub fn record_ledger_entry(
+    deps: DepsMut,
+    env: &Env,
+    event: LedgerEvent,
+    amounts: Vec<Coin>,
+    balances: impl FnOnce(Deps) -> StdResult<Coins>,
+) -> StdResult<()> {
+    let (value, nav) = match VALUATION.may_load(deps.storage)? {
+        Some(valuation) => {
+            let nav = value_priceable_in(
+                deps.as_ref(),
+                &balances(deps.as_ref())?.into_vec(),
+                &valuation.quote,
+                &valuation.oracle,
+            );
+
+            let value =
+                value_priceable_in(deps.as_ref(), &amounts, &valuation.quote, &valuation.oracle);
+
+            let mut totals = PERFORMANCE.may_load(deps.storage)?.unwrap_or_default();
+            fold_unvalued_outflows(deps.as_ref(), &valuation, &mut totals, nav)?;
+            totals.record(&event, value, nav)?;
+
+            PERFORMANCE.save(deps.storage, &totals)?;
+            UNVALUED_OUTFLOWS.remove(deps.storage);
+
+            (Some(value), Some(nav))
+        }
+        None => (None, None),
+    };
+
+    append_ledger_entry(deps.storage, env, event, amounts, value, nav)
+}
+
+// Records what a distribution sent out without valuing it, so that processing a
+// node never has to value the whole strategy. The outflows are valued with the
+// next deposit or withdrawal, or by the performance query until then.
+pub fn record_outflows(
+    storage: &mut dyn Storage,
+    env: &Env,
+    (distributed, fees): (Vec<Coin>, Vec<Coin>),
+) -> StdResult<()> {
+    if distributed.is_empty() && fees.is_empty() {
+        return Ok(());
+    }
+
+    if VALUATION.exists(storage) {
+        let mut outflows = UNVALUED_OUTFLOWS.may_load(storage)?.unwrap_or_default();
+        outflows.distributed = add_coins(outflows.distributed, &distributed)?;
+        outflows.fees = add_coins(outflows.fees, &fees)?;
+        UNVALUED_OUTFLOWS.save(storage, &outflows)?;
+    }
+
+    if !distributed.is_empty() {
+        append_ledger_entry(
+            storage,
+            env,
+            LedgerEvent::Distribution,
+            distributed,
+            None,
+            None,
+        )?;
+    }
+
+    if !fees.is_empty() {
+        append_ledger_entry(storage, env, LedgerEvent::Fee, fees, None, None)?;
+    }
+
+    Ok(())
+}
+
+// Folds outflows that have already left into the totals, as though they left
+// just before the strategy was valued at `nav`
+pub fn fold_unvalued_outflows(
+    deps: Deps,
+    valuation: &Valuation,
+    totals: &mut PerformanceTotals,
+    nav: Uint128,
+) -> StdResult<()> {
+    let outflows = UNVALUED_OUTFLOWS
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let distributed = value_priceable_in(
+        deps,
+        &outflows.distributed,
+        &valuation.quote,
+        &valuation.oracle,
+    );
+
+    let fees = value_priceable_in(deps, &outflows.fees, &valuation.quote, &valuation.oracle);
+
+    if !outflows.distributed.is_empty() {
+        totals.record(
+            &LedgerEvent::Distribution,
+            distributed,
+            nav + fees + distributed,
+        )?;
+    }
+
+    if !outflows.fees.is_empty() {
+        totals.record(&LedgerEvent::Fee, fees, nav + fees)?;
+    }
+
+    Ok(())
+}
+
+fn add_coins(existing: Vec<Coin>, amounts: &[Coin]) -> StdResult<Vec<Coin>> {
+    let mut coins = Coins::try_from(existing)?;
+
+    for coin in amounts {
+        coins.add(coin.clone())?;
+    }
+
+    Ok(coins.into_vec())
+}
@@ -5,7 +5,13 @@ use cosmwasm_std::{Coins, CosmosMsg, Deps, Env, StdResult};
 
 use crate::{
     actions::{
-        distribution::Distribution, limit_orders::fin_limit_order::FinLimitOrder, swaps::swap::Swap,
+        distribution::Distribution,
+        limit_orders::{
+            fin_limit_order::FinLimitOrder, fin_limit_order_ladder::FinLimitOrderLadder,
+            fin_market_maker::FinMarketMaker,
+        },
+        swaps::swap::Swap,
+        trailing_stop::TrailingStop,
     },
     manager::Affiliate,
     operation::{Operation, StatefulOperation},
@@ -15,6 +21,9 @@ use crate::{
 pub enum Action {
     Swap(Swap),
     LimitOrder(FinLimitOrder),
+    LimitOrderLadder(FinLimitOrderLadder),
+    MarketMake(FinMarketMaker),
+    TrailingStop(TrailingStop),
     Distribute(Distribution),
 }
 
@@ -23,7 +32,15 @@ impl Action {
         match self {
             Action::Swap(action) => action.routes.len() * 4 + 1,
             Action::Distribute(action) => action.destinations.len() + 1,
-            Action::LimitOrder(_) => 4,
+            Action::LimitOrder(action) => {
+                4 + action
+                    .fallback
+                    .as_ref()
+                    .map_or(0, |fallback| fallback.routes.len() * 4)
+            }
+            Action::LimitOrderLadder(action) => action.levels as usize + 1,
+            Action::MarketMake(_) => 8,
+            Action::TrailingStop(action) => action.swap.routes.len() * 4 + 2,
         }
     }
 }
@@ -35,6 +52,15 @@ impl Operation<Action> for Action {
             Action::LimitOrder(limit_order) => {
                 Action::LimitOrder(limit_order.init(deps, env, affiliates)?)
             }
+            Action::LimitOrderLadder(ladder) => {
+                Action::LimitOrderLadder(ladder.init(deps, env, affiliates)?)
+            }
+            Action::MarketMake(market_maker) => {
+                Action::MarketMake(market_maker.init(deps, env, affiliates)?)
+            }
+            Action::TrailingStop(trailing_stop) => {
+                Action::TrailingStop(trailing_stop.init(deps, env, affiliates)?)
+            }
             Action::Distribute(distribution) => {
                 Action::Distribute(distribution.init(deps, env, affiliates)?)
             }
@@ -51,6 +77,18 @@ impl Operation<Action> for Action {
                 let (messages, limit_order) = limit_order.execute(deps, env)?;
                 (messages, Action::LimitOrder(limit_order))
             }
+            Action::LimitOrderLadder(ladder) => {
+                let (messages, ladder) = ladder.execute(deps, env)?;
+                (messages, Action::LimitOrderLadder(ladder))
+            }
+            Action::MarketMake(market_maker) => {
+                let (messages, market_maker) = market_maker.execute(deps, env)?;
+                (messages, Action::MarketMake(market_maker))
+            }
+            Action::TrailingStop(trailing_stop) => {
+                let (messages, trailing_stop) = trailing_stop.execute(deps, env)?;
+                (messages, Action::TrailingStop(trailing_stop))
+            }
             Action::Distribute(distribution) => {
                 let (messages, distribution) = distribution.execute(deps, env)?;
                 (messages, Action::Distribute(distribution))
@@ -63,6 +101,8 @@ impl StatefulOperation<Action> for Action {
     fn balances(&self, deps: Deps, env: &Env) -> StdResult<Coins> {
         match self {
             Action::LimitOrder(limit_order) => limit_order.balances(deps, env),
+            Action::LimitOrderLadder(ladder) => ladder.balances(deps, env),
+            Action::MarketMake(market_maker) => market_maker.balances(deps, env),
             _ => Ok(Coins::default()),
         }
     }
@@ -73,16 +113,37 @@ impl StatefulOperation<Action> for Action {
                 let (messages, limit_order) = limit_order.cancel(deps, env)?;
                 Ok((messages, Action::LimitOrder(limit_order)))
             }
+            Action::LimitOrderLadder(ladder) => {
+                let (messages, ladder) = ladder.cancel(deps, env)?;
+                Ok((messages, Action::LimitOrderLadder(ladder)))
+            }
+            Action::MarketMake(market_maker) => {
+                let (messages, market_maker) = market_maker.cancel(deps, env)?;
+                Ok((messages, Action::MarketMake(market_maker)))
+            }
             _ => Ok((vec![], self)),
         }
     }
 
     fn commit(self, deps: Deps, env: &Env) -> StdResult<Action> {
         match self {
+            Action::Swap(swap) => Ok(Action::Swap(swap.commit(deps, env)?)),
+            Action::TrailingStop(trailing_stop) => {
+                let trailing_stop = trailing_stop.commit(deps, env)?;
+                Ok(Action::TrailingStop(trailing_stop))
+            }
             Action::LimitOrder(limit_order) => {
                 let limit_order = limit_order.commit(deps, env)?;
                 Ok(Action::LimitOrder(limit_order))
             }
+            Action::LimitOrderLadder(ladder) => {
+                let ladder = ladder.commit(deps, env)?;
+                Ok(Action::LimitOrderLadder(ladder))
+            }
+            Action::MarketMake(market_maker) => {
+                let market_maker = market_maker.commit(deps, env)?;
+                Ok(Action::MarketMake(market_maker))
+            }
             _ => Ok(self),
         }
     }
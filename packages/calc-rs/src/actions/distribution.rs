@@ -3,11 +3,11 @@ use std::vec;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    Addr, BankMsg, Binary, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult,
-    Uint128, WasmMsg,
+    from_json, Addr, BankMsg, Binary, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError,
+    StdResult, Uint128, WasmMsg,
 };
 
-use crate::manager::Affiliate;
+use crate::manager::{Affiliate, ManagerExecuteMsg, ManagerQueryMsg, Strategy};
 use crate::operation::Operation;
 use crate::thorchain::MsgDeposit;
 
@@ -44,10 +44,15 @@ pub struct Distribution {
 }
 
 impl Distribution {
-    pub fn with_affiliates(self, affiliates: &[Affiliate]) -> StdResult<Self> {
+    pub fn with_affiliates(
+        self,
+        affiliates: &[Affiliate],
+        exempt: &[Recipient],
+    ) -> StdResult<Self> {
         let total_fee_applied_shares = self
             .destinations
             .iter()
+            .filter(|d| !exempt.contains(&d.recipient))
             .fold(Uint128::zero(), |acc, d| acc + d.shares);
 
         Ok(Distribution {
@@ -64,12 +69,100 @@ impl Distribution {
                         label: Some(affiliate.label.clone()),
                         distributions: None,
                     })
+                    .filter(|d| !d.shares.is_zero())
                     .collect(),
             ]
             .concat(),
         })
     }
 
+    // Transfers to strategies in the same manager with the same owner are fee exempt
+    pub fn internal_recipients(&self, deps: Deps, env: &Env) -> Vec<Recipient> {
+        let Ok(contract_info) = deps.querier.query_wasm_contract_info(&env.contract.address) else {
+            return vec![];
+        };
+
+        let manager = contract_info.creator;
+
+        let Ok(strategy) = deps.querier.query_wasm_smart::<Strategy>(
+            &manager,
+            &ManagerQueryMsg::Strategy {
+                address: env.contract.address.clone(),
+            },
+        ) else {
+            return vec![];
+        };
+
+        self.destinations
+            .iter()
+            .filter(|destination| {
+                let address = match &destination.recipient {
+                    Recipient::Bank { address } => address.clone(),
+                    Recipient::Contract { address, msg } if *address == manager => {
+                        match from_json(msg) {
+                            Ok(ManagerExecuteMsg::Execute { contract_address }) => contract_address,
+                            _ => return false,
+                        }
+                    }
+                    Recipient::Contract { address, .. } => address.clone(),
+                    Recipient::Deposit { .. } => return false,
+                };
+
+                deps.querier
+                    .query_wasm_smart::<Strategy>(&manager, &ManagerQueryMsg::Strategy { address })
+                    .is_ok_and(|recipient| recipient.owner == strategy.owner)
+            })
+            .map(|destination| destination.recipient.clone())
+            .collect()
+    }
+
+    // Splits what has been sent since an earlier state of this distribution
+    // into fees paid to affiliates and distributions to everyone else
+    pub fn distributed_since(
+        &self,
+        previous: &Distribution,
+        affiliates: &[Affiliate],
+    ) -> StdResult<(Vec<Coin>, Vec<Coin>)> {
+        let mut distributed = Coins::default();
+        let mut fees = Coins::default();
+
+        for (index, destination) in self.destinations.iter().enumerate() {
+            let previous_distributions = previous
+                .destinations
+                .get(index)
+                .and_then(|previous| previous.distributions.clone())
+                .unwrap_or_default();
+
+            let is_fee = matches!(
+                &destination.recipient,
+                Recipient::Bank { address }
+                    if affiliates.iter().any(|affiliate| affiliate.address == *address)
+            );
+
+            for coin in destination.distributions.iter().flatten() {
+                let sent = coin.amount.saturating_sub(
+                    previous_distributions
+                        .iter()
+                        .filter(|previous| previous.denom == coin.denom)
+                        .map(|previous| previous.amount)
+                        .sum(),
+                );
+
+                if sent.is_zero() {
+                    continue;
+                }
+
+                if is_fee {
+                    fees.add(Coin::new(sent, coin.denom.clone()))?;
+                } else {
+                    distributed.add(Coin::new(sent, coin.denom.clone()))?;
+                }
+            }
+        }
+
+        Ok((distributed.into_vec(), fees.into_vec()))
+    }
+
     pub fn execute_unsafe(
         self,
         deps: Deps,
@@ -175,7 +268,7 @@ impl Distribution {
 }
 
 impl Operation<Distribution> for Distribution {
-    fn init(self, deps: Deps, _env: &Env, affiliates: &[Affiliate]) -> StdResult<Distribution> {
+    fn init(self, deps: Deps, env: &Env, affiliates: &[Affiliate]) -> StdResult<Distribution> {
         if self.denoms.is_empty() {
             return Err(StdError::generic_err("Denoms cannot be empty"));
         }
@@ -215,10 +308,187 @@ impl Operation<Distribution> for Distribution {
             )));
         }
 
-        self.with_affiliates(affiliates)
+        let exempt = self.internal_recipients(deps, env);
+
+        self.with_affiliates(affiliates, &exempt)
     }
 
     fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, Distribution)> {
         self.execute_unsafe(deps, env)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        to_json_binary, Binary, Coin, ContractResult, SystemResult, Uint128, WasmQuery,
+    };
+
+    use crate::{
+        actions::distribution::{Destination, Distribution, Recipient},
+        manager::{Affiliate, ManagerExecuteMsg, ManagerQueryMsg, Strategy, StrategyStatus},
+        operation::Operation,
+    };
+
+    #[test]
+    fn transfers_between_same_owner_strategies_are_fee_exempt() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let manager = deps.api.addr_make("manager");
+        let owner = deps.api.addr_make("owner");
+        let sibling = deps.api.addr_make("sibling");
+        let stranger = deps.api.addr_make("stranger");
+
+        let contract_address = env.contract.address.clone();
+        let creator = manager.clone();
+        let strategy_owner = owner.clone();
+        let sibling_address = sibling.clone();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { .. } => SystemResult::Ok(ContractResult::Ok(Binary::from(
+                format!(r#"{{"code_id":1,"creator":"{creator}","admin":null,"pinned":false}}"#)
+                    .as_bytes(),
+            ))),
+            WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                ManagerQueryMsg::Strategy { address }
+                    if address == contract_address || address == sibling_address =>
+                {
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&Strategy {
+                            id: 1,
+                            source: None,
+                            owner: strategy_owner.clone(),
+                            contract_address: address,
+                            code_id: 1,
+                            created_at: 0,
+                            updated_at: 0,
+                            label: "Strategy".to_string(),
+                            status: StrategyStatus::Active,
+                        })
+                        .unwrap(),
+                    ))
+                }
+                _ => SystemResult::Ok(ContractResult::Err("Strategy not found".to_string())),
+            },
+            _ => panic!("Unexpected query type"),
+        });
+
+        let destination = |recipient: Recipient| Destination {
+            shares: Uint128::new(5_000),
+            recipient,
+            label: None,
+            distributions: None,
+        };
+
+        let affiliates = vec![Affiliate {
+            label: "CALC".to_string(),
+            address: deps.api.addr_make("fee_collector"),
+            bps: 25,
+        }];
+
+        let distribution = Distribution {
+            denoms: vec!["rune".to_string()],
+            destinations: vec![
+                destination(Recipient::Bank {
+                    address: sibling.clone(),
+                }),
+                destination(Recipient::Bank {
+                    address: stranger.clone(),
+                }),
+            ],
+        }
+        .init(deps.as_ref(), &env, &affiliates)
+        .unwrap();
+
+        assert_eq!(distribution.destinations.len(), 3);
+        assert_eq!(distribution.destinations[2].shares, Uint128::new(13));
+
+        let distribution = Distribution {
+            denoms: vec!["rune".to_string()],
+            destinations: vec![
+                destination(Recipient::Contract {
+                    address: manager.clone(),
+                    msg: to_json_binary(&ManagerExecuteMsg::Execute {
+                        contract_address: sibling.clone(),
+                    })
+                    .unwrap(),
+                }),
+                destination(Recipient::Bank {
+                    address: sibling.clone(),
+                }),
+            ],
+        }
+        .init(deps.as_ref(), &env, &affiliates)
+        .unwrap();
+
+        assert_eq!(distribution.destinations.len(), 2);
+
+        let distribution = Distribution {
+            denoms: vec!["rune".to_string()],
+            destinations: vec![
+                destination(Recipient::Bank {
+                    address: stranger.clone(),
+                }),
+                destination(Recipient::Bank { address: stranger }),
+            ],
+        }
+        .init(deps.as_ref(), &env, &affiliates)
+        .unwrap();
+
+        assert_eq!(distribution.destinations[2].shares, Uint128::new(25));
+    }
+
+    #[test]
+    fn distributed_since_splits_affiliate_fees() {
+        let deps = mock_dependencies();
+        let fee_collector = deps.api.addr_make("fee_collector");
+
+        let destination = |address, distributions: Option<Vec<Coin>>| Destination {
+            shares: Uint128::new(5_000),
+            recipient: Recipient::Bank { address },
+            label: None,
+            distributions,
+        };
+
+        let previous = Distribution {
+            denoms: vec!["rune".to_string()],
+            destinations: vec![
+                destination(
+                    deps.api.addr_make("payee"),
+                    Some(vec![Coin::new(100u128, "rune")]),
+                ),
+                destination(fee_collector.clone(), None),
+            ],
+        };
+
+        let current = Distribution {
+            denoms: vec!["rune".to_string()],
+            destinations: vec![
+                destination(
+                    deps.api.addr_make("payee"),
+                    Some(vec![Coin::new(250u128, "rune")]),
+                ),
+                destination(fee_collector.clone(), Some(vec![Coin::new(3u128, "rune")])),
+            ],
+        };
+
+        assert_eq!(
+            current
+                .distributed_since(
+                    &previous,
+                    &[Affiliate {
+                        label: "CALC".to_string(),
+                        address: fee_collector,
+                        bps: 25,
+                    }],
+                )
+                .unwrap(),
+            (
+                vec![Coin::new(150u128, "rune")],
+                vec![Coin::new(3u128, "rune")]
+            )
+        );
+    }
+}
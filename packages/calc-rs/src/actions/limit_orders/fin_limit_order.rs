@@ -2,17 +2,19 @@ use std::{cmp::min, vec};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_json_binary, Addr, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128,
+    to_json_binary, Addr, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult,
+    Timestamp, Uint128,
 };
 use rujira_rs::fin::{
     ConfigResponse, ExecuteMsg, OrderResponse, OrdersResponse, Price, QueryMsg, Side,
 };
 
 use crate::{
+    actions::swaps::swap::{Swap, SwapAmountAdjustment},
     core::{Amount, Contract},
     manager::Affiliate,
     operation::{Operation, StatefulOperation},
-    rujira::get_side_price,
+    rujira::{get_pair_denoms, get_side_price},
 };
 
 #[cw_serde]
@@ -36,12 +38,27 @@ pub enum PriceStrategy {
         offset: Offset,
         tolerance: Option<Offset>,
     },
+    // Offset in basis points from the pair oracle price, which Fin tracks natively
+    Oracle {
+        offset: i16,
+    },
 }
 
 impl PriceStrategy {
-    pub fn should_reset(&self, current_price: Decimal, new_price: Decimal) -> bool {
+    pub fn should_reset(&self, current_price: &Price, new_price: &Price) -> bool {
+        match (current_price, new_price) {
+            (Price::Fixed(current_price), Price::Fixed(new_price)) => {
+                self.should_reset_fixed(*current_price, *new_price)
+            }
+            // Oracle pegged orders follow the market without being reset,
+            // so they only move when the peg itself changes
+            _ => current_price != new_price,
+        }
+    }
+
+    fn should_reset_fixed(&self, current_price: Decimal, new_price: Decimal) -> bool {
         match self {
-            PriceStrategy::Fixed(_) => current_price != new_price,
+            PriceStrategy::Fixed(_) | PriceStrategy::Oracle { .. } => current_price != new_price,
             PriceStrategy::Offset { tolerance, .. } => {
                 if let Some(tolerance) = tolerance {
                     let price_delta = current_price.abs_diff(new_price);
@@ -58,9 +75,10 @@ impl PriceStrategy {
         }
     }
 
-    pub fn get_new_price(&self, deps: Deps, pair_address: &Addr) -> StdResult<Decimal> {
+    pub fn get_new_price(&self, deps: Deps, pair_address: &Addr) -> StdResult<Price> {
         Ok(match self {
-            PriceStrategy::Fixed(price) => *price,
+            PriceStrategy::Fixed(price) => Price::Fixed(*price),
+            PriceStrategy::Oracle { offset } => Price::Oracle(*offset),
             PriceStrategy::Offset {
                 side,
                 direction,
@@ -69,7 +87,7 @@ impl PriceStrategy {
             } => {
                 let price = get_side_price(deps, pair_address, side)?;
 
-                match offset.clone() {
+                Price::Fixed(match offset.clone() {
                     Offset::Exact(offset) => match direction {
                         Direction::Above => price.saturating_add(offset),
                         Direction::Below => price.saturating_sub(offset),
@@ -82,20 +100,115 @@ impl PriceStrategy {
                                 .saturating_mul(Decimal::percent(100u64.saturating_sub(offset))),
                         }
                     }
-                }
+                })
             }
         })
     }
 }
 
+#[cw_serde]
+pub enum TimeInForce {
+    Blocks(u64),
+    Seconds(u64),
+    Until(Timestamp),
+    // Expires once this share of the order has filled
+    FillRatio(Decimal),
+}
+
+impl TimeInForce {
+    pub fn expiry(&self, env: &Env) -> Option<Expiry> {
+        match self {
+            TimeInForce::Blocks(blocks) => {
+                Some(Expiry::AtHeight(env.block.height.saturating_add(*blocks)))
+            }
+            TimeInForce::Seconds(seconds) => {
+                Some(Expiry::AtTime(env.block.time.plus_seconds(*seconds)))
+            }
+            TimeInForce::Until(timestamp) => Some(Expiry::AtTime(*timestamp)),
+            TimeInForce::FillRatio(_) => None,
+        }
+    }
+}
+
+#[cw_serde]
+pub enum Expiry {
+    AtHeight(u64),
+    AtTime(Timestamp),
+}
+
+impl Expiry {
+    pub fn is_expired(&self, env: &Env) -> bool {
+        match self {
+            Expiry::AtHeight(height) => env.block.height >= *height,
+            Expiry::AtTime(time) => env.block.time >= *time,
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct LimitOrderStats {
+    // Bid denom sent into orders
+    pub offered: Uint128,
+    // Bid denom consumed by fills
+    pub filled: Uint128,
+    // Ask denom received for those fills
+    pub claimed: Uint128,
+}
+
+impl LimitOrderStats {
+    pub fn record_fills(&self, order: &SetOrder) -> LimitOrderStats {
+        self.record_claim(order.offer.saturating_sub(order.remaining), order.filled)
+    }
+
+    pub fn record_claim(&self, filled: Uint128, claimed: Uint128) -> LimitOrderStats {
+        LimitOrderStats {
+            offered: self.offered,
+            filled: self.filled.saturating_add(filled),
+            claimed: self.claimed.saturating_add(claimed),
+        }
+    }
+
+    pub fn record_offer(&self, offered: Uint128) -> LimitOrderStats {
+        LimitOrderStats {
+            offered: self.offered.saturating_add(offered),
+            ..self.clone()
+        }
+    }
+
+    pub fn average_price(&self, side: &Side) -> Option<Decimal> {
+        if self.filled.is_zero() || self.claimed.is_zero() {
+            return None;
+        }
+
+        // Prices are quoted in the quote denom per base denom
+        Some(match side {
+            Side::Base => Decimal::from_ratio(self.claimed, self.filled),
+            Side::Quote => Decimal::from_ratio(self.filled, self.claimed),
+        })
+    }
+}
+
 #[cw_serde]
 pub struct FinLimitOrder {
     pub pair_address: Addr,
+    // Base and quote denoms of the pair, resolved at init
+    #[serde(default)]
+    pub pair_denoms: Vec<String>,
     pub bid_denom: String,
     pub bid_amount: Amount,
     pub side: Side,
     pub strategy: PriceStrategy,
     pub min_fill_ratio: Option<Decimal>,
+    #[serde(default)]
+    pub time_in_force: Option<TimeInForce>,
+    #[serde(default)]
+    pub fallback: Option<Swap>,
+    // Lifetime cap on the bid denom consumed by fills
+    #[serde(default)]
+    pub budget: Option<Uint128>,
+    #[serde(default)]
+    pub stats: LimitOrderStats,
     pub current_order: Option<StaleOrder>,
 }
 
@@ -105,14 +218,91 @@ impl FinLimitOrder {
             .query_wasm_smart::<ConfigResponse>(self.pair_address.clone(), &QueryMsg::Config {})
     }
 
+    fn is_expired(&self, env: &Env, existing_order: &StaleOrder, order: &SetOrder) -> bool {
+        if let Some(TimeInForce::FillRatio(ratio)) = &self.time_in_force {
+            return !order.offer.is_zero()
+                && Decimal::one()
+                    .saturating_sub(Decimal::from_ratio(order.remaining, order.offer))
+                    >= *ratio;
+        }
+
+        existing_order
+            .expires
+            .as_ref()
+            .is_some_and(|expiry| expiry.is_expired(env))
+    }
+
+    fn is_budget_spent(&self) -> bool {
+        self.budget
+            .is_some_and(|budget| self.stats.filled >= budget)
+    }
+
+    fn expire(
+        self,
+        deps: Deps,
+        env: &Env,
+        order: SetOrder,
+    ) -> StdResult<(Vec<CosmosMsg>, FinLimitOrder)> {
+        // A spent budget stops the order without spending more on a fallback
+        let use_fallback = !self.is_budget_spent();
+
+        let (mut messages, withdrawn_order_state) = FinLimitOrderState {
+            config: self,
+            state: order,
+        }
+        .withdraw()?
+        .execute();
+
+        let withdrawing = withdrawn_order_state.state.withdrawing;
+        let mut limit_order = withdrawn_order_state.config;
+
+        if let Some(fallback) = limit_order.fallback.clone() {
+            if use_fallback && !withdrawing.is_zero() {
+                // The swap spends what the withdrawal releases, and a fallback
+                // that cannot be quoted leaves those funds in the strategy
+                if let Ok((swap_messages, fallback)) = fallback.execute_from(deps, env, withdrawing)
+                {
+                    messages.extend(swap_messages);
+                    limit_order.fallback = Some(fallback);
+                }
+            }
+        }
+
+        // We let the commit stage remove the expired order
+        Ok((messages, limit_order))
+    }
+
     fn execute_unsafe(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinLimitOrder)> {
         let mut messages = vec![];
 
+        // Resets and refills keep the expiry of the order when it was first placed
+        let expires = match &self.current_order {
+            Some(existing_order) => existing_order.expires.clone(),
+            None => self
+                .time_in_force
+                .as_ref()
+                .and_then(|time_in_force| time_in_force.expiry(env)),
+        };
+
+        let stats = self.stats.clone();
+
         let order = if let Some(existing_order) = self.current_order.clone() {
             let refreshed_order = existing_order.refresh(deps, env, &self)?;
 
+            // Fills are claimed whenever the order is touched, so they are
+            // recorded up front and dropped again below if it is not
+            let config = FinLimitOrder {
+                stats: stats.record_fills(&refreshed_order),
+                ..self
+            };
+
+            if config.is_expired(env, &existing_order, &refreshed_order) || config.is_budget_spent()
+            {
+                return config.expire(deps, env, refreshed_order);
+            }
+
             let existing_order_state = FinLimitOrderState {
-                config: self,
+                config,
                 state: refreshed_order,
             };
 
@@ -122,6 +312,8 @@ impl FinLimitOrder {
             messages.extend(withdraw_messages);
 
             withdrawn_order_state
+        } else if self.is_budget_spent() {
+            return Ok((vec![], self));
         } else {
             FinLimitOrderState::new(self)
         };
@@ -131,9 +323,17 @@ impl FinLimitOrder {
         messages.extend(set_messages);
 
         Ok((
-            messages,
+            messages.clone(),
             FinLimitOrder {
-                current_order: Some(set_order_state.state.cached()),
+                current_order: Some(StaleOrder {
+                    expires,
+                    ..set_order_state.state.cached()
+                }),
+                stats: if messages.is_empty() {
+                    stats
+                } else {
+                    set_order_state.config.stats.clone()
+                },
                 ..set_order_state.config
             },
         ))
@@ -148,14 +348,14 @@ pub struct UnsetOrder {
 
 #[cw_serde]
 pub struct SettingOrder {
-    pub price: Decimal,
+    pub price: Price,
     pub offer: Uint128,
     pub messages: Vec<CosmosMsg>,
 }
 
 #[cw_serde]
 pub struct SetOrder {
-    pub price: Decimal,
+    pub price: Price,
     pub offer: Uint128,
     pub remaining: Uint128,
     pub filled: Uint128,
@@ -164,21 +364,33 @@ pub struct SetOrder {
 #[cw_serde]
 pub struct StaleOrder {
     pub price: Decimal,
+    // Oracle pegged orders are keyed by their offset, and leave the price at zero
+    #[serde(default)]
+    pub oracle_offset: Option<i16>,
+    #[serde(default)]
+    pub expires: Option<Expiry>,
 }
 
 impl StaleOrder {
+    pub fn order_price(&self) -> Price {
+        match self.oracle_offset {
+            Some(offset) => Price::Oracle(offset),
+            None => Price::Fixed(self.price),
+        }
+    }
+
     pub fn refresh(self, deps: Deps, env: &Env, config: &FinLimitOrder) -> StdResult<SetOrder> {
         let order = deps.querier.query_wasm_smart::<OrderResponse>(
             &config.pair_address,
             &QueryMsg::Order((
                 env.contract.address.to_string(),
                 config.side.clone(),
-                Price::Fixed(self.price),
+                self.order_price(),
             )),
         )?;
 
         Ok(SetOrder {
-            price: self.price,
+            price: self.order_price(),
             offer: order.offer,
             remaining: order.remaining,
             filled: order.filled,
@@ -188,7 +400,18 @@ impl StaleOrder {
 
 impl SetOrder {
     pub fn cached(self) -> StaleOrder {
-        StaleOrder { price: self.price }
+        match self.price {
+            Price::Fixed(price) => StaleOrder {
+                price,
+                oracle_offset: None,
+                expires: None,
+            },
+            Price::Oracle(offset) => StaleOrder {
+                price: Decimal::zero(),
+                oracle_offset: Some(offset),
+                expires: None,
+            },
+        }
     }
 }
 
@@ -197,7 +420,7 @@ pub struct WithdrawingOrder {
     pub withdrawing: Uint128,
     pub remaining: Uint128,
     pub messages: Vec<CosmosMsg>,
-    pub new_price: Option<Decimal>,
+    pub new_price: Option<Price>,
 }
 
 #[cw_serde]
@@ -224,17 +447,19 @@ impl FinLimitOrderState<UnsetOrder> {
             .get_new_price(deps, &self.config.pair_address)?;
 
         let (should_reset, price) = if let Some(current_order) = &self.config.current_order {
+            let current_price = current_order.order_price();
+
             let should_reset = self
                 .config
                 .strategy
-                .should_reset(current_order.price, new_price);
+                .should_reset(&current_price, &new_price);
 
             (
                 should_reset,
                 if should_reset {
                     new_price
                 } else {
-                    current_order.price
+                    current_price
                 },
             )
         } else {
@@ -270,6 +495,11 @@ impl FinLimitOrderState<UnsetOrder> {
             Amount::Fraction(percent) => available.mul_floor(percent),
         };
 
+        let final_offer = match self.config.budget {
+            Some(budget) => min(final_offer, budget.saturating_sub(self.config.stats.filled)),
+            None => final_offer,
+        };
+
         let funding = min(balance.amount + self.state.withdrawing, final_offer);
 
         if funding.is_zero() && !should_reset {
@@ -285,18 +515,24 @@ impl FinLimitOrderState<UnsetOrder> {
 
         let set_order_msg = Contract(self.config.pair_address.clone()).call(
             to_json_binary(&ExecuteMsg::Order((
-                vec![(
-                    self.config.side.clone(),
-                    Price::Fixed(price),
-                    Some(final_offer),
-                )],
+                vec![(self.config.side.clone(), price.clone(), Some(final_offer))],
                 None,
             )))?,
             vec![Coin::new(funding, self.config.bid_denom.clone())],
         );
 
+        // Funds withdrawn from the previous order were counted when it was
+        // placed, so only what is newly added to the order is offered
+        let stats = self
+            .config
+            .stats
+            .record_offer(funding.saturating_sub(self.state.withdrawing));
+
         Ok(FinLimitOrderState {
-            config: self.config,
+            config: FinLimitOrder {
+                stats,
+                ..self.config
+            },
             state: SettingOrder {
                 price,
                 offer: final_offer,
@@ -329,7 +565,7 @@ impl FinLimitOrderState<SetOrder> {
             to_json_binary(&ExecuteMsg::Order((
                 vec![(
                     self.config.side.clone(),
-                    Price::Fixed(self.state.price),
+                    self.state.price.clone(),
                     Some(Uint128::zero()),
                 )],
                 None,
@@ -363,12 +599,12 @@ impl FinLimitOrderState<SetOrder> {
         let should_reset = self
             .config
             .strategy
-            .should_reset(self.state.price, new_price);
+            .should_reset(&self.state.price, &new_price);
 
         let price = if should_reset {
             new_price
         } else {
-            self.state.price
+            self.state.price.clone()
         };
 
         let should_withdraw = self.state.remaining.eq(&Uint128::zero())
@@ -416,7 +652,7 @@ impl FinLimitOrderState<WithdrawingOrder> {
 }
 
 impl Operation<FinLimitOrder> for FinLimitOrder {
-    fn init(self, _deps: Deps, _env: &Env, _affiliates: &[Affiliate]) -> StdResult<FinLimitOrder> {
+    fn init(self, deps: Deps, env: &Env, affiliates: &[Affiliate]) -> StdResult<FinLimitOrder> {
         match self.bid_amount {
             Amount::Fixed(amount) => {
                 if amount.lt(&Uint128::new(100)) {
@@ -441,6 +677,20 @@ impl Operation<FinLimitOrder> for FinLimitOrder {
             ));
         }
 
+        if self.stats != LimitOrderStats::default() {
+            return Err(StdError::generic_err(
+                "Cannot initialise a limit order action with existing fill statistics",
+            ));
+        }
+
+        if let Some(budget) = self.budget {
+            if budget.is_zero() {
+                return Err(StdError::generic_err(
+                    "Limit order budget must be greater than zero",
+                ));
+            }
+        }
+
         if let Some(min_fill_ratio) = self.min_fill_ratio {
             if min_fill_ratio.gt(&Decimal::one()) {
                 return Err(StdError::generic_err(
@@ -449,7 +699,73 @@ impl Operation<FinLimitOrder> for FinLimitOrder {
             }
         }
 
-        Ok(self)
+        if let PriceStrategy::Oracle { offset } = self.strategy {
+            if offset <= -10_000 {
+                return Err(StdError::generic_err(
+                    "Oracle offset must be greater than -10000 basis points",
+                ));
+            }
+
+            if self.get_pair(deps)?.oracles.is_none() {
+                return Err(StdError::generic_err(
+                    "Pair does not have oracles configured for oracle pegged orders",
+                ));
+            }
+        }
+
+        match self.time_in_force {
+            Some(TimeInForce::Blocks(0)) | Some(TimeInForce::Seconds(0)) => {
+                return Err(StdError::generic_err(
+                    "Time in force duration must be greater than zero",
+                ));
+            }
+            Some(TimeInForce::Until(timestamp)) if timestamp <= env.block.time => {
+                return Err(StdError::generic_err(
+                    "Time in force expiry must be in the future",
+                ));
+            }
+            Some(TimeInForce::FillRatio(ratio)) if ratio.is_zero() || ratio > Decimal::one() => {
+                return Err(StdError::generic_err(
+                    "Time in force fill ratio must be between 0 and 1",
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(fallback) = &self.fallback {
+            if self.time_in_force.is_none() {
+                return Err(StdError::generic_err(
+                    "Limit order fallback requires a time in force",
+                ));
+            }
+
+            if fallback.swap_amount.denom != self.bid_denom {
+                return Err(StdError::generic_err(
+                    "Limit order fallback must swap the bid denom",
+                ));
+            }
+
+            // Fallbacks spend funds released by the order, so the units a value
+            // averaging fallback buys cannot be told apart from the order's fills
+            if matches!(
+                fallback.adjustment,
+                SwapAmountAdjustment::ValueAverage { .. }
+            ) {
+                return Err(StdError::generic_err(
+                    "Limit order fallback cannot use value averaging",
+                ));
+            }
+        }
+
+        Ok(FinLimitOrder {
+            pair_denoms: get_pair_denoms(deps, &self.pair_address, &self.pair_denoms)?,
+            fallback: self
+                .fallback
+                .clone()
+                .map(|fallback| fallback.init(deps, env, affiliates))
+                .transpose()?,
+            ..self
+        })
     }
 
     fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinLimitOrder)> {
@@ -483,10 +799,11 @@ impl StatefulOperation<FinLimitOrder> for FinLimitOrder {
                 state: existing_order.refresh(deps, env, &self)?,
             };
 
+            let stats = self.stats.record_fills(&order_state.state);
             let (messages, _) = order_state.withdraw()?.execute();
 
             // We let the commit stage remove the current order
-            Ok((messages, self))
+            Ok((messages, FinLimitOrder { stats, ..self }))
         } else {
             Ok((vec![], self))
         }
@@ -507,3 +824,633 @@ impl StatefulOperation<FinLimitOrder> for FinLimitOrder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        ContractResult, QuerierResult, SystemResult, WasmQuery,
+    };
+    use rujira_rs::fin::{
+        BookItemResponse, BookResponse, Denoms, SimulationResponse, SwapRequest, Tick,
+    };
+
+    use super::*;
+    use crate::actions::swaps::{
+        fin::FinRoute,
+        swap::{SwapAmountAdjustment, SwapRoute, SwapRouting},
+    };
+
+    fn pair_config() -> ConfigResponse {
+        ConfigResponse {
+            denoms: Denoms::new("rune", "x/ruji"),
+            oracles: None,
+            market_makers: vec![],
+            tick: Tick::new(6),
+            range_delta: Decimal::zero(),
+            range_min: Decimal::one(),
+            fee_taker: Decimal::percent(1),
+            fee_maker: Decimal::percent(1),
+            fee_range: Decimal::zero(),
+            fee_address: "feetaker".to_string(),
+        }
+    }
+
+    fn oracle_order_response(remaining: u128) -> OrderResponse {
+        OrderResponse {
+            owner: mock_env().contract.address.to_string(),
+            side: Side::Base,
+            price: Price::Oracle(25),
+            rate: Some(Decimal::one()),
+            updated_at: mock_env().block.time,
+            offer: Uint128::new(1_000),
+            remaining: Uint128::new(remaining),
+            filled: Uint128::new(1_000 - remaining),
+        }
+    }
+
+    #[test]
+    fn oracle_pegged_orders_rest_without_resets() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "rune")]);
+
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Orders { .. } => to_json_binary(&OrdersResponse {
+                        orders: vec![oracle_order_response(1_000)],
+                    })
+                    .unwrap(),
+                    QueryMsg::Order((_, side, price)) => {
+                        assert_eq!((side, price), (Side::Base, Price::Oracle(25)));
+                        to_json_binary(&oracle_order_response(1_000)).unwrap()
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let limit_order = FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fixed(Uint128::new(1_000)),
+            side: Side::Base,
+            strategy: PriceStrategy::Oracle { offset: 25 },
+            min_fill_ratio: None,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats: LimitOrderStats::default(),
+            current_order: None,
+        };
+
+        assert!(limit_order
+            .clone()
+            .init(deps.as_ref(), &env, &[])
+            .unwrap_err()
+            .to_string()
+            .contains("Pair does not have oracles configured"));
+
+        let (messages, limit_order) = limit_order.execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![(Side::Base, Price::Oracle(25), Some(Uint128::new(1_000)))],
+                    None,
+                )))
+                .unwrap(),
+                vec![Coin::new(1_000u128, "rune")],
+            )]
+        );
+
+        assert_eq!(
+            limit_order.current_order,
+            Some(StaleOrder {
+                price: Decimal::zero(),
+                oracle_offset: Some(25),
+                expires: None,
+            })
+        );
+
+        // The funds now rest in the order, which follows the oracle on its own
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![]);
+
+        let (messages, limit_order) = limit_order.execute(deps.as_ref(), &env).unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(
+            limit_order.current_order.unwrap().order_price(),
+            Price::Oracle(25)
+        );
+    }
+
+    #[test]
+    fn expired_orders_are_withdrawn_instead_of_reset() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "rune")]);
+
+        let resting_order = OrderResponse {
+            owner: env.contract.address.to_string(),
+            side: Side::Base,
+            price: Price::Fixed(Decimal::one()),
+            rate: Some(Decimal::one()),
+            updated_at: env.block.time,
+            offer: Uint128::new(1_000),
+            remaining: Uint128::new(600),
+            filled: Uint128::new(400),
+        };
+
+        deps.querier.update_wasm(move |query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Orders { .. } => to_json_binary(&OrdersResponse {
+                        orders: vec![resting_order.clone()],
+                    })
+                    .unwrap(),
+                    QueryMsg::Order(_) => to_json_binary(&resting_order).unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let limit_order = FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fixed(Uint128::new(1_000)),
+            side: Side::Base,
+            strategy: PriceStrategy::Fixed(Decimal::one()),
+            min_fill_ratio: Some(Decimal::percent(50)),
+            time_in_force: Some(TimeInForce::Blocks(10)),
+            fallback: None,
+            budget: None,
+            stats: LimitOrderStats::default(),
+            current_order: None,
+        };
+
+        let (_, placed_order) = limit_order.clone().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            placed_order.current_order.clone().unwrap().expires,
+            Some(Expiry::AtHeight(env.block.height + 10))
+        );
+
+        let withdraw_message = Contract(Addr::unchecked("pair")).call(
+            to_json_binary(&ExecuteMsg::Order((
+                vec![(
+                    Side::Base,
+                    Price::Fixed(Decimal::one()),
+                    Some(Uint128::zero()),
+                )],
+                None,
+            )))
+            .unwrap(),
+            vec![],
+        );
+
+        // Before expiry the partially filled order keeps resting, and is only
+        // topped up from the strategy balance
+        let (messages, _) = placed_order.clone().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![(
+                        Side::Base,
+                        Price::Fixed(Decimal::one()),
+                        Some(Uint128::new(1_000)),
+                    )],
+                    None,
+                )))
+                .unwrap(),
+                vec![Coin::new(1_000u128, "rune")],
+            )]
+        );
+
+        let mut expired_env = env.clone();
+        expired_env.block.height += 10;
+
+        let (messages, expired_order) = placed_order.execute(deps.as_ref(), &expired_env).unwrap();
+
+        assert_eq!(messages, vec![withdraw_message.clone()]);
+        assert!(expired_order.current_order.is_some());
+
+        // Good till filled ratio expires once 40% has filled
+        let (messages, _) = FinLimitOrder {
+            time_in_force: Some(TimeInForce::FillRatio(Decimal::percent(40))),
+            current_order: Some(StaleOrder {
+                price: Decimal::one(),
+                oracle_offset: None,
+                expires: None,
+            }),
+            ..limit_order.clone()
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        assert_eq!(messages, vec![withdraw_message]);
+
+        assert!(FinLimitOrder {
+            time_in_force: None,
+            fallback: Some(Swap {
+                swap_amount: Coin::new(1_000u128, "rune"),
+                minimum_receive_amount: Coin::new(900u128, "x/ruji"),
+                maximum_slippage_bps: 100,
+                adjustment: SwapAmountAdjustment::Fixed,
+                routes: vec![],
+                routing: SwapRouting::Best,
+            }),
+            ..limit_order
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("fallback requires a time in force"));
+    }
+
+    fn fallback_querier(simulated_return: u128) -> impl Fn(&WasmQuery) -> QuerierResult + 'static {
+        move |query| {
+            let env = mock_env();
+
+            let resting_order = OrderResponse {
+                owner: env.contract.address.to_string(),
+                side: Side::Base,
+                price: Price::Fixed(Decimal::one()),
+                rate: Some(Decimal::one()),
+                updated_at: env.block.time,
+                offer: Uint128::new(1_000),
+                remaining: Uint128::new(600),
+                filled: Uint128::new(400),
+            };
+
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Orders { .. } => to_json_binary(&OrdersResponse {
+                        orders: vec![resting_order],
+                    })
+                    .unwrap(),
+                    QueryMsg::Order(_) => to_json_binary(&resting_order).unwrap(),
+                    QueryMsg::Simulate(_) => to_json_binary(&SimulationResponse {
+                        returned: Uint128::new(simulated_return),
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { .. } => to_json_binary(&BookResponse {
+                        base: vec![BookItemResponse {
+                            price: Decimal::one(),
+                            total: Uint128::new(1_000_000),
+                        }],
+                        quote: vec![BookItemResponse {
+                            price: Decimal::one(),
+                            total: Uint128::new(1_000_000),
+                        }],
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        }
+    }
+
+    fn expired_order_with_fallback() -> FinLimitOrder {
+        FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fixed(Uint128::new(1_000)),
+            side: Side::Base,
+            strategy: PriceStrategy::Fixed(Decimal::one()),
+            min_fill_ratio: None,
+            time_in_force: Some(TimeInForce::Blocks(10)),
+            fallback: Some(Swap {
+                swap_amount: Coin::new(1_000u128, "rune"),
+                minimum_receive_amount: Coin::new(500u128, "x/ruji"),
+                maximum_slippage_bps: 100,
+                adjustment: SwapAmountAdjustment::Fixed,
+                routes: vec![SwapRoute::Fin(FinRoute {
+                    pair_address: Addr::unchecked("pair"),
+                })],
+                routing: SwapRouting::Best,
+            }),
+            budget: None,
+            stats: LimitOrderStats::default(),
+            current_order: Some(StaleOrder {
+                price: Decimal::one(),
+                oracle_offset: None,
+                expires: Some(Expiry::AtHeight(mock_env().block.height)),
+            }),
+        }
+    }
+
+    #[test]
+    fn expired_orders_swap_released_funds_with_fallback() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(fallback_querier(600));
+
+        let (messages, expired_order) = expired_order_with_fallback()
+            .execute(deps.as_ref(), &env)
+            .unwrap();
+
+        // The released funds are not in the strategy balance yet, so the
+        // swap is sized from the withdrawal, with its minimum scaled to match
+        assert_eq!(
+            messages,
+            vec![
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Base,
+                            Price::Fixed(Decimal::one()),
+                            Some(Uint128::zero()),
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![],
+                ),
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Swap(SwapRequest::Min {
+                        min_return: Uint128::new(300),
+                        to: None,
+                        callback: None,
+                    }))
+                    .unwrap(),
+                    vec![Coin::new(600u128, "rune")],
+                ),
+            ]
+        );
+
+        assert!(expired_order.current_order.is_some());
+    }
+
+    #[test]
+    fn expired_orders_keep_released_funds_if_fallback_cannot_quote() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // Nothing returned is below the fallback's minimum receive amount
+        deps.querier.update_wasm(fallback_querier(0));
+
+        let limit_order = expired_order_with_fallback();
+
+        let (messages, expired_order) = limit_order.clone().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![(
+                        Side::Base,
+                        Price::Fixed(Decimal::one()),
+                        Some(Uint128::zero()),
+                    )],
+                    None,
+                )))
+                .unwrap(),
+                vec![],
+            )]
+        );
+
+        assert_eq!(expired_order.fallback, limit_order.fallback);
+    }
+
+    #[test]
+    fn budget_caps_offers_and_stops_once_spent() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "rune")]);
+
+        let resting_order = OrderResponse {
+            owner: env.contract.address.to_string(),
+            side: Side::Base,
+            price: Price::Fixed(Decimal::one()),
+            rate: Some(Decimal::one()),
+            updated_at: env.block.time,
+            offer: Uint128::new(500),
+            remaining: Uint128::new(100),
+            filled: Uint128::new(400),
+        };
+
+        deps.querier.update_wasm(move |query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Orders { .. } => to_json_binary(&OrdersResponse {
+                        orders: vec![resting_order.clone()],
+                    })
+                    .unwrap(),
+                    QueryMsg::Order(_) => to_json_binary(&resting_order).unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let limit_order = FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fixed(Uint128::new(1_000)),
+            side: Side::Base,
+            strategy: PriceStrategy::Fixed(Decimal::one()),
+            min_fill_ratio: None,
+            time_in_force: None,
+            fallback: None,
+            budget: Some(Uint128::new(500)),
+            stats: LimitOrderStats::default(),
+            current_order: None,
+        };
+
+        let (messages, placed_order) = limit_order.clone().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![(
+                        Side::Base,
+                        Price::Fixed(Decimal::one()),
+                        Some(Uint128::new(500)),
+                    )],
+                    None,
+                )))
+                .unwrap(),
+                vec![Coin::new(500u128, "rune")],
+            )]
+        );
+        assert_eq!(placed_order.stats.offered, Uint128::new(500));
+
+        // Fills reaching the budget withdraw the order for good
+        let (messages, spent_order) = FinLimitOrder {
+            budget: Some(Uint128::new(400)),
+            ..placed_order
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![(
+                        Side::Base,
+                        Price::Fixed(Decimal::one()),
+                        Some(Uint128::zero()),
+                    )],
+                    None,
+                )))
+                .unwrap(),
+                vec![],
+            )]
+        );
+        assert_eq!(
+            spent_order.stats,
+            LimitOrderStats {
+                offered: Uint128::new(500),
+                filled: Uint128::new(400),
+                claimed: Uint128::new(400),
+            }
+        );
+        assert_eq!(
+            spent_order.stats.average_price(&Side::Base),
+            Some(Decimal::one())
+        );
+
+        let (messages, _) = FinLimitOrder {
+            current_order: None,
+            ..spent_order
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn resets_only_count_new_funding_as_offered() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(100u128, "rune")]);
+
+        let resting_order = OrderResponse {
+            owner: env.contract.address.to_string(),
+            side: Side::Base,
+            price: Price::Fixed(Decimal::one()),
+            rate: Some(Decimal::one()),
+            updated_at: env.block.time,
+            offer: Uint128::new(500),
+            remaining: Uint128::new(400),
+            filled: Uint128::new(100),
+        };
+
+        deps.querier.update_wasm(move |query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Orders { .. } => to_json_binary(&OrdersResponse {
+                        orders: vec![resting_order.clone()],
+                    })
+                    .unwrap(),
+                    QueryMsg::Order(_) => to_json_binary(&resting_order).unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let limit_order = FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fixed(Uint128::new(1_000)),
+            side: Side::Base,
+            strategy: PriceStrategy::Fixed(Decimal::percent(200)),
+            min_fill_ratio: None,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats: LimitOrderStats {
+                offered: Uint128::new(500),
+                ..LimitOrderStats::default()
+            },
+            current_order: Some(StaleOrder {
+                price: Decimal::one(),
+                oracle_offset: None,
+                expires: None,
+            }),
+        };
+
+        let (messages, reset_order) = limit_order.execute(deps.as_ref(), &env).unwrap();
+
+        // The 400 rune withdrawn from the old order is re-offered alongside
+        // the 100 rune balance at the new price
+        assert_eq!(
+            messages,
+            vec![
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Base,
+                            Price::Fixed(Decimal::one()),
+                            Some(Uint128::zero()),
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![],
+                ),
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Base,
+                            Price::Fixed(Decimal::percent(200)),
+                            Some(Uint128::new(500)),
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![Coin::new(500u128, "rune")],
+                ),
+            ]
+        );
+
+        // Only the 100 rune added from the balance is newly offered
+        assert_eq!(
+            reset_order.stats,
+            LimitOrderStats {
+                offered: Uint128::new(600),
+                filled: Uint128::new(100),
+                claimed: Uint128::new(100),
+            }
+        );
+    }
+}
@@ -0,0 +1,976 @@
+use std::{cmp::Ordering, vec};
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128,
+};
+use rujira_rs::fin::{
+    ConfigResponse, Denoms, ExecuteMsg, OrderResponse, Price, QueryMsg, Side, Tick,
+};
+
+use crate::{
+    actions::limit_orders::fin_limit_order::LimitOrderStats,
+    constants::MAX_LADDER_LEVELS,
+    core::Contract,
+    manager::Affiliate,
+    operation::{Operation, StatefulOperation},
+    rujira::{get_mid_price, get_pair_denoms},
+};
+
+const RATIO_ITERATIONS: u8 = 100;
+
+#[cw_serde]
+pub enum Spacing {
+    Arithmetic,
+    Geometric,
+}
+
+#[cw_serde]
+pub enum LadderSize {
+    Fixed { base: Uint128, quote: Uint128 },
+    Even,
+}
+
+#[cw_serde]
+pub struct LadderOrder {
+    pub side: Side,
+    pub price: Decimal,
+}
+
+impl LadderOrder {
+    pub fn refresh(&self, deps: Deps, env: &Env, pair_address: &Addr) -> StdResult<OrderResponse> {
+        deps.querier.query_wasm_smart::<OrderResponse>(
+            pair_address,
+            &QueryMsg::Order((
+                env.contract.address.to_string(),
+                self.side.clone(),
+                Price::Fixed(self.price),
+            )),
+        )
+    }
+}
+
+#[cw_serde]
+pub struct FinLimitOrderLadder {
+    pub pair_address: Addr,
+    // Base and quote denoms of the pair, resolved at init
+    #[serde(default)]
+    pub pair_denoms: Vec<String>,
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    pub levels: u16,
+    pub spacing: Spacing,
+    pub size: LadderSize,
+    pub orders: Vec<LadderOrder>,
+    // Fills of the levels bidding for the base asset
+    #[serde(default)]
+    pub bid_stats: LimitOrderStats,
+    // Fills of the levels offering the base asset
+    #[serde(default)]
+    pub ask_stats: LimitOrderStats,
+}
+
+fn offer_denom(denoms: &Denoms, side: &Side) -> String {
+    match side {
+        Side::Base => denoms.base().to_string(),
+        Side::Quote => denoms.quote().to_string(),
+    }
+}
+
+impl FinLimitOrderLadder {
+    pub fn get_pair(&self, deps: Deps) -> StdResult<ConfigResponse> {
+        deps.querier
+            .query_wasm_smart::<ConfigResponse>(self.pair_address.clone(), &QueryMsg::Config {})
+    }
+
+    fn stats_mut(&mut self, side: &Side) -> &mut LimitOrderStats {
+        match side {
+            Side::Quote => &mut self.bid_stats,
+            Side::Base => &mut self.ask_stats,
+        }
+    }
+
+    pub fn prices(&self, tick: &Tick) -> StdResult<Vec<Decimal>> {
+        let steps = self.levels - 1;
+
+        let prices = match self.spacing {
+            Spacing::Arithmetic => (0..self.levels)
+                .map(|level| {
+                    self.lower_price
+                        + (self.upper_price - self.lower_price) * Decimal::from_ratio(level, steps)
+                })
+                .collect::<Vec<_>>(),
+            Spacing::Geometric => {
+                let ratio = self.geometric_ratio();
+
+                (0..self.levels)
+                    .map(|level| {
+                        if level == steps {
+                            Ok(self.upper_price)
+                        } else {
+                            Ok(self.lower_price * ratio.checked_pow(level as u32)?)
+                        }
+                    })
+                    .collect::<StdResult<Vec<_>>>()?
+            }
+        }
+        .iter()
+        .map(|price| tick.truncate_floor(price))
+        .collect::<Vec<_>>();
+
+        if prices.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(StdError::generic_err(
+                "Ladder price levels must be distinct at the pair tick size",
+            ));
+        }
+
+        Ok(prices)
+    }
+
+    fn geometric_ratio(&self) -> Decimal {
+        let target = self.upper_price / self.lower_price;
+        let steps = (self.levels - 1) as u32;
+
+        let mut low = Decimal::one();
+        let mut high = target;
+
+        // Decimal has no fractional powers, so the ratio is found by bisection.
+        // The upper bound is used so tick truncation cannot pull a level down
+        // past its true price.
+        for _ in 0..RATIO_ITERATIONS {
+            let mid = (low + high) / Decimal::from_ratio(2u128, 1u128);
+
+            match mid.checked_pow(steps) {
+                Ok(value) if value <= target => low = mid,
+                _ => high = mid,
+            }
+        }
+
+        high
+    }
+
+    pub fn order_balances(&self, deps: Deps, env: &Env) -> StdResult<Vec<(LadderOrder, Coins)>> {
+        if self.orders.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let pair = self.get_pair(deps)?;
+
+        self.orders
+            .iter()
+            .map(|order| {
+                let state = order.refresh(deps, env, &self.pair_address)?;
+
+                Ok((
+                    order.clone(),
+                    Coins::try_from(vec![
+                        Coin::new(state.remaining, offer_denom(&pair.denoms, &order.side)),
+                        Coin::new(state.filled, pair.denoms.ask(&order.side)),
+                    ])?,
+                ))
+            })
+            .collect()
+    }
+
+    fn order_message(
+        &self,
+        orders: Vec<(Side, Price, Option<Uint128>)>,
+        funds: Coins,
+    ) -> StdResult<CosmosMsg> {
+        Ok(Contract(self.pair_address.clone()).call(
+            to_json_binary(&ExecuteMsg::Order((orders, None)))?,
+            funds.into_vec(),
+        ))
+    }
+
+    fn withdraw_message(&self, orders: &[LadderOrder]) -> StdResult<CosmosMsg> {
+        self.order_message(
+            orders
+                .iter()
+                .map(|order| {
+                    (
+                        order.side.clone(),
+                        Price::Fixed(order.price),
+                        Some(Uint128::zero()),
+                    )
+                })
+                .collect(),
+            Coins::default(),
+        )
+    }
+
+    fn place(
+        mut self,
+        deps: Deps,
+        env: &Env,
+        pair: &ConfigResponse,
+    ) -> StdResult<(Vec<CosmosMsg>, FinLimitOrderLadder)> {
+        let prices = self.prices(&pair.tick)?;
+        let mid_price = get_mid_price(deps, &self.pair_address)?;
+
+        // Levels below the mid price bid for the base asset and levels above it
+        // offer it, so the ladder never crosses the book when it is placed
+        let levels = prices
+            .iter()
+            .filter_map(|price| match price.cmp(&mid_price) {
+                Ordering::Less => Some(LadderOrder {
+                    side: Side::Quote,
+                    price: *price,
+                }),
+                Ordering::Greater => Some(LadderOrder {
+                    side: Side::Base,
+                    price: *price,
+                }),
+                Ordering::Equal => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut orders = vec![];
+        let mut entries = vec![];
+        let mut funds = Coins::default();
+
+        for side in [Side::Base, Side::Quote] {
+            let mut side_levels = levels
+                .iter()
+                .filter(|order| order.side == side)
+                .collect::<Vec<_>>();
+
+            if side_levels.is_empty() {
+                continue;
+            }
+
+            // Fund the levels closest to the mid price first
+            if side == Side::Quote {
+                side_levels.reverse();
+            }
+
+            let denom = offer_denom(&pair.denoms, &side);
+
+            let mut available = deps
+                .querier
+                .query_balance(&env.contract.address, &denom)?
+                .amount;
+
+            let size = match &self.size {
+                LadderSize::Fixed { base, quote } => match side {
+                    Side::Base => *base,
+                    Side::Quote => *quote,
+                },
+                LadderSize::Even => available / Uint128::new(side_levels.len() as u128),
+            };
+
+            for order in side_levels {
+                let amount = size.min(available);
+
+                if amount.is_zero() {
+                    break;
+                }
+
+                available -= amount;
+
+                let stats = self.stats_mut(&side);
+                *stats = stats.record_offer(amount);
+
+                entries.push((side.clone(), Price::Fixed(order.price), Some(amount)));
+                funds.add(Coin::new(amount, denom.clone()))?;
+                orders.push(order.clone());
+            }
+        }
+
+        let messages = if entries.is_empty() {
+            vec![]
+        } else {
+            vec![self.order_message(entries, funds)?]
+        };
+
+        Ok((messages, FinLimitOrderLadder { orders, ..self }))
+    }
+
+    fn rebalance(
+        mut self,
+        deps: Deps,
+        env: &Env,
+        pair: &ConfigResponse,
+    ) -> StdResult<(Vec<CosmosMsg>, FinLimitOrderLadder)> {
+        let prices = self.prices(&pair.tick)?;
+
+        let mut resting = vec![];
+        let mut filled = vec![];
+        let mut claims = vec![];
+
+        for order in self.orders.iter() {
+            let state = match order.refresh(deps, env, &self.pair_address) {
+                Ok(state) => state,
+                // Orders withdrawn outside of the strategy are dropped
+                Err(_) => continue,
+            };
+
+            if !state.remaining.is_zero() {
+                resting.push((order.clone(), state.remaining));
+
+                if state.filled.is_zero() {
+                    continue;
+                }
+            }
+
+            // Fully filled levels are withdrawn, and partially filled levels
+            // are set to their remaining amount, which claims the fill
+            claims.push((
+                order.side.clone(),
+                Price::Fixed(order.price),
+                Some(state.remaining),
+            ));
+
+            if !state.filled.is_zero() {
+                filled.push((order.clone(), state));
+            }
+        }
+
+        let mut messages = vec![];
+
+        if !claims.is_empty() {
+            messages.push(self.order_message(claims, Coins::default())?);
+        }
+
+        let mut entries = vec![];
+        let mut funds = Coins::default();
+
+        for (order, state) in filled {
+            let amount = state.filled;
+
+            let stats = self.stats_mut(&order.side);
+            *stats = stats.record_claim(state.offer.saturating_sub(state.remaining), amount);
+
+            let level = match prices.iter().position(|price| *price == order.price) {
+                Some(level) => level,
+                None => continue,
+            };
+
+            // A filled bid is offered one level up and a filled offer is bid
+            // one level down, so each round trip captures the level spacing
+            let (side, next_level) = match order.side {
+                Side::Quote => (Side::Base, level.checked_add(1)),
+                Side::Base => (Side::Quote, level.checked_sub(1)),
+            };
+
+            let price = match next_level.and_then(|level| prices.get(level)) {
+                Some(price) => *price,
+                // Proceeds from the edges of the ladder stay in the strategy
+                None => continue,
+            };
+
+            let target = match resting.iter_mut().find(|(resting_order, _)| {
+                resting_order.side == side && resting_order.price == price
+            }) {
+                Some((_, remaining)) => {
+                    *remaining += amount;
+                    *remaining
+                }
+                None => {
+                    resting.push((
+                        LadderOrder {
+                            side: side.clone(),
+                            price,
+                        },
+                        amount,
+                    ));
+                    amount
+                }
+            };
+
+            let stats = self.stats_mut(&side);
+            *stats = stats.record_offer(amount);
+
+            entries.push((side, Price::Fixed(price), Some(target)));
+            funds.add(Coin::new(amount, pair.denoms.ask(&order.side)))?;
+        }
+
+        if !entries.is_empty() {
+            messages.push(self.order_message(entries, funds)?);
+        }
+
+        Ok((
+            messages,
+            FinLimitOrderLadder {
+                orders: resting.into_iter().map(|(order, _)| order).collect(),
+                ..self
+            },
+        ))
+    }
+}
+
+impl Operation<FinLimitOrderLadder> for FinLimitOrderLadder {
+    fn init(
+        self,
+        deps: Deps,
+        _env: &Env,
+        _affiliates: &[Affiliate],
+    ) -> StdResult<FinLimitOrderLadder> {
+        if self.levels < 2 || self.levels > MAX_LADDER_LEVELS {
+            return Err(StdError::generic_err(format!(
+                "Limit order ladder must have between 2 and {MAX_LADDER_LEVELS} levels"
+            )));
+        }
+
+        if self.lower_price.is_zero() || self.lower_price >= self.upper_price {
+            return Err(StdError::generic_err(
+                "Ladder lower price must be greater than zero and less than the upper price",
+            ));
+        }
+
+        if let LadderSize::Fixed { base, quote } = &self.size {
+            if base.lt(&Uint128::new(100)) || quote.lt(&Uint128::new(100)) {
+                return Err(StdError::generic_err(
+                    "Ladder level sizes must be greater than or equal to 100",
+                ));
+            }
+        }
+
+        if !self.orders.is_empty() {
+            return Err(StdError::generic_err(
+                "Cannot initialise a limit order ladder with orders already set.",
+            ));
+        }
+
+        if self.bid_stats != LimitOrderStats::default()
+            || self.ask_stats != LimitOrderStats::default()
+        {
+            return Err(StdError::generic_err(
+                "Cannot initialise a limit order ladder with existing fill statistics",
+            ));
+        }
+
+        Ok(FinLimitOrderLadder {
+            pair_denoms: get_pair_denoms(deps, &self.pair_address, &self.pair_denoms)?,
+            ..self
+        })
+    }
+
+    fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinLimitOrderLadder)> {
+        let pair = self.get_pair(deps)?;
+
+        if self.orders.is_empty() {
+            self.place(deps, env, &pair)
+        } else {
+            self.rebalance(deps, env, &pair)
+        }
+    }
+}
+
+impl StatefulOperation<FinLimitOrderLadder> for FinLimitOrderLadder {
+    fn balances(&self, deps: Deps, env: &Env) -> StdResult<Coins> {
+        let mut balances = Coins::default();
+
+        for (_, order_balances) in self.order_balances(deps, env)? {
+            for balance in order_balances.into_vec() {
+                balances.add(balance)?;
+            }
+        }
+
+        Ok(balances)
+    }
+
+    fn cancel(mut self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinLimitOrderLadder)> {
+        let mut existing_orders = vec![];
+
+        for order in self.orders.clone() {
+            if let Ok(state) = order.refresh(deps, env, &self.pair_address) {
+                // Withdrawing claims any fills
+                let stats = self.stats_mut(&order.side);
+                *stats =
+                    stats.record_claim(state.offer.saturating_sub(state.remaining), state.filled);
+
+                existing_orders.push(order);
+            }
+        }
+
+        if existing_orders.is_empty() {
+            return Ok((vec![], self));
+        }
+
+        let message = self.withdraw_message(&existing_orders)?;
+
+        // We let the commit stage remove the withdrawn orders
+        Ok((vec![message], self))
+    }
+
+    fn commit(self, deps: Deps, env: &Env) -> StdResult<FinLimitOrderLadder> {
+        let orders = self
+            .orders
+            .iter()
+            // Wipe cached orders that no longer exist
+            .filter(|order| order.refresh(deps, env, &self.pair_address).is_ok())
+            .cloned()
+            .collect();
+
+        Ok(FinLimitOrderLadder { orders, ..self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        ContractResult, SystemResult, WasmQuery,
+    };
+    use rujira_rs::fin::{BookItemResponse, BookResponse};
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn ladder(spacing: Spacing) -> FinLimitOrderLadder {
+        FinLimitOrderLadder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            lower_price: dec("0.8"),
+            upper_price: dec("1.2"),
+            levels: 5,
+            spacing,
+            size: LadderSize::Even,
+            orders: vec![],
+            bid_stats: LimitOrderStats::default(),
+            ask_stats: LimitOrderStats::default(),
+        }
+    }
+
+    fn pair_config() -> ConfigResponse {
+        ConfigResponse {
+            denoms: Denoms::new("rune", "x/ruji"),
+            oracles: None,
+            market_makers: vec![],
+            tick: Tick::new(6),
+            range_delta: Decimal::zero(),
+            range_min: Decimal::one(),
+            fee_taker: Decimal::percent(1),
+            fee_maker: Decimal::percent(1),
+            fee_range: Decimal::zero(),
+            fee_address: "feetaker".to_string(),
+        }
+    }
+
+    fn order_response(side: Side, price: &str, remaining: u128, filled: u128) -> OrderResponse {
+        OrderResponse {
+            owner: mock_env().contract.address.to_string(),
+            side,
+            price: Price::Fixed(dec(price)),
+            rate: Some(dec(price)),
+            updated_at: mock_env().block.time,
+            offer: Uint128::new(remaining + filled),
+            remaining: Uint128::new(remaining),
+            filled: Uint128::new(filled),
+        }
+    }
+
+    #[test]
+    fn init_validates_ladder() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&pair_config()).unwrap()))
+        });
+
+        assert_eq!(
+            ladder(Spacing::Arithmetic)
+                .init(deps.as_ref(), &env, &[])
+                .unwrap()
+                .pair_denoms,
+            vec!["rune".to_string(), "x/ruji".to_string()]
+        );
+
+        assert!(FinLimitOrderLadder {
+            pair_denoms: vec!["rune".to_string(), "x/other".to_string()],
+            ..ladder(Spacing::Arithmetic)
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("trades rune and x/ruji"));
+
+        assert!(FinLimitOrderLadder {
+            levels: MAX_LADDER_LEVELS + 1,
+            ..ladder(Spacing::Arithmetic)
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("must have between 2 and"));
+
+        assert!(FinLimitOrderLadder {
+            lower_price: dec("1.2"),
+            ..ladder(Spacing::Arithmetic)
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("less than the upper price"));
+
+        assert!(FinLimitOrderLadder {
+            size: LadderSize::Fixed {
+                base: Uint128::new(99),
+                quote: Uint128::new(1_000),
+            },
+            ..ladder(Spacing::Arithmetic)
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("greater than or equal to 100"));
+
+        assert!(FinLimitOrderLadder {
+            orders: vec![LadderOrder {
+                side: Side::Base,
+                price: dec("1.1"),
+            }],
+            ..ladder(Spacing::Arithmetic)
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("orders already set"));
+    }
+
+    #[test]
+    fn prices_follow_spacing() {
+        let tick = Tick::new(6);
+
+        assert_eq!(
+            ladder(Spacing::Arithmetic).prices(&tick).unwrap(),
+            vec![dec("0.8"), dec("0.9"), dec("1"), dec("1.1"), dec("1.2")]
+        );
+
+        assert_eq!(
+            FinLimitOrderLadder {
+                lower_price: dec("1"),
+                upper_price: dec("16"),
+                ..ladder(Spacing::Geometric)
+            }
+            .prices(&tick)
+            .unwrap(),
+            vec![dec("1"), dec("2"), dec("4"), dec("8"), dec("16")]
+        );
+    }
+
+    #[test]
+    fn execute_places_ladder_around_mid_price() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(1_000u128, "rune"), Coin::new(900u128, "x/ruji")],
+        );
+
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&pair_config()).unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let (messages, placed) = ladder(Spacing::Arithmetic)
+            .execute(deps.as_ref(), &env)
+            .unwrap();
+
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Order((
+                    vec![
+                        (
+                            Side::Base,
+                            Price::Fixed(dec("1.1")),
+                            Some(Uint128::new(500))
+                        ),
+                        (
+                            Side::Base,
+                            Price::Fixed(dec("1.2")),
+                            Some(Uint128::new(500))
+                        ),
+                        (
+                            Side::Quote,
+                            Price::Fixed(dec("0.9")),
+                            Some(Uint128::new(450))
+                        ),
+                        (
+                            Side::Quote,
+                            Price::Fixed(dec("0.8")),
+                            Some(Uint128::new(450))
+                        ),
+                    ],
+                    None,
+                )))
+                .unwrap(),
+                vec![Coin::new(1_000u128, "rune"), Coin::new(900u128, "x/ruji")],
+            )]
+        );
+
+        assert_eq!(
+            placed.orders,
+            vec![
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.1"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.2"),
+                },
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("0.9"),
+                },
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("0.8"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn execute_requotes_filled_levels_on_the_opposite_side() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                QueryMsg::Config {} => {
+                    SystemResult::Ok(ContractResult::Ok(to_json_binary(&pair_config()).unwrap()))
+                }
+                QueryMsg::Order((_, side, price)) => {
+                    if side == Side::Quote && price == Price::Fixed(dec("0.9")) {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_json_binary(&order_response(Side::Quote, "0.9", 0, 550)).unwrap(),
+                        ))
+                    } else if side == Side::Base && price == Price::Fixed(dec("1.1")) {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_json_binary(&order_response(Side::Base, "1.1", 400, 100)).unwrap(),
+                        ))
+                    } else {
+                        SystemResult::Ok(ContractResult::Err("Order not found".to_string()))
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        });
+
+        let ladder = FinLimitOrderLadder {
+            orders: vec![
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("0.9"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.1"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.2"),
+                },
+            ],
+            ..ladder(Spacing::Arithmetic)
+        };
+
+        let (messages, rebalanced) = ladder.clone().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(
+            messages,
+            vec![
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![
+                            (Side::Quote, Price::Fixed(dec("0.9")), Some(Uint128::zero())),
+                            (
+                                Side::Base,
+                                Price::Fixed(dec("1.1")),
+                                Some(Uint128::new(400))
+                            ),
+                        ],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![],
+                ),
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![
+                            (Side::Base, Price::Fixed(dec("1")), Some(Uint128::new(550))),
+                            (Side::Quote, Price::Fixed(dec("1")), Some(Uint128::new(100))),
+                        ],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![Coin::new(550u128, "rune"), Coin::new(100u128, "x/ruji")],
+                ),
+            ]
+        );
+
+        // The order withdrawn outside of the strategy is dropped
+        assert_eq!(
+            rebalanced.orders,
+            vec![
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.1"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1"),
+                },
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("1"),
+                },
+            ]
+        );
+
+        let filled_ladder = FinLimitOrderLadder {
+            orders: ladder.orders[..2].to_vec(),
+            ..ladder
+        };
+
+        assert_eq!(
+            filled_ladder.balances(deps.as_ref(), &env).unwrap(),
+            Coins::try_from(vec![
+                Coin::new(950u128, "rune"),
+                Coin::new(100u128, "x/ruji"),
+            ])
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn execute_claims_and_requotes_partially_filled_levels() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                QueryMsg::Config {} => {
+                    SystemResult::Ok(ContractResult::Ok(to_json_binary(&pair_config()).unwrap()))
+                }
+                QueryMsg::Order((_, side, price)) => {
+                    if side == Side::Quote && price == Price::Fixed(dec("0.9")) {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_json_binary(&order_response(Side::Quote, "0.9", 300, 200)).unwrap(),
+                        ))
+                    } else {
+                        SystemResult::Ok(ContractResult::Ok(
+                            to_json_binary(&order_response(side, "1.2", 500, 0)).unwrap(),
+                        ))
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        });
+
+        let ladder = FinLimitOrderLadder {
+            orders: vec![
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("0.9"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.2"),
+                },
+            ],
+            ..ladder(Spacing::Arithmetic)
+        };
+
+        let (messages, rebalanced) = ladder.clone().execute(deps.as_ref(), &env).unwrap();
+
+        // The partially filled bid keeps resting with its remaining amount,
+        // and its fill is claimed and offered one level up
+        assert_eq!(
+            messages,
+            vec![
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Quote,
+                            Price::Fixed(dec("0.9")),
+                            Some(Uint128::new(300))
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![],
+                ),
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(Side::Base, Price::Fixed(dec("1")), Some(Uint128::new(200)))],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![Coin::new(200u128, "rune")],
+                ),
+            ]
+        );
+
+        assert_eq!(
+            rebalanced.orders,
+            vec![
+                LadderOrder {
+                    side: Side::Quote,
+                    price: dec("0.9"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1.2"),
+                },
+                LadderOrder {
+                    side: Side::Base,
+                    price: dec("1"),
+                },
+            ]
+        );
+
+        assert_eq!(
+            rebalanced.bid_stats,
+            LimitOrderStats {
+                offered: Uint128::zero(),
+                filled: Uint128::new(200),
+                claimed: Uint128::new(200),
+            }
+        );
+        assert_eq!(
+            rebalanced.ask_stats,
+            LimitOrderStats {
+                offered: Uint128::new(200),
+                filled: Uint128::zero(),
+                claimed: Uint128::zero(),
+            }
+        );
+    }
+}
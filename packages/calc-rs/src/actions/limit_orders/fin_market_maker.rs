@@ -0,0 +1,547 @@
+use std::vec;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128};
+use rujira_rs::fin::{ConfigResponse, QueryMsg, Side};
+
+use crate::{
+    actions::limit_orders::fin_limit_order::{
+        FinLimitOrder, LimitOrderStats, PriceStrategy, StaleOrder,
+    },
+    core::Amount,
+    manager::Affiliate,
+    operation::{Operation, StatefulOperation},
+    rujira::{get_mid_price, get_pair_denoms},
+};
+
+#[cw_serde]
+pub struct FinMarketMaker {
+    pub pair_address: Addr,
+    // Base and quote denoms of the pair, resolved at init
+    #[serde(default)]
+    pub pair_denoms: Vec<String>,
+    pub spread: Decimal,
+    pub size: Amount,
+    pub target_base_ratio: Decimal,
+    pub skew: Decimal,
+    pub min_fill_ratio: Option<Decimal>,
+    pub bid: Option<StaleOrder>,
+    pub ask: Option<StaleOrder>,
+    #[serde(default)]
+    pub bid_stats: LimitOrderStats,
+    #[serde(default)]
+    pub ask_stats: LimitOrderStats,
+}
+
+#[cw_serde]
+pub struct QuotedOrder {
+    pub price: Decimal,
+    pub size: Uint128,
+}
+
+impl FinMarketMaker {
+    pub fn get_pair(&self, deps: Deps) -> StdResult<ConfigResponse> {
+        deps.querier
+            .query_wasm_smart::<ConfigResponse>(self.pair_address.clone(), &QueryMsg::Config {})
+    }
+
+    fn limit_order(&self, pair: &ConfigResponse, side: Side, quote: QuotedOrder) -> FinLimitOrder {
+        let (bid_denom, current_order, stats) = match side {
+            Side::Base => (pair.denoms.base(), self.ask.clone(), self.ask_stats.clone()),
+            Side::Quote => (
+                pair.denoms.quote(),
+                self.bid.clone(),
+                self.bid_stats.clone(),
+            ),
+        };
+
+        FinLimitOrder {
+            pair_address: self.pair_address.clone(),
+            pair_denoms: vec![
+                pair.denoms.base().to_string(),
+                pair.denoms.quote().to_string(),
+            ],
+            bid_denom: bid_denom.to_string(),
+            bid_amount: Amount::Fixed(quote.size),
+            side,
+            strategy: PriceStrategy::Fixed(quote.price),
+            min_fill_ratio: self.min_fill_ratio,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats,
+            current_order,
+        }
+    }
+
+    fn with_limit_order(self, limit_order: FinLimitOrder) -> FinMarketMaker {
+        match limit_order.side {
+            Side::Quote => FinMarketMaker {
+                bid: limit_order.current_order,
+                bid_stats: limit_order.stats,
+                ..self
+            },
+            Side::Base => FinMarketMaker {
+                ask: limit_order.current_order,
+                ask_stats: limit_order.stats,
+                ..self
+            },
+        }
+    }
+
+    // The bid and ask as limit orders, so both sides share the withdraw,
+    // claim and reset handling of a single limit order
+    pub fn limit_orders(&self, deps: Deps) -> StdResult<Vec<FinLimitOrder>> {
+        let pair = self.get_pair(deps)?;
+
+        Ok([(Side::Quote, &self.bid), (Side::Base, &self.ask)]
+            .into_iter()
+            .map(|(side, order)| {
+                let price = order.as_ref().map(|order| order.price).unwrap_or_default();
+
+                self.limit_order(
+                    &pair,
+                    side,
+                    QuotedOrder {
+                        price,
+                        size: Uint128::zero(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    pub fn quotes(&self, deps: Deps, env: &Env) -> StdResult<Option<(QuotedOrder, QuotedOrder)>> {
+        let pair = self.get_pair(deps)?;
+
+        let mut inventory = Coins::default();
+
+        for denom in [pair.denoms.base(), pair.denoms.quote()] {
+            inventory.add(deps.querier.query_balance(&env.contract.address, denom)?)?;
+        }
+
+        for order in self.limit_orders(deps)? {
+            if order.current_order.is_some() {
+                for balance in order.balances(deps, env)?.into_vec() {
+                    inventory.add(balance)?;
+                }
+            }
+        }
+
+        let base = inventory.amount_of(pair.denoms.base());
+        let quote = inventory.amount_of(pair.denoms.quote());
+
+        let mid_price = get_mid_price(deps, &self.pair_address)?;
+
+        let base_value = base.mul_floor(mid_price);
+        let total_value = base_value.checked_add(quote)?;
+
+        if total_value.is_zero() {
+            return Ok(None);
+        }
+
+        // Inventory away from the target ratio moves the reference price and
+        // sizes so that the side that rebalances it fills more readily
+        let base_ratio = Decimal::from_ratio(base_value, total_value);
+        let excess_base = base_ratio > self.target_base_ratio;
+        let adjustment = base_ratio.abs_diff(self.target_base_ratio) * self.skew;
+        let shift = self.spread * adjustment;
+
+        let reference_price = if excess_base {
+            mid_price * Decimal::one().saturating_sub(shift)
+        } else {
+            mid_price * Decimal::one().saturating_add(shift)
+        };
+
+        let half_spread = self.spread / Decimal::from_ratio(2u128, 1u128);
+
+        let bid_price = pair
+            .tick
+            .truncate_floor(&(reference_price * Decimal::one().saturating_sub(half_spread)));
+        let ask_price = pair
+            .tick
+            .truncate_ceil(&(reference_price * Decimal::one().saturating_add(half_spread)));
+
+        let order_value = match self.size {
+            Amount::Fixed(value) => value,
+            Amount::Fraction(fraction) => total_value.mul_floor(fraction),
+        };
+
+        let (bid_factor, ask_factor) = if excess_base {
+            (
+                Decimal::one().saturating_sub(adjustment),
+                Decimal::one().saturating_add(adjustment),
+            )
+        } else {
+            (
+                Decimal::one().saturating_add(adjustment),
+                Decimal::one().saturating_sub(adjustment),
+            )
+        };
+
+        Ok(Some((
+            QuotedOrder {
+                price: bid_price,
+                size: order_value.mul_floor(bid_factor).min(quote),
+            },
+            QuotedOrder {
+                price: ask_price,
+                size: order_value
+                    .mul_floor(ask_factor)
+                    .div_floor(mid_price)
+                    .min(base),
+            },
+        )))
+    }
+}
+
+impl Operation<FinMarketMaker> for FinMarketMaker {
+    fn init(self, deps: Deps, _env: &Env, _affiliates: &[Affiliate]) -> StdResult<FinMarketMaker> {
+        if self.spread.is_zero() || self.spread >= Decimal::one() {
+            return Err(StdError::generic_err(
+                "Market maker spread must be between 0 and 1",
+            ));
+        }
+
+        match self.size {
+            Amount::Fixed(amount) => {
+                if amount.lt(&Uint128::new(100)) {
+                    return Err(StdError::generic_err(
+                        "Market maker order size must be greater than or equal to 100",
+                    ));
+                }
+            }
+            Amount::Fraction(percent)
+                if percent == Decimal::zero() || percent > Decimal::percent(100) =>
+            {
+                return Err(StdError::generic_err(
+                    "Market maker order size fraction must be between 0 and 1",
+                ));
+            }
+            _ => {}
+        }
+
+        if self.target_base_ratio > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Target base ratio cannot be greater than one",
+            ));
+        }
+
+        if self.skew > Decimal::one() {
+            return Err(StdError::generic_err(
+                "Inventory skew cannot be greater than one",
+            ));
+        }
+
+        if let Some(min_fill_ratio) = self.min_fill_ratio {
+            if min_fill_ratio.gt(&Decimal::one()) {
+                return Err(StdError::generic_err(
+                    "Minimum fill ratio cannot be greater than one",
+                ));
+            }
+        }
+
+        if self.bid.is_some() || self.ask.is_some() {
+            return Err(StdError::generic_err(
+                "Cannot initialise a market maker action with orders already set.",
+            ));
+        }
+
+        if self.bid_stats != LimitOrderStats::default()
+            || self.ask_stats != LimitOrderStats::default()
+        {
+            return Err(StdError::generic_err(
+                "Cannot initialise a market maker action with existing fill statistics",
+            ));
+        }
+
+        Ok(FinMarketMaker {
+            pair_denoms: get_pair_denoms(deps, &self.pair_address, &self.pair_denoms)?,
+            ..self
+        })
+    }
+
+    fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinMarketMaker)> {
+        let (bid_quote, ask_quote) = match self.quotes(deps, env)? {
+            Some(quotes) => quotes,
+            None => return Ok((vec![], self)),
+        };
+
+        let pair = self.get_pair(deps)?;
+
+        let mut messages = vec![];
+        let mut market_maker = self.clone();
+
+        for (side, quote) in [(Side::Quote, bid_quote), (Side::Base, ask_quote)] {
+            let limit_order = self.limit_order(&pair, side.clone(), quote.clone());
+
+            let (order_messages, limit_order) = if quote.size.is_zero() {
+                // Nothing to offer on this side, so withdraw any resting order
+                // and let the commit stage remove it
+                limit_order.cancel(deps, env)?
+            } else {
+                limit_order.execute(deps, env)?
+            };
+
+            messages.extend(order_messages);
+            market_maker = market_maker.with_limit_order(limit_order);
+        }
+
+        Ok((messages, market_maker))
+    }
+}
+
+impl StatefulOperation<FinMarketMaker> for FinMarketMaker {
+    fn balances(&self, deps: Deps, env: &Env) -> StdResult<Coins> {
+        let mut balances = Coins::default();
+
+        for order in self.limit_orders(deps)? {
+            for balance in order.balances(deps, env)?.into_vec() {
+                balances.add(balance)?;
+            }
+        }
+
+        Ok(balances)
+    }
+
+    fn cancel(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, FinMarketMaker)> {
+        let mut messages = vec![];
+        let mut market_maker = self.clone();
+
+        for order in self.limit_orders(deps)? {
+            let (order_messages, order) = order.cancel(deps, env)?;
+            messages.extend(order_messages);
+
+            // Keeps the fills claimed by the withdrawal, and lets the commit
+            // stage remove the current orders
+            market_maker = market_maker.with_limit_order(order);
+        }
+
+        Ok((messages, market_maker))
+    }
+
+    fn commit(self, deps: Deps, env: &Env) -> StdResult<FinMarketMaker> {
+        let mut market_maker = self.clone();
+
+        for order in self.limit_orders(deps)? {
+            market_maker = market_maker.with_limit_order(order.commit(deps, env)?);
+        }
+
+        Ok(market_maker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        to_json_binary, Coin, ContractResult, SystemResult, WasmQuery,
+    };
+    use rujira_rs::fin::{
+        BookItemResponse, BookResponse, Denoms, ExecuteMsg, OrdersResponse, Price, Tick,
+    };
+
+    use crate::core::Contract;
+
+    fn market_maker() -> FinMarketMaker {
+        FinMarketMaker {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            spread: Decimal::percent(2),
+            size: Amount::Fixed(Uint128::new(1_000)),
+            target_base_ratio: Decimal::percent(50),
+            skew: Decimal::one(),
+            min_fill_ratio: None,
+            bid: None,
+            ask: None,
+            bid_stats: LimitOrderStats::default(),
+            ask_stats: LimitOrderStats::default(),
+        }
+    }
+
+    #[test]
+    fn init_validates_market_maker() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&ConfigResponse {
+                    denoms: Denoms::new("rune", "x/ruji"),
+                    oracles: None,
+                    market_makers: vec![],
+                    tick: Tick::new(6),
+                    range_delta: Decimal::zero(),
+                    range_min: Decimal::one(),
+                    fee_taker: Decimal::percent(1),
+                    fee_maker: Decimal::percent(1),
+                    fee_range: Decimal::zero(),
+                    fee_address: "feetaker".to_string(),
+                })
+                .unwrap(),
+            ))
+        });
+
+        assert_eq!(
+            market_maker()
+                .init(deps.as_ref(), &env, &[])
+                .unwrap()
+                .pair_denoms,
+            vec!["rune".to_string(), "x/ruji".to_string()]
+        );
+
+        assert!(FinMarketMaker {
+            spread: Decimal::zero(),
+            ..market_maker()
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("spread must be between 0 and 1"));
+
+        assert!(FinMarketMaker {
+            size: Amount::Fixed(Uint128::new(99)),
+            ..market_maker()
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("greater than or equal to 100"));
+
+        assert!(FinMarketMaker {
+            skew: Decimal::percent(101),
+            ..market_maker()
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("skew cannot be greater than one"));
+
+        assert!(FinMarketMaker {
+            bid: Some(StaleOrder {
+                price: Decimal::one(),
+                oracle_offset: None,
+                expires: None,
+            }),
+            ..market_maker()
+        }
+        .init(deps.as_ref(), &env, &[])
+        .unwrap_err()
+        .to_string()
+        .contains("orders already set"));
+    }
+
+    #[test]
+    fn execute_skews_quotes_towards_target_inventory() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // 75% of the inventory value is held in the base asset
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(3_000u128, "rune"), Coin::new(1_000u128, "x/ruji")],
+        );
+
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Orders { .. } => {
+                        to_json_binary(&OrdersResponse { orders: vec![] }).unwrap()
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let (messages, market_maker) = market_maker().execute(deps.as_ref(), &env).unwrap();
+
+        // Excess base moves the reference price down by a quarter of the spread,
+        // shrinks the bid and grows the ask by the 25% imbalance
+        assert_eq!(
+            messages,
+            vec![
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Quote,
+                            Price::Fixed(Decimal::from_str("0.98505").unwrap()),
+                            Some(Uint128::new(750)),
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![Coin::new(750u128, "x/ruji")],
+                ),
+                Contract(Addr::unchecked("pair")).call(
+                    to_json_binary(&ExecuteMsg::Order((
+                        vec![(
+                            Side::Base,
+                            Price::Fixed(Decimal::from_str("1.00495").unwrap()),
+                            Some(Uint128::new(1_250)),
+                        )],
+                        None,
+                    )))
+                    .unwrap(),
+                    vec![Coin::new(1_250u128, "rune")],
+                ),
+            ]
+        );
+
+        assert_eq!(
+            market_maker.bid,
+            Some(StaleOrder {
+                price: Decimal::from_str("0.98505").unwrap(),
+                oracle_offset: None,
+                expires: None,
+            })
+        );
+        assert_eq!(
+            market_maker.ask,
+            Some(StaleOrder {
+                price: Decimal::from_str("1.00495").unwrap(),
+                oracle_offset: None,
+                expires: None,
+            })
+        );
+
+        // Each side keeps the stats of its own offers
+        assert_eq!(market_maker.bid_stats.offered, Uint128::new(750));
+        assert_eq!(market_maker.ask_stats.offered, Uint128::new(1_250));
+    }
+}
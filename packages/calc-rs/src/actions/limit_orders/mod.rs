@@ -1 +1,3 @@
 pub mod fin_limit_order;
+pub mod fin_limit_order_ladder;
+pub mod fin_market_maker;
@@ -2,3 +2,4 @@ pub mod action;
 pub mod distribution;
 pub mod limit_orders;
 pub mod swaps;
+pub mod trailing_stop;
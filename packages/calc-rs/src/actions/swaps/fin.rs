@@ -2,8 +2,10 @@ use std::{cmp::max, vec};
 
 use crate::{
     actions::swaps::swap::{Adjusted, Executable, New, SwapQuote},
+    constants::MAX_FIN_ROUTE_HOPS,
     core::Contract,
     rujira::get_mid_price,
+    strategy::StrategyExecuteMsg,
 };
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
@@ -152,4 +154,327 @@ impl FinRoute {
 
         Ok(swap_msg)
     }
+
+    // Fin returns exactly the minimum receive amount and refunds the unused offer
+    pub fn execute_exact(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        swap_amount: &Coin,
+        minimum_receive_amount: &Coin,
+    ) -> StdResult<CosmosMsg> {
+        let swap_msg = Contract(self.pair_address.clone()).call(
+            to_json_binary(&ExecuteMsg::Swap(SwapRequest::Exact {
+                exact_return: minimum_receive_amount.amount,
+                to: None,
+                callback: None,
+            }))?,
+            vec![swap_amount.clone()],
+        );
+
+        Ok(swap_msg)
+    }
+}
+
+#[cw_serde]
+pub struct FinHop {
+    pub pair_address: Addr,
+    pub offer: Coin,
+    pub expected_return: Coin,
+    pub min_return: Uint128,
+}
+
+#[cw_serde]
+pub struct FinMultiHopRoute {
+    pub pair_addresses: Vec<Addr>,
+}
+
+impl FinMultiHopRoute {
+    // Walks the pairs from the offer denom, returning the denom received from each hop
+    fn ask_denoms(&self, deps: Deps, offer_denom: &str) -> StdResult<Vec<String>> {
+        let mut denom = offer_denom.to_string();
+        let mut ask_denoms = Vec::with_capacity(self.pair_addresses.len());
+
+        for pair_address in &self.pair_addresses {
+            let pair = deps
+                .querier
+                .query_wasm_smart::<ConfigResponse>(pair_address, &QueryMsg::Config {})?;
+
+            denom = if pair.denoms.base() == denom {
+                pair.denoms.quote().to_string()
+            } else if pair.denoms.quote() == denom {
+                pair.denoms.base().to_string()
+            } else {
+                return Err(StdError::generic_err(format!(
+                    "Pair at {pair_address} does not support swapping from {denom}"
+                )));
+            };
+
+            ask_denoms.push(denom.clone());
+        }
+
+        Ok(ask_denoms)
+    }
+
+    // Each hop is simulated on the return expected from the hop before it, with
+    // intermediate minimums padded by the maximum slippage
+    pub fn hops(
+        &self,
+        deps: Deps,
+        swap_amount: &Coin,
+        minimum_receive_amount: &Coin,
+        maximum_slippage_bps: u64,
+    ) -> StdResult<Vec<FinHop>> {
+        let hop_tolerance = Decimal::one()
+            .checked_sub(Decimal::bps(maximum_slippage_bps))
+            .unwrap_or(Decimal::zero());
+
+        let ask_denoms = self.ask_denoms(deps, &swap_amount.denom)?;
+        let mut offer = swap_amount.clone();
+        let mut hops = Vec::with_capacity(ask_denoms.len());
+
+        for (index, (pair_address, ask_denom)) in
+            self.pair_addresses.iter().zip(ask_denoms).enumerate()
+        {
+            let simulation = deps.querier.query_wasm_smart::<SimulationResponse>(
+                pair_address,
+                &QueryMsg::Simulate(offer.clone()),
+            )?;
+
+            let min_return = if index == self.pair_addresses.len() - 1 {
+                minimum_receive_amount.amount
+            } else {
+                simulation.returned.mul_floor(hop_tolerance)
+            };
+
+            hops.push(FinHop {
+                pair_address: pair_address.clone(),
+                offer: offer.clone(),
+                expected_return: Coin::new(simulation.returned, ask_denom.clone()),
+                min_return,
+            });
+
+            offer = Coin::new(simulation.returned, ask_denom);
+        }
+
+        Ok(hops)
+    }
+
+    pub fn validate(&self, deps: Deps, route: &SwapQuote<New>) -> StdResult<()> {
+        if self.pair_addresses.len() < 2 || self.pair_addresses.len() > MAX_FIN_ROUTE_HOPS {
+            return Err(StdError::generic_err(format!(
+                "Multi-hop Fin routes must have between 2 and {MAX_FIN_ROUTE_HOPS} pairs"
+            )));
+        }
+
+        let ask_denoms = self.ask_denoms(deps, &route.swap_amount.denom)?;
+
+        if ask_denoms.last() != Some(&route.minimum_receive_amount.denom) {
+            return Err(StdError::generic_err(format!(
+                "Multi-hop Fin route does not end in {}",
+                route.minimum_receive_amount.denom
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_expected_amount_out(
+        &self,
+        deps: Deps,
+        quote: &SwapQuote<New>,
+    ) -> StdResult<Uint128> {
+        let hops = self.hops(
+            deps,
+            &quote.swap_amount,
+            &quote.minimum_receive_amount,
+            quote.maximum_slippage_bps,
+        )?;
+
+        Ok(hops
+            .last()
+            .map_or(Uint128::zero(), |hop| hop.expected_return.amount))
+    }
+
+    pub fn validate_adjusted(
+        self,
+        deps: Deps,
+        _env: &Env,
+        route: SwapQuote<Adjusted>,
+    ) -> StdResult<SwapQuote<Executable>> {
+        if route.swap_amount.amount.is_zero() {
+            return Err(StdError::generic_err(
+                "Swap amount after adjustment is zero".to_string(),
+            ));
+        }
+
+        let hops = self.hops(
+            deps,
+            &route.swap_amount,
+            &route.minimum_receive_amount,
+            route.maximum_slippage_bps,
+        )?;
+
+        // Slippage is checked hop by hop against each pair's mid price
+        for hop in &hops {
+            let mid_price = get_mid_price(deps, &hop.pair_address)?;
+
+            let pair = deps
+                .querier
+                .query_wasm_smart::<ConfigResponse>(&hop.pair_address, &QueryMsg::Config {})?;
+
+            let spot_price = if hop.offer.denom == pair.denoms.base() {
+                Decimal::one() / mid_price
+            } else {
+                mid_price
+            };
+
+            let optimal_return_amount = max(
+                hop.expected_return.amount,
+                hop.offer.amount.mul_floor(Decimal::one() / spot_price),
+            );
+
+            let slippage_bps = Uint128::new(10_000).mul_ceil(
+                Decimal::one()
+                    .checked_sub(Decimal::from_ratio(
+                        hop.expected_return.amount,
+                        optimal_return_amount,
+                    ))
+                    .unwrap_or(Decimal::one()),
+            );
+
+            if slippage_bps.gt(&Uint128::new(route.maximum_slippage_bps as u128)) {
+                return Err(StdError::generic_err(format!(
+                    "Slippage of {} bps through pair at {} exceeds maximum allowed of {} bps",
+                    slippage_bps, hop.pair_address, route.maximum_slippage_bps
+                )));
+            }
+        }
+
+        let expected_amount_out = Coin::new(
+            hops.last()
+                .map_or(Uint128::zero(), |hop| hop.expected_return.amount),
+            route.minimum_receive_amount.denom.clone(),
+        );
+
+        if expected_amount_out.amount < route.minimum_receive_amount.amount {
+            return Err(StdError::generic_err(format!(
+                "Expected amount out {} for swapping {} is less than minimum receive amount {}",
+                expected_amount_out.amount,
+                route.swap_amount.amount,
+                route.minimum_receive_amount.amount
+            )));
+        }
+
+        Ok(SwapQuote {
+            swap_amount: route.swap_amount,
+            minimum_receive_amount: route.minimum_receive_amount,
+            maximum_slippage_bps: route.maximum_slippage_bps,
+            adjustment: route.adjustment,
+            route: route.route,
+            destination: route.destination,
+            state: Executable {
+                expected_amount_out,
+            },
+        })
+    }
+
+    // Hops run through a strategy self-call so each one can spend what the hop
+    // before it actually returned
+    pub fn execute(
+        &self,
+        deps: Deps,
+        env: &Env,
+        quote: &SwapQuote<Executable>,
+    ) -> StdResult<Vec<CosmosMsg>> {
+        let hops = self.hops(
+            deps,
+            &quote.swap_amount,
+            &quote.minimum_receive_amount,
+            quote.maximum_slippage_bps,
+        )?;
+
+        Ok(vec![FinMultiHopSwap {
+            hops,
+            offer_balance_before: None,
+        }
+        .continuation(env)?])
+    }
+}
+
+#[cw_serde]
+pub struct FinMultiHopSwap {
+    pub hops: Vec<FinHop>,
+    pub offer_balance_before: Option<Uint128>,
+}
+
+impl FinMultiHopSwap {
+    fn continuation(self, env: &Env) -> StdResult<CosmosMsg> {
+        Ok(Contract(env.contract.address.clone()).call(
+            to_json_binary(&StrategyExecuteMsg::SwapFinHops(self))?,
+            vec![],
+        ))
+    }
+
+    // Swaps through the next hop, offering the balance received since the
+    // previous hop was dispatched, and queues the remaining hops after it
+    pub fn execute(self, deps: Deps, env: &Env) -> StdResult<Vec<CosmosMsg>> {
+        let Some((hop, remaining_hops)) = self.hops.split_first() else {
+            return Ok(vec![]);
+        };
+
+        let offer = match self.offer_balance_before {
+            Some(balance_before) => Coin::new(
+                deps.querier
+                    .query_balance(&env.contract.address, &hop.offer.denom)?
+                    .amount
+                    .saturating_sub(balance_before),
+                hop.offer.denom.clone(),
+            ),
+            None => hop.offer.clone(),
+        };
+
+        if offer.amount.is_zero() {
+            return Err(StdError::generic_err(format!(
+                "Nothing received to swap through pair at {}",
+                hop.pair_address
+            )));
+        }
+
+        // The last hop keeps the minimum receive amount of the whole swap, while
+        // intermediate minimums scale with the amount actually offered
+        let min_return = if remaining_hops.is_empty() {
+            hop.min_return
+        } else {
+            hop.min_return
+                .multiply_ratio(offer.amount, hop.offer.amount)
+        };
+
+        let mut messages = vec![FinRoute {
+            pair_address: hop.pair_address.clone(),
+        }
+        .execute(
+            deps,
+            env,
+            &offer,
+            &Coin::new(min_return, hop.expected_return.denom.clone()),
+        )?];
+
+        if !remaining_hops.is_empty() {
+            let offer_balance_before = deps
+                .querier
+                .query_balance(&env.contract.address, &hop.expected_return.denom)?
+                .amount;
+
+            messages.push(
+                FinMultiHopSwap {
+                    hops: remaining_hops.to_vec(),
+                    offer_balance_before: Some(offer_balance_before),
+                }
+                .continuation(env)?,
+            );
+        }
+
+        Ok(messages)
+    }
 }
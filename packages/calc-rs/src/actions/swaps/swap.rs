@@ -4,12 +4,20 @@ use std::{
 };
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128};
+use cosmwasm_std::{
+    Addr, Coin, Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Timestamp, Uint128,
+};
 
 use crate::{
-    actions::swaps::{fin::FinRoute, thor::ThorchainRoute},
-    manager::Affiliate,
-    operation::Operation,
+    actions::swaps::{
+        fin::{FinMultiHopRoute, FinRoute},
+        thor::ThorchainRoute,
+    },
+    conditions::asset_value_ratio::PriceSource,
+    constants::{EXACT_OUTPUT_SIZING_ITERATIONS, SPLIT_SWAP_STEPS},
+    manager::{Affiliate, ThorchainAffiliate},
+    operation::{Operation, StatefulOperation},
+    oracle::fetch_price,
 };
 
 #[cw_serde]
@@ -20,11 +28,48 @@ pub enum SwapAmountAdjustment {
         minimum_swap_amount: Option<Coin>,
         scalar: Decimal,
     },
+    // Targets a holding of the receive denom worth `increment` more, in the
+    // swap denom, every `period` seconds since `start`
+    ValueAverage {
+        increment: Uint128,
+        period: u64,
+        minimum_swap_amount: Option<Coin>,
+        allow_sells: bool,
+        oracle: Option<PriceSource>,
+        start: Option<Timestamp>,
+        // Receive denom bought less sold by this swap, settled on commit
+        #[serde(default)]
+        units: Uint128,
+        #[serde(default)]
+        pending: Option<PendingValueAverageSwap>,
+    },
+    // Receives the minimum receive amount, spending at most the smallest input
+    // the route quotes to return it
+    ExactOutput,
+}
+
+#[cw_serde]
+pub struct PendingValueAverageSwap {
+    // Receive denom balance before the swap was sent
+    pub balance_before: Uint128,
+    // Minimum receive amount of buys that settle after the commit (i.e. Thorchain)
+    pub settling: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum SwapRouting {
+    // Swap everything through the route with the best quote
+    #[default]
+    Best,
+    // Spread the swap across routes to maximise the total amount out
+    Split,
 }
 
 #[cw_serde]
 pub enum SwapRoute {
     Fin(FinRoute),
+    FinMultiHop(FinMultiHopRoute),
     Thorchain(ThorchainRoute),
 }
 
@@ -32,6 +77,7 @@ impl SwapRoute {
     pub fn validate(&self, deps: Deps, quote: &SwapQuote<New>) -> StdResult<()> {
         match self {
             SwapRoute::Fin(route) => route.validate(deps, quote),
+            SwapRoute::FinMultiHop(route) => route.validate(deps, quote),
             SwapRoute::Thorchain(route) => route.validate(deps),
         }
     }
@@ -43,6 +89,7 @@ impl SwapRoute {
     ) -> StdResult<Uint128> {
         match self {
             SwapRoute::Fin(route) => route.get_expected_amount_out(deps, quote),
+            SwapRoute::FinMultiHop(route) => route.get_expected_amount_out(deps, quote),
             SwapRoute::Thorchain(route) => route.get_expected_amount_out(deps, quote),
         }
     }
@@ -55,22 +102,51 @@ impl SwapRoute {
     ) -> StdResult<SwapQuote<Executable>> {
         match self {
             SwapRoute::Fin(pair_address) => pair_address.validate_adjusted(deps, env, quote),
+            SwapRoute::FinMultiHop(route) => route.validate_adjusted(deps, env, quote),
             SwapRoute::Thorchain(route) => route.validate_adjusted(deps, env, quote),
         }
     }
 
+    // The same route swapping in the opposite direction
+    pub fn reversed(self) -> SwapRoute {
+        match self {
+            SwapRoute::FinMultiHop(FinMultiHopRoute { pair_addresses }) => {
+                SwapRoute::FinMultiHop(FinMultiHopRoute {
+                    pair_addresses: pair_addresses.into_iter().rev().collect(),
+                })
+            }
+            route => route,
+        }
+    }
+
     pub fn execute(
         &self,
         deps: Deps,
         env: &Env,
-        swap_amount: &Coin,
-        minimum_receive_amount: &Coin,
-    ) -> StdResult<CosmosMsg> {
+        quote: &SwapQuote<Executable>,
+    ) -> StdResult<Vec<CosmosMsg>> {
         match self {
-            SwapRoute::Fin(route) => route.execute(deps, env, swap_amount, minimum_receive_amount),
-            SwapRoute::Thorchain(route) => {
-                route.execute(deps, env, swap_amount, minimum_receive_amount)
+            SwapRoute::Fin(route) if quote.adjustment == SwapAmountAdjustment::ExactOutput => {
+                Ok(vec![route.execute_exact(
+                    deps,
+                    env,
+                    &quote.swap_amount,
+                    &quote.minimum_receive_amount,
+                )?])
             }
+            SwapRoute::Fin(route) => Ok(vec![route.execute(
+                deps,
+                env,
+                &quote.swap_amount,
+                &quote.minimum_receive_amount,
+            )?]),
+            SwapRoute::FinMultiHop(route) => route.execute(deps, env, quote),
+            SwapRoute::Thorchain(route) => Ok(vec![route.execute(
+                deps,
+                env,
+                &quote.swap_amount,
+                &quote.minimum_receive_amount,
+            )?]),
         }
     }
 }
@@ -107,8 +183,27 @@ impl SwapQuote<New> {
             .querier
             .query_balance(&env.contract.address, &self.swap_amount.denom)?;
 
+        self.adjust_to(deps, env, swap_balance.amount)
+    }
+
+    // Adjusts against an explicit available amount, for funds that are only
+    // released by messages sent ahead of the swap
+    pub fn adjust_to(
+        self,
+        deps: Deps,
+        env: &Env,
+        available: Uint128,
+    ) -> StdResult<SwapQuote<Adjusted>> {
+        match self.adjustment {
+            SwapAmountAdjustment::ValueAverage { .. } => {
+                return self.value_average(deps, env, available)
+            }
+            SwapAmountAdjustment::ExactOutput => return self.exact_output(deps, available),
+            _ => {}
+        }
+
         let swap_amount = Coin::new(
-            min(swap_balance.amount, self.swap_amount.amount),
+            min(available, self.swap_amount.amount),
             self.swap_amount.denom.clone(),
         );
 
@@ -164,7 +259,7 @@ impl SwapQuote<New> {
 
                 let new_swap_amount = Coin::new(
                     min(
-                        swap_balance.amount,
+                        available,
                         max(
                             scaled_swap_amount,
                             minimum_swap_amount
@@ -192,6 +287,11 @@ impl SwapQuote<New> {
 
                 (new_swap_amount, new_minimum_receive_amount)
             }
+            SwapAmountAdjustment::ValueAverage { .. } | SwapAmountAdjustment::ExactOutput => {
+                return Err(StdError::generic_err(
+                    "Quotes with a target amount cannot be scaled from the swap amount",
+                ))
+            }
         };
 
         Ok(SwapQuote {
@@ -207,6 +307,209 @@ impl SwapQuote<New> {
             state: Adjusted,
         })
     }
+
+    // Buys up to the swap amount while the holding is behind its target value,
+    // and sells up to the same value back into the swap denom when ahead
+    fn value_average(
+        self,
+        deps: Deps,
+        env: &Env,
+        available: Uint128,
+    ) -> StdResult<SwapQuote<Adjusted>> {
+        let SwapAmountAdjustment::ValueAverage {
+            increment,
+            period,
+            minimum_swap_amount,
+            allow_sells,
+            oracle,
+            start,
+            units,
+            ..
+        } = &self.adjustment
+        else {
+            return Err(StdError::generic_err(
+                "Swap amount adjustment is not value averaging",
+            ));
+        };
+
+        let start = start.ok_or(StdError::generic_err(
+            "Value averaging start time has not been set",
+        ))?;
+
+        let periods = env.block.time.seconds().saturating_sub(start.seconds()) / period + 1;
+        let target_value = increment.checked_mul(Uint128::from(periods))?;
+
+        // Price of the receive denom in the swap denom
+        let price = match oracle {
+            Some(oracle) => fetch_price(
+                deps,
+                &self.minimum_receive_amount.denom,
+                &self.swap_amount.denom,
+                oracle,
+            )?,
+            None => {
+                let expected_amount_out = self.route.get_expected_amount_out(deps, &self)?;
+
+                if expected_amount_out.is_zero() {
+                    return Err(StdError::generic_err(
+                        "Expected amount out is zero".to_string(),
+                    ));
+                }
+
+                Decimal::from_ratio(self.swap_amount.amount, expected_amount_out)
+            }
+        };
+
+        // Only the units bought by this swap count towards the target, so
+        // other funds held in the receive denom do not stall it
+        let holding_value = units.mul_floor(price);
+        let minimum_swap_amount = minimum_swap_amount
+            .as_ref()
+            .map_or(Uint128::zero(), |minimum| minimum.amount);
+
+        if holding_value < target_value {
+            let swap_amount = min(
+                min(target_value - holding_value, self.swap_amount.amount),
+                available,
+            );
+
+            if swap_amount.is_zero() || swap_amount < minimum_swap_amount {
+                return Err(StdError::generic_err(format!(
+                    "Value averaging swap amount {swap_amount} is below the minimum swap amount"
+                )));
+            }
+
+            let minimum_receive_amount = self
+                .minimum_receive_amount
+                .amount
+                .mul_floor(Decimal::from_ratio(swap_amount, self.swap_amount.amount));
+
+            return Ok(SwapQuote {
+                swap_amount: Coin::new(swap_amount, self.swap_amount.denom),
+                minimum_receive_amount: Coin::new(
+                    minimum_receive_amount,
+                    self.minimum_receive_amount.denom,
+                ),
+                maximum_slippage_bps: self.maximum_slippage_bps,
+                adjustment: self.adjustment,
+                route: self.route,
+                destination: self.destination,
+                state: Adjusted,
+            });
+        }
+
+        if !allow_sells {
+            return Err(StdError::generic_err(
+                "Holding value is not below the value averaging target",
+            ));
+        }
+
+        let sell_value = min(holding_value - target_value, self.swap_amount.amount);
+
+        if sell_value.is_zero() || sell_value < minimum_swap_amount {
+            return Err(StdError::generic_err(format!(
+                "Value averaging sell value {sell_value} is below the minimum swap amount"
+            )));
+        }
+
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, &self.minimum_receive_amount.denom)?
+            .amount;
+
+        let sell_amount = min(
+            sell_value.mul_floor(Decimal::one() / price),
+            min(*units, balance),
+        );
+
+        let minimum_receive_amount = sell_amount.mul_floor(price).mul_floor(
+            Decimal::one()
+                .checked_sub(Decimal::bps(self.maximum_slippage_bps))
+                .unwrap_or(Decimal::zero()),
+        );
+
+        Ok(SwapQuote {
+            swap_amount: Coin::new(sell_amount, self.minimum_receive_amount.denom),
+            minimum_receive_amount: Coin::new(minimum_receive_amount, self.swap_amount.denom),
+            maximum_slippage_bps: self.maximum_slippage_bps,
+            adjustment: self.adjustment,
+            route: self.route.reversed(),
+            destination: self.destination,
+            state: Adjusted,
+        })
+    }
+
+    fn expected_amount_out_for(&self, deps: Deps, amount: Uint128) -> StdResult<Uint128> {
+        self.route.get_expected_amount_out(
+            deps,
+            &SwapQuote {
+                swap_amount: Coin::new(amount, self.swap_amount.denom.clone()),
+                ..self.clone()
+            },
+        )
+    }
+
+    // Sizes the swap to the smallest input quoted to return the minimum receive
+    // amount. The maximum slippage is checked when the quote is validated, and
+    // does not pad the input.
+    fn exact_output(self, deps: Deps, available: Uint128) -> StdResult<SwapQuote<Adjusted>> {
+        let target = self.minimum_receive_amount.amount;
+        let maximum_swap_amount = min(available, self.swap_amount.amount);
+
+        if maximum_swap_amount.is_zero() {
+            return Err(StdError::generic_err(
+                "Available swap amount is zero".to_string(),
+            ));
+        }
+
+        let maximum_amount_out = self.expected_amount_out_for(deps, maximum_swap_amount)?;
+
+        if maximum_amount_out < target {
+            return Err(StdError::generic_err(format!(
+                "Expected amount out {maximum_amount_out} for swapping the maximum {maximum_swap_amount} is less than the target receive amount {target}"
+            )));
+        }
+
+        // The smallest input known to return the target, and the largest known not to
+        let mut sufficient = (maximum_swap_amount, maximum_amount_out);
+        let mut insufficient = Uint128::zero();
+
+        for _ in 0..EXACT_OUTPUT_SIZING_ITERATIONS {
+            // Scale the sufficient input down to the target at its quoted price,
+            // bisecting instead if that lands on an input known to fall short
+            let scaled = sufficient
+                .0
+                .mul_ceil(Decimal::from_ratio(target, sufficient.1));
+
+            let amount = if scaled > insufficient {
+                scaled
+            } else {
+                insufficient + (sufficient.0 - insufficient + Uint128::one()) / Uint128::new(2)
+            };
+
+            if amount >= sufficient.0 {
+                break;
+            }
+
+            let amount_out = self.expected_amount_out_for(deps, amount)?;
+
+            if amount_out >= target {
+                sufficient = (amount, amount_out);
+            } else {
+                insufficient = amount;
+            }
+        }
+
+        Ok(SwapQuote {
+            swap_amount: Coin::new(sufficient.0, self.swap_amount.denom),
+            minimum_receive_amount: self.minimum_receive_amount,
+            maximum_slippage_bps: self.maximum_slippage_bps,
+            adjustment: self.adjustment,
+            route: self.route,
+            destination: self.destination,
+            state: Adjusted,
+        })
+    }
 }
 
 impl SwapQuote<Adjusted> {
@@ -216,9 +519,8 @@ impl SwapQuote<Adjusted> {
 }
 
 impl SwapQuote<Executable> {
-    pub fn execute(&self, deps: Deps, env: &Env) -> StdResult<CosmosMsg> {
-        self.route
-            .execute(deps, env, &self.swap_amount, &self.minimum_receive_amount)
+    pub fn execute(&self, deps: Deps, env: &Env) -> StdResult<Vec<CosmosMsg>> {
+        self.route.execute(deps, env, self)
     }
 }
 
@@ -229,6 +531,8 @@ pub struct Swap {
     pub maximum_slippage_bps: u64,
     pub adjustment: SwapAmountAdjustment,
     pub routes: Vec<SwapRoute>,
+    #[serde(default)]
+    pub routing: SwapRouting,
 }
 
 impl Swap {
@@ -247,8 +551,27 @@ impl Swap {
             return Err(StdError::generic_err("No swap routes provided"));
         }
 
+        if self.routing == SwapRouting::Split && self.routes.len() < 2 {
+            return Err(StdError::generic_err(
+                "Split routing requires at least two swap routes",
+            ));
+        }
+
         match &self.adjustment {
             SwapAmountAdjustment::Fixed => {}
+            SwapAmountAdjustment::ExactOutput => {
+                if self.minimum_receive_amount.amount.is_zero() {
+                    return Err(StdError::generic_err(
+                        "Exact output swaps require a non-zero receive amount",
+                    ));
+                }
+
+                if self.routing == SwapRouting::Split {
+                    return Err(StdError::generic_err(
+                        "Exact output swaps cannot use split routing",
+                    ));
+                }
+            }
             SwapAmountAdjustment::LinearScalar {
                 base_receive_amount,
                 minimum_swap_amount,
@@ -264,6 +587,32 @@ impl Swap {
                     ));
                 }
 
+                if let Some(minimum_swap_amount) = minimum_swap_amount {
+                    if minimum_swap_amount.denom != self.swap_amount.denom {
+                        return Err(StdError::generic_err(
+                            "Minimum swap amount denom must match swap amount denom",
+                        ));
+                    }
+                }
+            }
+            SwapAmountAdjustment::ValueAverage {
+                increment,
+                period,
+                minimum_swap_amount,
+                ..
+            } => {
+                if increment.is_zero() {
+                    return Err(StdError::generic_err(
+                        "Value averaging increment cannot be zero",
+                    ));
+                }
+
+                if *period == 0 {
+                    return Err(StdError::generic_err(
+                        "Value averaging period cannot be zero",
+                    ));
+                }
+
                 if let Some(minimum_swap_amount) = minimum_swap_amount {
                     if minimum_swap_amount.denom != self.swap_amount.denom {
                         return Err(StdError::generic_err(
@@ -290,16 +639,28 @@ impl Swap {
         Ok(())
     }
 
-    pub fn with_affiliates(self) -> Self {
+    pub fn pairs(&self) -> Vec<Addr> {
+        self.routes
+            .iter()
+            .flat_map(|route| match route {
+                SwapRoute::Fin(FinRoute { pair_address }) => vec![pair_address.clone()],
+                SwapRoute::FinMultiHop(FinMultiHopRoute { pair_addresses }) => {
+                    pair_addresses.clone()
+                }
+                SwapRoute::Thorchain(_) => vec![],
+            })
+            .collect()
+    }
+
+    pub fn with_affiliates(self, affiliate: Option<&ThorchainAffiliate>) -> Self {
         Swap {
             routes: self
                 .routes
                 .into_iter()
                 .map(|route| match route {
                     SwapRoute::Thorchain(thor_route) => SwapRoute::Thorchain(ThorchainRoute {
-                        // As per agreement with Rujira
-                        affiliate_code: Some("rj".to_string()),
-                        affiliate_bps: Some(10),
+                        affiliate_code: affiliate.map(|affiliate| affiliate.code.clone()),
+                        affiliate_bps: affiliate.map(|affiliate| affiliate.bps),
                         ..thor_route
                     }),
                     _ => route,
@@ -310,6 +671,15 @@ impl Swap {
     }
 
     pub fn best_quote(&self, deps: Deps, env: &Env) -> StdResult<SwapQuote<Executable>> {
+        self.best_quote_from(deps, env, None)
+    }
+
+    fn best_quote_from(
+        &self,
+        deps: Deps,
+        env: &Env,
+        available: Option<Uint128>,
+    ) -> StdResult<SwapQuote<Executable>> {
         let mut best_quote = None;
         let mut best_amount = Uint128::zero();
 
@@ -324,12 +694,25 @@ impl Swap {
                 route: route.clone(),
                 destination: env.contract.address.clone(),
                 state: New,
+            };
+
+            let quote = match available {
+                Some(available) => quote.adjust_to(deps, env, available),
+                None => quote.adjust(deps, env),
             }
-            .adjust(deps, env)
             .and_then(|adjusted_quote| adjusted_quote.validate(deps, env));
 
             if let Ok(validated_quote) = quote {
-                if validated_quote.state.expected_amount_out.amount > best_amount {
+                // Exact output quotes all return at least the target, so the
+                // best route is the one that spends the least
+                let is_better = match (&self.adjustment, &best_quote) {
+                    (SwapAmountAdjustment::ExactOutput, Some(best_quote)) => {
+                        validated_quote.swap_amount.amount < best_quote.swap_amount.amount
+                    }
+                    _ => validated_quote.state.expected_amount_out.amount > best_amount,
+                };
+
+                if is_better {
                     best_amount = validated_quote.state.expected_amount_out.amount;
                     best_quote = Some(validated_quote);
                 }
@@ -344,16 +727,229 @@ impl Swap {
         )))
     }
 
+    fn expected_amount_out(
+        &self,
+        deps: Deps,
+        env: &Env,
+        route: &SwapRoute,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        route.get_expected_amount_out(
+            deps,
+            &SwapQuote {
+                swap_amount: Coin::new(amount, self.swap_amount.denom.clone()),
+                minimum_receive_amount: self.minimum_receive_amount.clone(),
+                maximum_slippage_bps: self.maximum_slippage_bps,
+                adjustment: self.adjustment.clone(),
+                route: route.clone(),
+                destination: env.contract.address.clone(),
+                state: New,
+            },
+        )
+    }
+
+    // Allocates the best quote's swap amount across routes in equal steps, each
+    // going to the route with the largest marginal amount out. Returns None when
+    // a single route does at least as well or any leg fails validation.
+    fn split_quotes(
+        &self,
+        deps: Deps,
+        env: &Env,
+        best_quote: &SwapQuote<Executable>,
+    ) -> StdResult<Option<Vec<(usize, SwapQuote<Executable>)>>> {
+        let total = best_quote.swap_amount.amount;
+        let step = total / Uint128::new(SPLIT_SWAP_STEPS);
+
+        if step.is_zero() {
+            return Ok(None);
+        }
+
+        let mut allocations = vec![Uint128::zero(); self.routes.len()];
+        let mut amounts_out = vec![Uint128::zero(); self.routes.len()];
+
+        for i in 0..SPLIT_SWAP_STEPS {
+            let amount = if i == SPLIT_SWAP_STEPS - 1 {
+                total - step * Uint128::new(SPLIT_SWAP_STEPS - 1)
+            } else {
+                step
+            };
+
+            let mut best_step: Option<(usize, Uint128)> = None;
+
+            for (index, route) in self.routes.iter().enumerate() {
+                let Ok(amount_out) =
+                    self.expected_amount_out(deps, env, route, allocations[index] + amount)
+                else {
+                    continue;
+                };
+
+                let marginal = amount_out.saturating_sub(amounts_out[index]);
+
+                let is_better = match best_step {
+                    Some((best_index, best_amount_out)) => {
+                        marginal > best_amount_out.saturating_sub(amounts_out[best_index])
+                    }
+                    None => true,
+                };
+
+                if is_better {
+                    best_step = Some((index, amount_out));
+                }
+            }
+
+            let Some((index, amount_out)) = best_step else {
+                return Ok(None);
+            };
+
+            allocations[index] += amount;
+            amounts_out[index] = amount_out;
+        }
+
+        let total_amount_out = amounts_out.iter().copied().sum::<Uint128>();
+
+        if total_amount_out <= best_quote.state.expected_amount_out.amount {
+            return Ok(None);
+        }
+
+        // Every leg must return the same share of its expected amount out,
+        // so the legs together honour the aggregate minimum receive amount
+        let minimum_receive_ratio =
+            Decimal::from_ratio(best_quote.minimum_receive_amount.amount, total_amount_out);
+
+        let mut legs = vec![];
+
+        for (index, route) in self.routes.iter().enumerate() {
+            if allocations[index].is_zero() {
+                continue;
+            }
+
+            let leg = SwapQuote {
+                swap_amount: Coin::new(allocations[index], self.swap_amount.denom.clone()),
+                minimum_receive_amount: Coin::new(
+                    amounts_out[index].mul_ceil(minimum_receive_ratio),
+                    self.minimum_receive_amount.denom.clone(),
+                ),
+                maximum_slippage_bps: self.maximum_slippage_bps,
+                adjustment: self.adjustment.clone(),
+                route: route.clone(),
+                destination: env.contract.address.clone(),
+                state: Adjusted,
+            }
+            .validate(deps, env);
+
+            match leg {
+                Ok(leg) => legs.push((index, leg)),
+                Err(_) => return Ok(None),
+            }
+        }
+
+        Ok(Some(legs))
+    }
+
     pub fn execute_unsafe(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, Swap)> {
-        let quote = self.best_quote(deps, env)?;
-        let swap_message = quote.execute(deps, env)?;
+        self.execute_available(deps, env, None)
+    }
+
+    // Swaps up to the given amount regardless of the current balance, so that
+    // it can spend funds released by messages sent ahead of it
+    pub fn execute_from(
+        self,
+        deps: Deps,
+        env: &Env,
+        available: Uint128,
+    ) -> StdResult<(Vec<CosmosMsg>, Swap)> {
+        self.execute_available(deps, env, Some(available))
+    }
+
+    fn execute_available(
+        self,
+        deps: Deps,
+        env: &Env,
+        available: Option<Uint128>,
+    ) -> StdResult<(Vec<CosmosMsg>, Swap)> {
+        let quote = self.best_quote_from(deps, env, available)?;
+
+        // Value averaging sells are not split, as legs are sized in the swap denom
+        if self.routing == SwapRouting::Split && quote.swap_amount.denom == self.swap_amount.denom {
+            if let Some(legs) = self.split_quotes(deps, env, &quote)? {
+                let settling = legs.iter().map(|(_, leg)| self.settling(leg)).sum();
+                let (messages, swap) = self.execute_legs(deps, env, legs)?;
+                return Ok((messages, swap.with_pending(deps, env, settling)?));
+            }
+        }
+
+        let settling = self.settling(&quote);
+        let (messages, swap) = self.execute_quote(deps, env, quote)?;
+
+        Ok((messages, swap.with_pending(deps, env, settling)?))
+    }
+
+    // Thorchain buys pay out after the commit, so their minimum receive amount
+    // is credited to the value averaging units in place of the balance change
+    fn settling(&self, quote: &SwapQuote<Executable>) -> Uint128 {
+        match quote.route {
+            SwapRoute::Thorchain(_) if quote.swap_amount.denom == self.swap_amount.denom => {
+                quote.minimum_receive_amount.amount
+            }
+            _ => Uint128::zero(),
+        }
+    }
+
+    // Records the receive denom balance for value averaging swaps to settle on commit
+    fn with_pending(self, deps: Deps, env: &Env, settling: Uint128) -> StdResult<Swap> {
+        let mut swap = self;
+
+        if let SwapAmountAdjustment::ValueAverage { pending, .. } = &mut swap.adjustment {
+            *pending = Some(PendingValueAverageSwap {
+                balance_before: deps
+                    .querier
+                    .query_balance(&env.contract.address, &swap.minimum_receive_amount.denom)?
+                    .amount,
+                settling,
+            });
+        }
+
+        Ok(swap)
+    }
+
+    fn execute_legs(
+        self,
+        deps: Deps,
+        env: &Env,
+        legs: Vec<(usize, SwapQuote<Executable>)>,
+    ) -> StdResult<(Vec<CosmosMsg>, Swap)> {
+        let mut messages = Vec::with_capacity(legs.len());
+        let mut routes = self.routes.clone();
+
+        for (index, leg) in legs {
+            messages.extend(leg.execute(deps, env)?);
+            routes[index] = leg.route;
+        }
+
+        Ok((messages, Swap { routes, ..self }))
+    }
+
+    fn execute_quote(
+        self,
+        deps: Deps,
+        env: &Env,
+        quote: SwapQuote<Executable>,
+    ) -> StdResult<(Vec<CosmosMsg>, Swap)> {
+        let swap_messages = quote.execute(deps, env)?;
+
+        // Value averaging sells run the route in reverse
+        let executed_route = if quote.swap_amount.denom == self.swap_amount.denom {
+            quote.route.clone()
+        } else {
+            quote.route.clone().reversed()
+        };
 
         let updated_routes = self
             .routes
             .iter()
             .map(|r| {
-                if discriminant(r) == discriminant(&quote.route) {
-                    quote.route.clone()
+                if discriminant(r) == discriminant(&executed_route) {
+                    executed_route.clone()
                 } else {
                     r.clone()
                 }
@@ -361,7 +957,7 @@ impl Swap {
             .collect::<Vec<_>>();
 
         Ok((
-            vec![swap_message],
+            swap_messages,
             Swap {
                 // Some routes (i.e. Thorchain) may have relevant state that cannot be
                 // verifiably committed or recreated, so we cache it here.
@@ -375,7 +971,23 @@ impl Swap {
 impl Operation<Swap> for Swap {
     fn init(self, deps: Deps, env: &Env, _affiliates: &[Affiliate]) -> StdResult<Swap> {
         self.validate(deps, env)?;
-        Ok(self.with_affiliates())
+
+        if let SwapAmountAdjustment::ValueAverage {
+            pending: Some(_), ..
+        } = &self.adjustment
+        {
+            return Err(StdError::generic_err(
+                "Cannot initialise a value averaging swap with a pending swap",
+            ));
+        }
+
+        let mut swap = self;
+
+        if let SwapAmountAdjustment::ValueAverage { start, .. } = &mut swap.adjustment {
+            start.get_or_insert(env.block.time);
+        }
+
+        Ok(swap)
     }
 
     fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, Swap)> {
@@ -383,20 +995,67 @@ impl Operation<Swap> for Swap {
     }
 }
 
+impl StatefulOperation<Swap> for Swap {
+    fn balances(&self, _deps: Deps, _env: &Env) -> StdResult<Coins> {
+        Ok(Coins::default())
+    }
+
+    fn cancel(self, _deps: Deps, _env: &Env) -> StdResult<(Vec<CosmosMsg>, Swap)> {
+        Ok((vec![], self))
+    }
+
+    // Settles the units bought or sold by the last value averaging swap from
+    // the change in the receive denom balance since it was sent
+    fn commit(self, deps: Deps, env: &Env) -> StdResult<Swap> {
+        let mut swap = self;
+
+        if let SwapAmountAdjustment::ValueAverage { units, pending, .. } = &mut swap.adjustment {
+            if let Some(PendingValueAverageSwap {
+                balance_before,
+                settling,
+            }) = pending.take()
+            {
+                let balance = deps
+                    .querier
+                    .query_balance(&env.contract.address, &swap.minimum_receive_amount.denom)?
+                    .amount;
+
+                *units = (*units + balance + settling).saturating_sub(balance_before);
+            }
+        }
+
+        Ok(swap)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use calc_rs_test::mocks::mock_dependencies_with_custom_grpc_querier;
     use cosmwasm_std::{
+        from_json,
         testing::{mock_dependencies, mock_env},
         to_json_binary, Addr, Binary, Coin, ContractResult, Decimal, SystemResult, Uint128,
+        WasmQuery,
     };
     use prost::Message;
-    use rujira_rs::{fin::SimulationResponse, proto::types::QueryQuoteSwapResponse};
+    use rujira_rs::{
+        fin::{
+            BookItemResponse, BookResponse, ConfigResponse, Denoms, ExecuteMsg, QueryMsg,
+            SimulationResponse, SwapRequest, Tick,
+        },
+        proto::types::QueryQuoteSwapResponse,
+    };
 
-    use crate::actions::swaps::{
-        fin::FinRoute,
-        swap::{New, SwapAmountAdjustment, SwapQuote, SwapRoute},
-        thor::ThorchainRoute,
+    use crate::{
+        actions::swaps::{
+            fin::{FinHop, FinMultiHopRoute, FinMultiHopSwap, FinRoute},
+            swap::{New, Swap, SwapAmountAdjustment, SwapQuote, SwapRoute, SwapRouting},
+            thor::ThorchainRoute,
+        },
+        conditions::asset_value_ratio::PriceSource,
+        core::Contract,
+        operation::{Operation, StatefulOperation},
+        strategy::StrategyExecuteMsg,
     };
 
     #[test]
@@ -623,4 +1282,611 @@ mod tests {
             quote.swap_amount.amount.mul_floor(Decimal::percent(150))
         );
     }
+
+    #[test]
+    fn split_routing_spreads_swap_across_routes() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(1_000_000u128, "rune")],
+        );
+
+        // Both pairs quote at 1 with slippage growing with the swap amount
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Simulate(offer) => to_json_binary(&SimulationResponse {
+                        returned: offer.amount
+                            - offer.amount * offer.amount / Uint128::new(100_000_000),
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let route = |pair_address: &str| {
+            SwapRoute::Fin(FinRoute {
+                pair_address: Addr::unchecked(pair_address),
+            })
+        };
+
+        let swap = Swap {
+            swap_amount: Coin::new(1_000_000u128, "rune"),
+            minimum_receive_amount: Coin::new(900_000u128, "x/ruji"),
+            maximum_slippage_bps: 100,
+            adjustment: SwapAmountAdjustment::Fixed,
+            routes: vec![route("pair_a"), route("pair_b")],
+            routing: SwapRouting::Split,
+        };
+
+        let (messages, _) = swap.clone().execute(deps.as_ref(), &env).unwrap();
+
+        // Two halves return 497,500 each against 990,000 through a single pair,
+        // and each leg must return 900,000/995,000 of its expected amount
+        assert_eq!(
+            messages,
+            vec![
+                FinRoute {
+                    pair_address: Addr::unchecked("pair_a"),
+                }
+                .execute(
+                    deps.as_ref(),
+                    &env,
+                    &Coin::new(500_000u128, "rune"),
+                    &Coin::new(450_000u128, "x/ruji"),
+                )
+                .unwrap(),
+                FinRoute {
+                    pair_address: Addr::unchecked("pair_b"),
+                }
+                .execute(
+                    deps.as_ref(),
+                    &env,
+                    &Coin::new(500_000u128, "rune"),
+                    &Coin::new(450_000u128, "x/ruji"),
+                )
+                .unwrap(),
+            ]
+        );
+
+        let (messages, _) = Swap {
+            routing: SwapRouting::Best,
+            ..swap.clone()
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        assert_eq!(messages.len(), 1);
+
+        assert!(Swap {
+            routes: vec![route("pair_a")],
+            ..swap
+        }
+        .validate(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("at least two swap routes"));
+    }
+
+    #[test]
+    fn multi_hop_fin_route_chains_hops() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1000u128, "x/ruji")]);
+
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { contract_addr, msg } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: match contract_addr.as_str() {
+                            "ruji_rune" => Denoms::new("x/ruji", "rune"),
+                            _ => Denoms::new("rune", "usdc"),
+                        },
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Simulate(offer) => to_json_binary(&SimulationResponse {
+                        returned: offer.amount.multiply_ratio(995u128, 1000u128),
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let swap = Swap {
+            swap_amount: Coin::new(1000u128, "x/ruji"),
+            minimum_receive_amount: Coin::new(900u128, "usdc"),
+            maximum_slippage_bps: 100,
+            adjustment: SwapAmountAdjustment::Fixed,
+            routes: vec![SwapRoute::FinMultiHop(FinMultiHopRoute {
+                pair_addresses: vec![Addr::unchecked("ruji_rune"), Addr::unchecked("rune_usdc")],
+            })],
+            routing: SwapRouting::Best,
+        };
+
+        assert!(swap.validate(deps.as_ref(), &env).is_ok());
+
+        let (messages, _) = swap.clone().execute(deps.as_ref(), &env).unwrap();
+
+        // The second hop is simulated on the return expected from the first, and
+        // the minimum receive amount protects the last
+        let hops = vec![
+            FinHop {
+                pair_address: Addr::unchecked("ruji_rune"),
+                offer: Coin::new(1000u128, "x/ruji"),
+                expected_return: Coin::new(995u128, "rune"),
+                min_return: Uint128::new(985),
+            },
+            FinHop {
+                pair_address: Addr::unchecked("rune_usdc"),
+                offer: Coin::new(995u128, "rune"),
+                expected_return: Coin::new(990u128, "usdc"),
+                min_return: Uint128::new(900),
+            },
+        ];
+
+        assert_eq!(
+            messages,
+            vec![Contract(env.contract.address.clone()).call(
+                to_json_binary(&StrategyExecuteMsg::SwapFinHops(FinMultiHopSwap {
+                    hops: hops.clone(),
+                    offer_balance_before: None,
+                }))
+                .unwrap(),
+                vec![],
+            )]
+        );
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(20u128, "rune")]);
+
+        let messages = FinMultiHopSwap {
+            hops: hops.clone(),
+            offer_balance_before: None,
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        // The first hop snapshots the rune balance before the remaining hops run
+        assert_eq!(
+            messages,
+            vec![
+                FinRoute {
+                    pair_address: Addr::unchecked("ruji_rune"),
+                }
+                .execute(
+                    deps.as_ref(),
+                    &env,
+                    &Coin::new(1000u128, "x/ruji"),
+                    &Coin::new(985u128, "rune"),
+                )
+                .unwrap(),
+                Contract(env.contract.address.clone()).call(
+                    to_json_binary(&StrategyExecuteMsg::SwapFinHops(FinMultiHopSwap {
+                        hops: vec![hops[1].clone()],
+                        offer_balance_before: Some(Uint128::new(20)),
+                    }))
+                    .unwrap(),
+                    vec![],
+                ),
+            ]
+        );
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1020u128, "rune")]);
+
+        let messages = FinMultiHopSwap {
+            hops: vec![hops[1].clone()],
+            offer_balance_before: Some(Uint128::new(20)),
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap();
+
+        // The last hop spends everything the first hop actually returned
+        assert_eq!(
+            messages,
+            vec![FinRoute {
+                pair_address: Addr::unchecked("rune_usdc"),
+            }
+            .execute(
+                deps.as_ref(),
+                &env,
+                &Coin::new(1000u128, "rune"),
+                &Coin::new(900u128, "usdc"),
+            )
+            .unwrap()]
+        );
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(20u128, "rune")]);
+
+        assert!(FinMultiHopSwap {
+            hops: vec![hops[1].clone()],
+            offer_balance_before: Some(Uint128::new(20)),
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("Nothing received to swap"));
+
+        assert!(Swap {
+            minimum_receive_amount: Coin::new(900u128, "rune"),
+            ..swap
+        }
+        .validate(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("does not end in rune"));
+    }
+
+    #[test]
+    fn value_averaging_buys_behind_and_sells_ahead_of_target() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // Rune is priced at 2 x/ruji
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::from_ratio(2u128, 1u128),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::from_ratio(2u128, 1u128),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Simulate(offer) => to_json_binary(&SimulationResponse {
+                        returned: if offer.denom == "rune" {
+                            offer.amount * Uint128::new(2)
+                        } else {
+                            offer.amount / Uint128::new(2)
+                        },
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let value_average = |units: u128, allow_sells: bool| SwapAmountAdjustment::ValueAverage {
+            increment: Uint128::new(1_000),
+            period: 100,
+            minimum_swap_amount: None,
+            allow_sells,
+            oracle: Some(PriceSource::Fin {
+                address: Addr::unchecked("pair"),
+            }),
+            // Two periods have started, so the target value is 2,000 x/ruji
+            start: Some(env.block.time.minus_seconds(150)),
+            units: Uint128::new(units),
+            pending: None,
+        };
+
+        let quote = SwapQuote {
+            swap_amount: Coin::new(1_000u128, "x/ruji"),
+            minimum_receive_amount: Coin::new(400u128, "rune"),
+            maximum_slippage_bps: 200,
+            adjustment: value_average(100, true),
+            route: SwapRoute::Fin(FinRoute {
+                pair_address: Addr::unchecked("pair"),
+            }),
+            destination: env.contract.address.clone(),
+            state: New,
+        };
+
+        // Rune held beyond the swap's units is not valued against the target
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![
+                Coin::new(10_000u128, "x/ruji"),
+                Coin::new(5_000u128, "rune"),
+            ],
+        );
+
+        // 100 units worth 200 x/ruji buy up to the swap amount
+        let adjusted_quote = quote.clone().adjust(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(adjusted_quote.swap_amount, Coin::new(1_000u128, "x/ruji"));
+        assert_eq!(
+            adjusted_quote.minimum_receive_amount,
+            Coin::new(400u128, "rune")
+        );
+
+        // 1,200 units worth 2,400 x/ruji sell 400 x/ruji worth of rune
+        let adjusted_quote = SwapQuote {
+            adjustment: value_average(1_200, true),
+            ..quote.clone()
+        }
+        .adjust(deps.as_ref(), &env)
+        .unwrap();
+
+        assert_eq!(adjusted_quote.swap_amount, Coin::new(200u128, "rune"));
+        assert_eq!(
+            adjusted_quote.minimum_receive_amount,
+            Coin::new(392u128, "x/ruji")
+        );
+
+        assert!(SwapQuote {
+            adjustment: value_average(1_200, false),
+            ..quote.clone()
+        }
+        .adjust(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("not below the value averaging target"));
+
+        // Sells are capped at the rune the strategy still holds
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(10_000u128, "x/ruji"), Coin::new(150u128, "rune")],
+        );
+
+        let adjusted_quote = SwapQuote {
+            adjustment: value_average(1_200, true),
+            ..quote
+        }
+        .adjust(deps.as_ref(), &env)
+        .unwrap();
+
+        assert_eq!(adjusted_quote.swap_amount, Coin::new(150u128, "rune"));
+
+        // Executing records the rune balance, and the commit settles the rune
+        // received into the swap's units
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![
+                Coin::new(10_000u128, "x/ruji"),
+                Coin::new(5_000u128, "rune"),
+            ],
+        );
+
+        let swap = Swap {
+            swap_amount: Coin::new(1_000u128, "x/ruji"),
+            minimum_receive_amount: Coin::new(400u128, "rune"),
+            maximum_slippage_bps: 200,
+            adjustment: value_average(100, true),
+            routes: vec![SwapRoute::Fin(FinRoute {
+                pair_address: Addr::unchecked("pair"),
+            })],
+            routing: SwapRouting::Best,
+        };
+
+        let (messages, swap) = swap.execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(messages.len(), 1);
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(9_000u128, "x/ruji"), Coin::new(5_500u128, "rune")],
+        );
+
+        let swap = swap.commit(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(swap.adjustment, value_average(600, true));
+    }
+
+    #[test]
+    fn exact_output_receives_at_least_the_target() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.bank.update_balance(
+            &env.contract.address,
+            vec![Coin::new(1_000_000u128, "rune")],
+        );
+
+        deps.querier.update_wasm(|query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price: Decimal::one(),
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Simulate(offer) => to_json_binary(&SimulationResponse {
+                        returned: offer.amount
+                            - offer.amount * offer.amount / Uint128::new(100_000_000),
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        });
+
+        let swap = Swap {
+            swap_amount: Coin::new(1_000_000u128, "rune"),
+            minimum_receive_amount: Coin::new(90_000u128, "x/ruji"),
+            maximum_slippage_bps: 100,
+            adjustment: SwapAmountAdjustment::ExactOutput,
+            routes: vec![SwapRoute::Fin(FinRoute {
+                pair_address: Addr::unchecked("pair"),
+            })],
+            routing: SwapRouting::Best,
+        };
+
+        // 90,081 rune is the smallest input quoted to return 90,000 x/ruji, and
+        // the 9 bps of slippage it is quoted at is within the maximum
+        let quote = swap.best_quote_from(deps.as_ref(), &env, None).unwrap();
+
+        assert_eq!(quote.swap_amount, Coin::new(90_081u128, "rune"));
+        assert_eq!(
+            quote.state.expected_amount_out,
+            Coin::new(90_000u128, "x/ruji")
+        );
+
+        let (messages, _) = swap.clone().execute(deps.as_ref(), &env).unwrap();
+
+        // The swap asks the pair for exactly the target, so no more than the
+        // sized input leaves the strategy and Fin refunds whatever is unused
+        assert_eq!(
+            messages,
+            vec![Contract(Addr::unchecked("pair")).call(
+                to_json_binary(&ExecuteMsg::Swap(SwapRequest::Exact {
+                    exact_return: Uint128::new(90_000),
+                    to: None,
+                    callback: None,
+                }))
+                .unwrap(),
+                vec![Coin::new(90_081u128, "rune")],
+            )]
+        );
+
+        assert!(Swap {
+            maximum_slippage_bps: 5,
+            ..swap.clone()
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("Slippage of 9 bps exceeds maximum allowed of 5 bps"));
+
+        assert!(Swap {
+            minimum_receive_amount: Coin::new(1_000_000u128, "x/ruji"),
+            ..swap.clone()
+        }
+        .execute(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("less than the target receive amount"));
+
+        // Each split leg would need its own exact target, so exact output
+        // swaps only ever take a single route
+        assert!(Swap {
+            routes: vec![
+                SwapRoute::Fin(FinRoute {
+                    pair_address: Addr::unchecked("pair"),
+                }),
+                SwapRoute::Fin(FinRoute {
+                    pair_address: Addr::unchecked("pair2"),
+                }),
+            ],
+            routing: SwapRouting::Split,
+            ..swap
+        }
+        .validate(deps.as_ref(), &env)
+        .unwrap_err()
+        .to_string()
+        .contains("Exact output swaps cannot use split routing"));
+    }
 }
@@ -0,0 +1,464 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coins, CosmosMsg, Decimal, Deps, Env, StdError, StdResult, Uint128};
+
+use crate::{
+    actions::swaps::swap::Swap,
+    conditions::asset_value_ratio::PriceSource,
+    manager::Affiliate,
+    operation::{Operation, StatefulOperation},
+    oracle::fetch_price,
+};
+
+#[cw_serde]
+pub enum Position {
+    // Holding the swap denom: tracks its high and exits when it falls
+    Long,
+    // Holding the receive denom's counter asset: tracks the low of the
+    // receive denom and buys it back when it rises
+    Short,
+}
+
+#[cw_serde]
+pub struct TrailingStop {
+    pub swap: Swap,
+    pub position: Position,
+    pub oracle: PriceSource,
+    pub trail: Decimal,
+    pub take_profit: Option<Decimal>,
+    pub mark: Option<Decimal>,
+    // Set once the exit swap has spent the swap denom, disarming the stop
+    // until it is updated
+    #[serde(default)]
+    pub exited: bool,
+    // Swap denom balance before the exit swap was sent, settled on commit
+    #[serde(default)]
+    pub exiting: Option<Uint128>,
+}
+
+impl TrailingStop {
+    // The tracked asset is priced in the other denom of the exit swap
+    pub fn price(&self, deps: Deps) -> StdResult<Decimal> {
+        let (asset, quote) = match self.position {
+            Position::Long => (
+                &self.swap.swap_amount.denom,
+                &self.swap.minimum_receive_amount.denom,
+            ),
+            Position::Short => (
+                &self.swap.minimum_receive_amount.denom,
+                &self.swap.swap_amount.denom,
+            ),
+        };
+
+        fetch_price(deps, asset, quote, &self.oracle)
+    }
+
+    pub fn next_mark(&self, price: Decimal) -> Decimal {
+        match (&self.position, self.mark) {
+            (_, None) => price,
+            (Position::Long, Some(mark)) => mark.max(price),
+            (Position::Short, Some(mark)) => mark.min(price),
+        }
+    }
+
+    pub fn stop_price(&self, mark: Decimal) -> Decimal {
+        match self.position {
+            Position::Long => mark * (Decimal::one() - self.trail),
+            Position::Short => mark * (Decimal::one() + self.trail),
+        }
+    }
+
+    pub fn is_triggered(&self, price: Decimal, mark: Decimal) -> bool {
+        let stop_price = self.stop_price(mark);
+
+        match self.position {
+            Position::Long => {
+                price <= stop_price
+                    || self
+                        .take_profit
+                        .is_some_and(|take_profit| price >= take_profit)
+            }
+            Position::Short => {
+                price >= stop_price
+                    || self
+                        .take_profit
+                        .is_some_and(|take_profit| price <= take_profit)
+            }
+        }
+    }
+
+    pub fn execute_unsafe(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, Self)> {
+        if self.exited {
+            return Ok((vec![], self));
+        }
+
+        let price = self.price(deps)?;
+        let mark = self.next_mark(price);
+
+        if self.is_triggered(price, mark) {
+            let balance_before = deps
+                .querier
+                .query_balance(&env.contract.address, &self.swap.swap_amount.denom)?
+                .amount;
+
+            let (messages, swap) = self.swap.clone().execute(deps, env)?;
+
+            // An exit that sends nothing (e.g. no balance to swap) stays armed,
+            // and one that does is only disarmed once committed
+            let exiting = (!messages.is_empty()).then_some(balance_before);
+
+            return Ok((
+                messages,
+                TrailingStop {
+                    swap,
+                    mark: Some(mark),
+                    exiting,
+                    ..self
+                },
+            ));
+        }
+
+        Ok((
+            vec![],
+            TrailingStop {
+                mark: Some(mark),
+                ..self
+            },
+        ))
+    }
+}
+
+impl Operation<TrailingStop> for TrailingStop {
+    fn init(self, deps: Deps, env: &Env, affiliates: &[Affiliate]) -> StdResult<TrailingStop> {
+        if self.trail.is_zero() || self.trail >= Decimal::one() {
+            return Err(StdError::generic_err(
+                "Trailing stop trail must be between 0 and 1",
+            ));
+        }
+
+        if self
+            .take_profit
+            .is_some_and(|take_profit| take_profit.is_zero())
+        {
+            return Err(StdError::generic_err(
+                "Trailing stop take profit price cannot be zero",
+            ));
+        }
+
+        if self.mark.is_some() {
+            return Err(StdError::generic_err(
+                "Cannot initialise a trailing stop with a mark already set",
+            ));
+        }
+
+        if self.exited || self.exiting.is_some() {
+            return Err(StdError::generic_err(
+                "Cannot initialise a trailing stop that has already exited",
+            ));
+        }
+
+        self.price(deps)?;
+
+        Ok(TrailingStop {
+            swap: self.swap.clone().init(deps, env, affiliates)?,
+            ..self
+        })
+    }
+
+    fn execute(self, deps: Deps, env: &Env) -> StdResult<(Vec<CosmosMsg>, TrailingStop)> {
+        self.execute_unsafe(deps, env)
+    }
+}
+
+impl StatefulOperation<TrailingStop> for TrailingStop {
+    fn balances(&self, _deps: Deps, _env: &Env) -> StdResult<Coins> {
+        Ok(Coins::default())
+    }
+
+    fn cancel(self, _deps: Deps, _env: &Env) -> StdResult<(Vec<CosmosMsg>, TrailingStop)> {
+        Ok((vec![], self))
+    }
+
+    // Exit swaps are sent with replies that swallow their errors, so the stop
+    // is only disarmed once the swap denom balance shows the exit went through
+    fn commit(self, deps: Deps, env: &Env) -> StdResult<TrailingStop> {
+        let swap = self.swap.clone().commit(deps, env)?;
+
+        if let Some(balance_before) = self.exiting {
+            let balance = deps
+                .querier
+                .query_balance(&env.contract.address, &swap.swap_amount.denom)?
+                .amount;
+
+            if balance < balance_before {
+                return Ok(TrailingStop {
+                    swap,
+                    mark: None,
+                    exited: true,
+                    exiting: None,
+                    ..self
+                });
+            }
+        }
+
+        Ok(TrailingStop {
+            swap,
+            exiting: None,
+            ..self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        to_json_binary, Addr, Coin, ContractResult, QuerierResult, SystemResult, Uint128, WasmMsg,
+        WasmQuery,
+    };
+    use rujira_rs::fin::{
+        BookItemResponse, BookResponse, ConfigResponse, Denoms, ExecuteMsg, QueryMsg,
+        SimulationResponse, Tick,
+    };
+
+    use crate::actions::swaps::{
+        fin::FinRoute,
+        swap::{SwapAmountAdjustment, SwapRoute, SwapRouting},
+    };
+
+    fn trailing_stop() -> TrailingStop {
+        TrailingStop {
+            swap: Swap {
+                swap_amount: Coin::new(1_000u128, "rune"),
+                minimum_receive_amount: Coin::new(1u128, "x/ruji"),
+                maximum_slippage_bps: 100,
+                adjustment: SwapAmountAdjustment::Fixed,
+                routes: vec![SwapRoute::Fin(FinRoute {
+                    pair_address: Addr::unchecked("pair"),
+                })],
+                routing: SwapRouting::Best,
+            },
+            position: Position::Long,
+            oracle: PriceSource::Fin {
+                address: Addr::unchecked("pair"),
+            },
+            trail: Decimal::percent(10),
+            take_profit: Some(Decimal::percent(150)),
+            mark: None,
+            exited: false,
+            exiting: None,
+        }
+    }
+
+    // Answers Fin queries for a pair quoting at the given price on both sides
+    fn book(price: &str) -> impl Fn(&WasmQuery) -> QuerierResult {
+        let price = Decimal::from_str(price).unwrap();
+
+        move |query: &WasmQuery| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Book { limit, .. } => to_json_binary(&BookResponse {
+                        base: vec![
+                            BookItemResponse {
+                                price,
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                        quote: vec![
+                            BookItemResponse {
+                                price,
+                                total: Uint128::new(30_000_000),
+                            };
+                            limit.unwrap() as usize
+                        ],
+                    })
+                    .unwrap(),
+                    QueryMsg::Simulate(offer) => to_json_binary(&SimulationResponse {
+                        returned: offer.amount.mul_floor(price),
+                        fee: Uint128::zero(),
+                    })
+                    .unwrap(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }))
+        }
+    }
+
+    #[test]
+    fn triggers_on_retrace_or_take_profit() {
+        let long = trailing_stop();
+
+        assert!(!long.is_triggered(Decimal::percent(91), Decimal::one()));
+        assert!(long.is_triggered(Decimal::percent(90), Decimal::one()));
+        assert!(long.is_triggered(Decimal::percent(150), Decimal::percent(150)));
+
+        let short = TrailingStop {
+            position: Position::Short,
+            take_profit: Some(Decimal::percent(50)),
+            ..trailing_stop()
+        };
+
+        assert_eq!(short.next_mark(Decimal::percent(80)), Decimal::percent(80));
+        assert!(!short.is_triggered(Decimal::percent(109), Decimal::one()));
+        assert!(short.is_triggered(Decimal::percent(110), Decimal::one()));
+        assert!(short.is_triggered(Decimal::percent(50), Decimal::percent(50)));
+
+        assert!(TrailingStop {
+            trail: Decimal::one(),
+            ..trailing_stop()
+        }
+        .init(mock_dependencies().as_ref(), &mock_env(), &[])
+        .unwrap_err()
+        .to_string()
+        .contains("trail must be between 0 and 1"));
+    }
+
+    #[test]
+    fn execute_ratchets_mark_with_price() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(book("1"));
+
+        let (messages, stop) = trailing_stop().execute(deps.as_ref(), &env).unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(stop.mark, Some(Decimal::one()));
+
+        deps.querier.update_wasm(book("1.2"));
+
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(stop.mark, Some(Decimal::percent(120)));
+
+        // A dip that stays above the trailing stop keeps the high-water mark
+        deps.querier.update_wasm(book("1.1"));
+
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert!(messages.is_empty());
+        assert_eq!(stop.mark, Some(Decimal::percent(120)));
+        assert_eq!(
+            stop.stop_price(Decimal::percent(120)),
+            Decimal::percent(108)
+        );
+    }
+
+    #[test]
+    fn execute_sends_exit_swap_and_disarms_once_committed() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "rune")]);
+
+        deps.querier.update_wasm(book("1.2"));
+
+        let (_, stop) = trailing_stop().execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(stop.mark, Some(Decimal::percent(120)));
+
+        // Falling through the 1.08 stop sells the rune into the pair
+        deps.querier.update_wasm(book("1"));
+
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(messages.len(), 1);
+
+        match &messages[0] {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, "pair");
+                assert_eq!(funds, &vec![Coin::new(1_000u128, "rune")]);
+                assert!(matches!(from_json(msg).unwrap(), ExecuteMsg::Swap(_)));
+            }
+            message => panic!("Expected an exit swap, got {message:?}"),
+        }
+
+        // The stop stays armed until the exit is committed
+        assert!(!stop.exited);
+        assert_eq!(stop.exiting, Some(Uint128::new(1_000)));
+        assert_eq!(stop.mark, Some(Decimal::percent(120)));
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "x/ruji")]);
+
+        let stop = stop.commit(deps.as_ref(), &env).unwrap();
+
+        assert!(stop.exited);
+        assert_eq!(stop.exiting, None);
+        assert_eq!(stop.mark, None);
+
+        // A disarmed stop neither tracks the price nor swaps again
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert!(messages.is_empty());
+        assert!(stop.exited);
+        assert_eq!(stop.mark, None);
+
+        assert!(stop
+            .init(deps.as_ref(), &env, &[])
+            .unwrap_err()
+            .to_string()
+            .contains("already exited"));
+    }
+
+    #[test]
+    fn failed_exit_swap_keeps_stop_armed() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier
+            .bank
+            .update_balance(&env.contract.address, vec![Coin::new(1_000u128, "rune")]);
+
+        deps.querier.update_wasm(book("1.2"));
+
+        let (_, stop) = trailing_stop().execute(deps.as_ref(), &env).unwrap();
+
+        deps.querier.update_wasm(book("1"));
+
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(stop.exiting, Some(Uint128::new(1_000)));
+
+        // The swap failed (e.g. on slippage), so the rune is still held
+        let stop = stop.commit(deps.as_ref(), &env).unwrap();
+
+        assert!(!stop.exited);
+        assert_eq!(stop.exiting, None);
+        assert_eq!(stop.mark, Some(Decimal::percent(120)));
+
+        // The next execution retries the exit
+        let (messages, stop) = stop.execute(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(stop.exiting, Some(Uint128::new(1_000)));
+    }
+}
@@ -1,12 +1,8 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Decimal, Deps, Env, StdError, StdResult};
-use rujira_rs::{
-    fin::{ConfigResponse, QueryMsg},
-    query::Pool,
-    Asset,
-};
+use rujira_rs::fin::{ConfigResponse, QueryMsg};
 
-use crate::rujira::get_mid_price;
+use crate::{oracle::fetch_l1_asset_price, rujira::get_mid_price};
 
 #[cw_serde]
 pub enum PriceSource {
@@ -115,22 +111,6 @@ impl AssetValueRatio {
     }
 }
 
-fn fetch_l1_asset_price(deps: Deps, asset: &str) -> StdResult<Decimal> {
-    let layer_1_asset = Asset::from_denom(&asset.to_string())
-        .map_err(|e| {
-            StdError::generic_err(format!("'{asset}' is not a valid asset: {e}"))
-        })?
-        .to_layer_1();
-
-    Pool::load(deps.querier, &layer_1_asset)
-        .map_err(|e| {
-            StdError::generic_err(format!(
-                "Failed to load oracle price for {layer_1_asset}, error: {e}"
-            ))
-        })
-        .map(|pool| pool.asset_tor_price)
-}
-
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -12,7 +12,7 @@ use rujira_rs::{
 
 use crate::{
     actions::{
-        limit_orders::fin_limit_order::{Direction, FinLimitOrder, PriceStrategy},
+        limit_orders::fin_limit_order::{Direction, FinLimitOrder, LimitOrderStats, PriceStrategy},
         swaps::swap::Swap,
     },
     conditions::{asset_value_ratio::AssetValueRatio, schedule::Schedule},
@@ -32,6 +32,9 @@ pub enum Condition {
         pair_address: Addr,
         side: Side,
         price: Decimal,
+        // Checks an oracle pegged order at this offset instead of a fixed price
+        #[serde(default)]
+        oracle_offset: Option<i16>,
     },
     BalanceAvailable {
         address: Option<Addr>,
@@ -48,6 +51,10 @@ pub enum Condition {
         price: Decimal,
     },
     AssetValueRatio(AssetValueRatio),
+    Once {
+        #[serde(default)]
+        completed: bool,
+    },
 }
 
 impl Condition {
@@ -62,6 +69,7 @@ impl Condition {
             Condition::StrategyStatus { .. } => 2,
             Condition::OraclePrice { .. } => 2,
             Condition::AssetValueRatio(_) => 2,
+            Condition::Once { .. } => 1,
         }
     }
 
@@ -75,13 +83,17 @@ impl Condition {
                 pair_address,
                 side,
                 price,
+                oracle_offset,
             } => {
                 let order = deps.querier.query_wasm_smart::<OrderResponse>(
                     pair_address,
                     &QueryMsg::Order((
                         owner.as_ref().unwrap_or(&env.contract.address).to_string(),
                         side.clone(),
-                        Price::Fixed(*price),
+                        match oracle_offset {
+                            Some(offset) => Price::Oracle(*offset),
+                            None => Price::Fixed(*price),
+                        },
                     )),
                 )?;
 
@@ -137,6 +149,7 @@ impl Condition {
             Condition::AssetValueRatio(asset_value_ratio) => {
                 asset_value_ratio.is_satisfied(deps, env)?
             }
+            Condition::Once { completed } => !completed,
         })
     }
 }
@@ -184,6 +197,7 @@ impl Operation<Condition> for Condition {
                 ref pair_address,
                 ref side,
                 price,
+                oracle_offset,
                 ..
             } => {
                 let pair = deps
@@ -192,6 +206,7 @@ impl Operation<Condition> for Condition {
 
                 let limit_order = FinLimitOrder {
                     pair_address: pair_address.clone(),
+                    pair_denoms: vec![],
                     side: side.clone(),
                     bid_amount: Amount::Fraction(Decimal::percent(100)),
                     bid_denom: if side == &Side::Base {
@@ -201,7 +216,14 @@ impl Operation<Condition> for Condition {
                     }
                     .to_string(),
                     min_fill_ratio: None,
-                    strategy: PriceStrategy::Fixed(price),
+                    time_in_force: None,
+                    fallback: None,
+                    budget: None,
+                    stats: LimitOrderStats::default(),
+                    strategy: match oracle_offset {
+                        Some(offset) => PriceStrategy::Oracle { offset },
+                        None => PriceStrategy::Fixed(price),
+                    },
                     current_order: None,
                 };
 
@@ -222,7 +244,9 @@ impl Operation<Condition> for Condition {
                 asset_value_ratio.validate(deps)?;
                 Ok(self)
             }
-            Condition::BlocksCompleted(_) | Condition::TimestampElapsed(_) => Ok(self),
+            Condition::BlocksCompleted(_)
+            | Condition::TimestampElapsed(_)
+            | Condition::Once { .. } => Ok(self),
         }
     }
 
@@ -238,6 +262,7 @@ impl StatefulOperation<Condition> for Condition {
     fn commit(self, deps: Deps, env: &Env) -> StdResult<Condition> {
         match self {
             Condition::Schedule(schedule) => schedule.commit(deps, env),
+            Condition::Once { .. } => Ok(Condition::Once { completed: true }),
             _ => Ok(self),
         }
     }
@@ -270,7 +295,7 @@ mod conditions_tests {
     use crate::{
         actions::{
             swaps::fin::FinRoute,
-            swaps::swap::{SwapAmountAdjustment, SwapRoute},
+            swaps::swap::{SwapAmountAdjustment, SwapRoute, SwapRouting},
         },
         manager::{Strategy, StrategyStatus},
     };
@@ -418,7 +443,8 @@ mod conditions_tests {
                 pair_address: Addr::unchecked("fin_pair")
             })],
             maximum_slippage_bps: 100,
-            adjustment: SwapAmountAdjustment::Fixed
+            adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         })
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
@@ -430,7 +456,8 @@ mod conditions_tests {
                 pair_address: Addr::unchecked("fin_pair")
             })],
             maximum_slippage_bps: 100,
-            adjustment: SwapAmountAdjustment::Fixed
+            adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         })
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
@@ -442,7 +469,8 @@ mod conditions_tests {
                 pair_address: Addr::unchecked("fin_pair")
             })],
             maximum_slippage_bps: 100,
-            adjustment: SwapAmountAdjustment::Fixed
+            adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         })
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
@@ -474,6 +502,7 @@ mod conditions_tests {
             pair_address: Addr::unchecked("pair"),
             side: Side::Base,
             price: Decimal::from_str("1.0").unwrap(),
+            oracle_offset: None,
         }
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
@@ -499,6 +528,7 @@ mod conditions_tests {
             pair_address: Addr::unchecked("pair"),
             side: Side::Base,
             price: Decimal::from_str("1.0").unwrap(),
+            oracle_offset: None,
         }
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
@@ -515,6 +545,7 @@ mod conditions_tests {
                     id: 1,
                     source: None,
                     contract_address: Addr::unchecked("strategy"),
+                    code_id: 1,
                     status: StrategyStatus::Active,
                     owner: Addr::unchecked("owner"),
                     created_at: 0,
@@ -543,4 +574,19 @@ mod conditions_tests {
         .is_satisfied(deps.as_ref(), &env)
         .unwrap());
     }
+
+    #[test]
+    fn once_check() {
+        let deps = mock_dependencies();
+        let env = mock_env();
+
+        let condition = Condition::Once { completed: false };
+
+        assert!(condition.is_satisfied(deps.as_ref(), &env).unwrap());
+
+        let condition = condition.commit(deps.as_ref(), &env).unwrap();
+
+        assert_eq!(condition, Condition::Once { completed: true });
+        assert!(!condition.is_satisfied(deps.as_ref(), &env).unwrap());
+    }
 }
@@ -1,5 +1,5 @@
 /**
-  Base and Minimum fees in basis points (bps) for the strategy.
+  Default Base and Minimum fees in basis points (bps) for the strategy.
   Taken on all distributions/withdrawals out of the strategy.
   Affiliates can take up to BASE_FEE_BPS - MIN_FEE_BPS
   without increasing the total fees taken by the strategy.
@@ -9,14 +9,63 @@ pub const BASE_FEE_BPS: u64 = 25;
 pub const MIN_FEE_BPS: u64 = 15;
 
 /**
-  Maximum total affiliate basis points (bps) that can be applied to a strategy.
+  Default maximum total affiliate basis points (bps) that can be applied to a strategy.
   This is the maximum amount of bps that can be taken by affiliates to
   prevent excessive fees being applied to strategies.
 */
 pub const MAX_TOTAL_AFFILIATE_BPS: u64 = 200;
 
+/**
+  Default Thorchain affiliate code and basis points (bps) applied to Thorchain swap routes.
+  As per agreement with Rujira.
+*/
+pub const THORCHAIN_AFFILIATE_CODE: &str = "rj";
+pub const THORCHAIN_AFFILIATE_BPS: u64 = 10;
+
+/**
+  Maximum performance fee in basis points (bps) that can be applied to a strategy.
+  Charged on gains above the strategy's high-water mark when funds are withdrawn,
+  in place of the flow fees usually taken on withdrawals. The mark is raised by
+  the gains each fee pays for, so they are only ever charged once.
+*/
+pub const MAX_PERFORMANCE_FEE_BPS: u64 = 3_000;
+
+/**
+  Maximum number of entries kept in a strategy's ledger.
+  The oldest entries are dropped beyond this, which loses nothing from
+  performance reporting as it is kept in running totals.
+*/
+pub const MAX_LEDGER_ENTRIES: u64 = 1_000;
+
 /**
   Maximum size of a strategy as a sum of its node sizes.
   Each node size is determined by the action/condition it contains.
 */
 pub const MAX_STRATEGY_SIZE: usize = 50;
+
+/**
+  Maximum number of price levels in a limit order ladder.
+  Every level can hold a resting order that is refreshed on each execution,
+  so this bounds the queries and messages a single ladder generates.
+*/
+pub const MAX_LADDER_LEVELS: u16 = 20;
+
+/**
+  Number of equal steps a split swap allocates across its routes.
+  Each step quotes every route, so this bounds the queries a split swap makes
+  while keeping legs no smaller than a tenth of the swap amount.
+*/
+pub const SPLIT_SWAP_STEPS: u128 = 10;
+
+/**
+  Maximum number of pairs in a multi-hop Fin swap route.
+  Every hop is simulated when quoting and sent as its own swap message.
+*/
+pub const MAX_FIN_ROUTE_HOPS: usize = 4;
+
+/**
+  Maximum number of quotes used to size the input of an exact output swap.
+  Each iteration re-quotes the route, converging on the smallest input that
+  still returns the requested amount.
+*/
+pub const EXACT_OUTPUT_SIZING_ITERATIONS: usize = 8;
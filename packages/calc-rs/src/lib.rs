@@ -5,7 +5,11 @@ pub mod constants;
 pub mod core;
 pub mod manager;
 pub mod operation;
+pub mod oracle;
+pub mod performance;
 pub mod rujira;
 pub mod scheduler;
 pub mod strategy;
 pub mod thorchain;
+pub mod v0;
+pub mod version;
@@ -1,13 +1,145 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Coin, Deps, StdError, StdResult, Uint128};
 use cw_storage_plus::{Key, Prefixer, PrimaryKey};
 
-use crate::strategy::Node;
+use crate::{
+    actions::distribution::Recipient,
+    conditions::asset_value_ratio::PriceSource,
+    constants::{
+        BASE_FEE_BPS, MAX_TOTAL_AFFILIATE_BPS, MIN_FEE_BPS, THORCHAIN_AFFILIATE_BPS,
+        THORCHAIN_AFFILIATE_CODE,
+    },
+    performance::PerformanceFee,
+    strategy::{DisabledNode, Node},
+};
 
 #[cw_serde]
 pub struct ManagerConfig {
+    pub admin: Option<Addr>,
     pub fee_collector: Addr,
     pub strategy_code_id: u64,
+    #[serde(default)]
+    pub fee_schedule: FeeSchedule,
+}
+
+#[cw_serde]
+pub struct ThorchainAffiliate {
+    pub code: String,
+    pub bps: u64,
+}
+
+#[cw_serde]
+pub struct SourceFee {
+    pub source: String,
+    pub base_fee_bps: u64,
+    pub min_fee_bps: u64,
+}
+
+#[cw_serde]
+pub struct FeeTier {
+    pub denom: String,
+    pub min_balance: Uint128,
+    pub discount_bps: u64,
+}
+
+#[cw_serde]
+pub struct FeeSchedule {
+    pub base_fee_bps: u64,
+    pub min_fee_bps: u64,
+    pub max_total_affiliate_bps: u64,
+    pub thorchain_affiliate: Option<ThorchainAffiliate>,
+    pub sources: Vec<SourceFee>,
+    pub tiers: Vec<FeeTier>,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        FeeSchedule {
+            base_fee_bps: BASE_FEE_BPS,
+            min_fee_bps: MIN_FEE_BPS,
+            max_total_affiliate_bps: MAX_TOTAL_AFFILIATE_BPS,
+            thorchain_affiliate: Some(ThorchainAffiliate {
+                code: THORCHAIN_AFFILIATE_CODE.to_string(),
+                bps: THORCHAIN_AFFILIATE_BPS,
+            }),
+            sources: vec![],
+            tiers: vec![],
+        }
+    }
+}
+
+impl FeeSchedule {
+    pub fn validate(&self) -> StdResult<()> {
+        if self.min_fee_bps > self.base_fee_bps {
+            return Err(StdError::generic_err(format!(
+                "Minimum fee bps ({}) cannot exceed base fee bps ({})",
+                self.min_fee_bps, self.base_fee_bps
+            )));
+        }
+
+        if self.base_fee_bps > 10_000 || self.max_total_affiliate_bps > 10_000 {
+            return Err(StdError::generic_err("Fee bps cannot exceed 10,000 (100%)"));
+        }
+
+        if let Some(affiliate) = &self.thorchain_affiliate {
+            if affiliate.code.is_empty() || affiliate.bps > 10_000 {
+                return Err(StdError::generic_err(format!(
+                    "Invalid Thorchain affiliate: {} ({} bps)",
+                    affiliate.code, affiliate.bps
+                )));
+            }
+        }
+
+        for source in &self.sources {
+            if source.min_fee_bps > source.base_fee_bps || source.base_fee_bps > 10_000 {
+                return Err(StdError::generic_err(format!(
+                    "Invalid fee override for source {}",
+                    source.source
+                )));
+            }
+        }
+
+        for tier in &self.tiers {
+            if tier.denom.is_empty() || tier.discount_bps > 10_000 {
+                return Err(StdError::generic_err(format!(
+                    "Invalid fee tier for denom '{}'",
+                    tier.denom
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn protocol_fee_bps(
+        &self,
+        deps: Deps,
+        source: Option<&String>,
+        owner: &Addr,
+        total_affiliate_bps: u64,
+    ) -> StdResult<u64> {
+        let (base_fee_bps, min_fee_bps) = source
+            .and_then(|source| self.sources.iter().find(|s| &s.source == source))
+            .map_or((self.base_fee_bps, self.min_fee_bps), |s| {
+                (s.base_fee_bps, s.min_fee_bps)
+            });
+
+        let mut discount_bps = 0;
+
+        for tier in &self.tiers {
+            if tier.discount_bps > discount_bps
+                && deps.querier.query_balance(owner, &tier.denom)?.amount >= tier.min_balance
+            {
+                discount_bps = tier.discount_bps;
+            }
+        }
+
+        // The minimum fee is a hard floor that discounts cannot go below
+        Ok(base_fee_bps
+            .saturating_sub(total_affiliate_bps)
+            .saturating_sub(discount_bps)
+            .max(min_fee_bps))
+    }
 }
 
 #[cw_serde]
@@ -57,12 +189,92 @@ pub struct Strategy {
     pub source: Option<String>,
     pub owner: Addr,
     pub contract_address: Addr,
+    // 0 for strategies registered before code tracking, until an admin runs MigrateStrategies
+    #[serde(default)]
+    pub code_id: u64,
     pub created_at: u64,
     pub updated_at: u64,
     pub label: String,
     pub status: StrategyStatus,
 }
 
+#[cw_serde]
+pub struct Valuation {
+    pub quote: String,
+    pub oracle: PriceSource,
+}
+
+#[cw_serde]
+pub struct PortfolioStrategy {
+    pub strategy: Strategy,
+    pub balances: Vec<Coin>,
+    pub value: Option<Uint128>,
+    // Set when the strategy's balances could not be queried
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct Portfolio {
+    pub owner: Addr,
+    pub strategies: Vec<PortfolioStrategy>,
+    pub balances: Vec<Coin>,
+    // Only set when every strategy on the page could be valued
+    pub value: Option<Uint128>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct AddressBook {
+    pub addresses: Vec<Addr>,
+    pub memos: Vec<String>,
+}
+
+impl AddressBook {
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.memos.is_empty()
+    }
+
+    pub fn allows(&self, recipient: &Recipient) -> bool {
+        match recipient {
+            Recipient::Bank { address } | Recipient::Contract { address, .. } => {
+                self.addresses.contains(address)
+            }
+            Recipient::Deposit { memo } => self.memos.contains(memo),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PendingAddressBook {
+    pub owner: Addr,
+    pub additions: AddressBook,
+    pub queued_at: u64,
+    pub eta: u64,
+}
+
+#[cw_serde]
+pub struct Timelock {
+    pub duration: u64,
+    pub guardian: Option<Addr>,
+}
+
+#[cw_serde]
+pub enum PendingChange {
+    Nodes(Vec<Node>),
+    Status(StrategyStatus),
+    Timelock(Option<Timelock>),
+    CodeId(u64),
+    ReleaseAdmin,
+}
+
+#[cw_serde]
+pub struct PendingUpdate {
+    pub contract_address: Addr,
+    pub change: PendingChange,
+    pub queued_at: u64,
+    pub eta: u64,
+}
+
 #[cw_serde]
 pub enum ManagerExecuteMsg {
     Instantiate {
@@ -71,6 +283,14 @@ pub enum ManagerExecuteMsg {
         label: String,
         affiliates: Vec<Affiliate>,
         nodes: Vec<Node>,
+        checkpoint_interval: Option<u16>,
+        #[serde(default)]
+        restrict_outflows: bool,
+        performance_fee: Option<PerformanceFee>,
+        #[serde(default)]
+        migratable: bool,
+        #[serde(default)]
+        valuation: Option<Valuation>,
     },
     Execute {
         contract_address: Addr,
@@ -87,6 +307,45 @@ pub enum ManagerExecuteMsg {
         contract_address: Addr,
         label: String,
     },
+    SetDisabledNodes {
+        contract_address: Addr,
+        nodes: Vec<DisabledNode>,
+    },
+    SetTimelock {
+        contract_address: Addr,
+        timelock: Option<Timelock>,
+    },
+    ApplyPendingUpdate {
+        contract_address: Addr,
+    },
+    CancelPendingUpdate {
+        contract_address: Addr,
+    },
+    UpdateAddressBook {
+        addresses: Vec<Addr>,
+        memos: Vec<String>,
+    },
+    ApplyPendingAddressBook {
+        owner: Addr,
+    },
+    CancelPendingAddressBook {
+        owner: Addr,
+    },
+    UpdateConfig(ManagerConfig),
+    SetPaused {
+        paused: bool,
+    },
+    MigrateStrategies {
+        start_after: Option<Addr>,
+        limit: Option<u16>,
+    },
+    ReleaseAdmin {
+        contract_address: Addr,
+    },
+    ReindexStrategies {
+        start_after: Option<Addr>,
+        limit: Option<u16>,
+    },
 }
 
 #[cw_serde]
@@ -104,10 +363,157 @@ pub enum ManagerQueryMsg {
         limit: Option<u16>,
     },
     #[returns(Vec<Strategy>)]
+    StrategiesBySource {
+        source: String,
+        start_after: Option<u64>,
+        limit: Option<u16>,
+    },
+    #[returns(Vec<Strategy>)]
+    StrategiesByLabel {
+        prefix: String,
+        start_after: Option<Addr>,
+        limit: Option<u16>,
+    },
+    #[returns(Vec<Strategy>)]
+    StrategiesByDenom {
+        denom: String,
+        start_after: Option<Addr>,
+        limit: Option<u16>,
+    },
+    #[returns(Vec<Strategy>)]
+    StrategiesByPair {
+        pair_address: Addr,
+        start_after: Option<Addr>,
+        limit: Option<u16>,
+    },
+    #[returns(Portfolio)]
+    Portfolio {
+        owner: Addr,
+        valuation: Option<Valuation>,
+        start_after: Option<(u64, Addr)>,
+        limit: Option<u16>,
+    },
+    #[returns(Vec<Strategy>)]
     StrategiesById {
         start_after: Option<Addr>,
         limit: Option<u16>,
     },
     #[returns(u64)]
     Count {},
+    #[returns(Option<Timelock>)]
+    Timelock { address: Addr },
+    #[returns(Option<PendingUpdate>)]
+    PendingUpdate { address: Addr },
+    #[returns(AddressBook)]
+    AddressBook { owner: Addr },
+    #[returns(Option<PendingAddressBook>)]
+    PendingAddressBook { owner: Addr },
+    #[returns(bool)]
+    Paused {},
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, testing::mock_dependencies, Coin, Uint128};
+
+    use crate::manager::{FeeSchedule, FeeTier, ManagerExecuteMsg, SourceFee};
+
+    #[test]
+    fn protocol_fee_applies_source_overrides_and_tiers() {
+        let mut deps = mock_dependencies();
+        let owner = deps.api.addr_make("owner");
+        let holder = deps.api.addr_make("holder");
+
+        deps.querier
+            .bank
+            .update_balance(&holder, vec![Coin::new(1_000_u128, "x/ruji")]);
+
+        let fee_schedule = FeeSchedule {
+            sources: vec![SourceFee {
+                source: "frontend".to_string(),
+                base_fee_bps: 20,
+                min_fee_bps: 5,
+            }],
+            tiers: vec![
+                FeeTier {
+                    denom: "x/ruji".to_string(),
+                    min_balance: Uint128::new(100),
+                    discount_bps: 5,
+                },
+                FeeTier {
+                    denom: "x/ruji".to_string(),
+                    min_balance: Uint128::new(1_000),
+                    discount_bps: 10,
+                },
+                FeeTier {
+                    denom: "x/ruji".to_string(),
+                    min_balance: Uint128::new(10_000),
+                    discount_bps: 15,
+                },
+            ],
+            ..FeeSchedule::default()
+        };
+
+        fee_schedule.validate().unwrap();
+
+        let fee = |source: Option<&str>, owner, affiliate_bps| {
+            fee_schedule
+                .protocol_fee_bps(
+                    deps.as_ref(),
+                    source.map(|s| s.to_string()).as_ref(),
+                    owner,
+                    affiliate_bps,
+                )
+                .unwrap()
+        };
+
+        assert_eq!(fee(None, &owner, 0), 25);
+        assert_eq!(fee(None, &owner, 5), 20);
+        assert_eq!(fee(None, &owner, 50), 15);
+        assert_eq!(fee(Some("frontend"), &owner, 0), 20);
+        assert_eq!(fee(Some("frontend"), &owner, 50), 5);
+        assert_eq!(fee(Some("unknown"), &owner, 0), 25);
+        assert_eq!(fee(None, &holder, 0), 15);
+        assert_eq!(fee(None, &holder, 5), 15);
+        assert_eq!(fee(Some("frontend"), &holder, 0), 10);
+        assert_eq!(fee(Some("frontend"), &holder, 50), 5);
+    }
+
+    #[test]
+    fn fee_schedule_validation_rejects_inverted_bounds() {
+        assert!(FeeSchedule {
+            min_fee_bps: 30,
+            ..FeeSchedule::default()
+        }
+        .validate()
+        .is_err());
+
+        assert!(FeeSchedule {
+            sources: vec![SourceFee {
+                source: "frontend".to_string(),
+                base_fee_bps: 5,
+                min_fee_bps: 10,
+            }],
+            ..FeeSchedule::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn instantiate_msg_defaults_new_flags() {
+        let msg = from_json::<ManagerExecuteMsg>(
+            r#"{"instantiate":{"label":"Test","affiliates":[],"nodes":[]}}"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            msg,
+            ManagerExecuteMsg::Instantiate {
+                restrict_outflows: false,
+                migratable: false,
+                ..
+            }
+        ));
+    }
 }
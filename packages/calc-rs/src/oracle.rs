@@ -0,0 +1,189 @@
+use cosmwasm_std::{Coin, Decimal, Deps, StdError, StdResult, Uint128};
+use rujira_rs::{
+    fin::{ConfigResponse, QueryMsg},
+    query::Pool,
+    Asset,
+};
+
+use crate::{
+    conditions::asset_value_ratio::PriceSource, rujira::get_mid_price, thorchain::Network,
+};
+
+pub fn fetch_l1_asset_price(deps: Deps, asset: &str) -> StdResult<Decimal> {
+    if asset == "rune" {
+        return Network::load(deps.querier)
+            .map_err(|e| {
+                StdError::generic_err(format!(
+                    "Failed to load oracle price for THOR.RUNE, error: {e}"
+                ))
+            })
+            .map(|network| network.rune_price_in_tor);
+    }
+
+    let layer_1_asset = Asset::from_denom(&asset.to_string())
+        .map_err(|e| StdError::generic_err(format!("'{asset}' is not a valid asset: {e}")))?
+        .to_layer_1();
+
+    Pool::load(deps.querier, &layer_1_asset)
+        .map_err(|e| {
+            StdError::generic_err(format!(
+                "Failed to load oracle price for {layer_1_asset}, error: {e}"
+            ))
+        })
+        .map(|pool| pool.asset_tor_price)
+}
+
+pub fn fetch_price(
+    deps: Deps,
+    denom: &str,
+    quote: &str,
+    oracle: &PriceSource,
+) -> StdResult<Decimal> {
+    match oracle {
+        PriceSource::Thorchain => fetch_l1_asset_price(deps, denom)?
+            .checked_div(fetch_l1_asset_price(deps, quote)?)
+            .map_err(|_| StdError::generic_err(format!("L1 oracle price for '{quote}' is zero"))),
+        PriceSource::Fin { address } => {
+            let pair = deps
+                .querier
+                .query_wasm_smart::<ConfigResponse>(address.clone(), &QueryMsg::Config {})?;
+
+            if pair.denoms.base() == denom && pair.denoms.quote() == quote {
+                return get_mid_price(deps, address);
+            }
+
+            if pair.denoms.base() == quote && pair.denoms.quote() == denom {
+                return Decimal::one()
+                    .checked_div(get_mid_price(deps, address)?)
+                    .map_err(|_| {
+                        StdError::generic_err(format!("Pair at {address} has a zero price"))
+                    });
+            }
+
+            Err(StdError::generic_err(format!(
+                "Pair at {address} cannot price {denom} in {quote}"
+            )))
+        }
+    }
+}
+
+pub fn value_in(
+    deps: Deps,
+    coins: &[Coin],
+    denom: &str,
+    oracle: &PriceSource,
+) -> StdResult<Uint128> {
+    let mut value = Uint128::zero();
+
+    for coin in coins {
+        if coin.amount.is_zero() {
+            continue;
+        }
+
+        if coin.denom == denom {
+            value += coin.amount;
+            continue;
+        }
+
+        value += coin
+            .amount
+            .mul_floor(fetch_price(deps, &coin.denom, denom, oracle)?);
+    }
+
+    Ok(value)
+}
+
+// Values only the coins the oracle can price, so that a denom without a
+// price can neither block nor skew a valuation
+pub fn value_priceable_in(
+    deps: Deps,
+    coins: &[Coin],
+    denom: &str,
+    oracle: &PriceSource,
+) -> Uint128 {
+    coins
+        .iter()
+        .map(|coin| value_in(deps, std::slice::from_ref(coin), denom, oracle).unwrap_or_default())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use calc_rs_test::{fixtures::mock_pool, mocks::mock_dependencies_with_custom_grpc_querier};
+    use cosmwasm_std::{testing::mock_dependencies, Coin, ContractResult, SystemResult, Uint128};
+
+    use crate::{
+        conditions::asset_value_ratio::PriceSource,
+        oracle::{value_in, value_priceable_in},
+    };
+
+    #[test]
+    fn values_coins_in_denom() {
+        let mut deps = mock_dependencies_with_custom_grpc_querier();
+
+        deps.querier.with_grpc_handler(|query| {
+            SystemResult::Ok(ContractResult::Ok(mock_pool(query.data.clone()).unwrap()))
+        });
+
+        assert_eq!(
+            value_in(
+                deps.as_ref(),
+                &[
+                    Coin::new(1_u128, "btc-btc"),
+                    Coin::new(100_u128, "eth-usdc"),
+                ],
+                "eth-usdc",
+                &PriceSource::Thorchain
+            )
+            .unwrap(),
+            Uint128::new(100_100)
+        );
+
+        assert_eq!(
+            value_in(
+                deps.as_ref(),
+                &[Coin::new(200_200_u128, "eth-usdc")],
+                "btc-btc",
+                &PriceSource::Thorchain
+            )
+            .unwrap(),
+            Uint128::new(2)
+        );
+    }
+
+    #[test]
+    fn values_denom_without_oracle() {
+        let deps = mock_dependencies();
+
+        assert_eq!(
+            value_in(
+                deps.as_ref(),
+                &[
+                    Coin::new(100_u128, "eth-usdc"),
+                    Coin::new(0_u128, "btc-btc")
+                ],
+                "eth-usdc",
+                &PriceSource::Thorchain
+            )
+            .unwrap(),
+            Uint128::new(100)
+        );
+    }
+
+    #[test]
+    fn values_only_priceable_coins() {
+        let deps = mock_dependencies();
+
+        let coins = [
+            Coin::new(100_u128, "eth-usdc"),
+            Coin::new(1_u128, "btc-btc"),
+        ];
+
+        assert!(value_in(deps.as_ref(), &coins, "eth-usdc", &PriceSource::Thorchain).is_err());
+
+        assert_eq!(
+            value_priceable_in(deps.as_ref(), &coins, "eth-usdc", &PriceSource::Thorchain),
+            Uint128::new(100)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal, Deps, StdError, StdResult, Uint128};
+
+use crate::{constants::MAX_PERFORMANCE_FEE_BPS, oracle::fetch_l1_asset_price};
+
+#[cw_serde]
+pub struct PerformanceFee {
+    pub bps: u64,
+    pub denom: String,
+}
+
+impl PerformanceFee {
+    pub fn validate(&self, deps: Deps) -> StdResult<()> {
+        if self.bps == 0 || self.bps > MAX_PERFORMANCE_FEE_BPS {
+            return Err(StdError::generic_err(format!(
+                "Performance fee bps must be between 1 and {MAX_PERFORMANCE_FEE_BPS}, got {}",
+                self.bps
+            )));
+        }
+
+        fetch_l1_asset_price(deps, &self.denom)?;
+
+        Ok(())
+    }
+}
+
+#[cw_serde]
+pub struct HighWaterMark {
+    pub fee: PerformanceFee,
+    pub value: Uint128,
+}
+
+impl HighWaterMark {
+    pub fn new(fee: PerformanceFee) -> Self {
+        HighWaterMark {
+            fee,
+            value: Uint128::zero(),
+        }
+    }
+
+    pub fn deposit(&mut self, value: Uint128) {
+        self.value += value;
+    }
+
+    // Returns the share of the withdrawal owed as a performance fee. The fee on
+    // all gains above the mark is taken from the withdrawal as far as it covers
+    // it, and the mark is raised by the gains paid for so they are never charged
+    // again. The mark then shrinks pro-rata with the funds that leave.
+    pub fn withdraw(&mut self, nav: Uint128, withdrawn: Uint128) -> Decimal {
+        if nav.is_zero() || withdrawn.is_zero() {
+            return Decimal::zero();
+        }
+
+        let withdrawn = withdrawn.min(nav);
+        let gains = nav.saturating_sub(self.value);
+        let owed = gains.mul_floor(Decimal::bps(self.fee.bps));
+        let fee = owed.min(withdrawn);
+
+        let paid_for = if owed.is_zero() {
+            gains
+        } else {
+            gains.multiply_ratio(fee, owed)
+        };
+
+        self.value = (self.value + paid_for).multiply_ratio(nav - withdrawn, nav);
+
+        Decimal::from_ratio(fee, withdrawn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::mock_dependencies, Decimal, Uint128};
+
+    use crate::performance::{HighWaterMark, PerformanceFee};
+
+    fn high_water_mark(value: u128) -> HighWaterMark {
+        HighWaterMark {
+            fee: PerformanceFee {
+                bps: 2_000,
+                denom: "eth-usdc".to_string(),
+            },
+            value: Uint128::new(value),
+        }
+    }
+
+    #[test]
+    fn charges_all_gains_above_mark_and_raises_it() {
+        let mut mark = high_water_mark(1_000);
+
+        assert_eq!(
+            mark.withdraw(Uint128::new(1_500), Uint128::new(300)),
+            Decimal::from_ratio(1_u128, 3_u128)
+        );
+        assert_eq!(mark.value, Uint128::new(1_200));
+
+        // Recovering to the raised mark is not charged again
+        assert_eq!(
+            mark.withdraw(Uint128::new(1_200), Uint128::new(1_200)),
+            Decimal::zero()
+        );
+        assert_eq!(mark.value, Uint128::zero());
+    }
+
+    #[test]
+    fn only_raises_mark_by_gains_the_withdrawal_pays_for() {
+        let mut mark = high_water_mark(1_000);
+
+        assert_eq!(
+            mark.withdraw(Uint128::new(1_500), Uint128::new(50)),
+            Decimal::one()
+        );
+        assert_eq!(mark.value, Uint128::new(1_208));
+
+        assert_eq!(
+            mark.withdraw(Uint128::new(1_450), Uint128::new(100)),
+            Decimal::percent(48)
+        );
+        assert_eq!(mark.value, Uint128::new(1_350));
+    }
+
+    #[test]
+    fn charges_nothing_below_mark() {
+        let mut mark = high_water_mark(1_000);
+
+        assert_eq!(
+            mark.withdraw(Uint128::new(800), Uint128::new(400)),
+            Decimal::zero()
+        );
+        assert_eq!(mark.value, Uint128::new(500));
+
+        mark.deposit(Uint128::new(100));
+
+        assert_eq!(mark.value, Uint128::new(600));
+        assert_eq!(
+            mark.withdraw(Uint128::zero(), Uint128::zero()),
+            Decimal::zero()
+        );
+    }
+
+    #[test]
+    fn validates_fee_bps() {
+        let deps = mock_dependencies();
+
+        assert!(PerformanceFee {
+            bps: 0,
+            denom: "eth-usdc".to_string(),
+        }
+        .validate(deps.as_ref())
+        .unwrap_err()
+        .to_string()
+        .contains("Performance fee bps must be between 1 and"));
+
+        assert!(PerformanceFee {
+            bps: 10_000,
+            denom: "eth-usdc".to_string(),
+        }
+        .validate(deps.as_ref())
+        .unwrap_err()
+        .to_string()
+        .contains("Performance fee bps must be between 1 and"));
+    }
+}
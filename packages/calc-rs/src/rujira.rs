@@ -84,6 +84,33 @@ pub fn get_side_price(deps: Deps, pair_address: &Addr, side: &Side) -> StdResult
     Ok(price)
 }
 
+// Pair denoms are resolved once so they can be read back without querying the pair
+pub fn get_pair_denoms(
+    deps: Deps,
+    pair_address: &Addr,
+    expected: &[String],
+) -> StdResult<Vec<String>> {
+    let pair = deps
+        .querier
+        .query_wasm_smart::<ConfigResponse>(pair_address, &QueryMsg::Config {})?;
+
+    let denoms = vec![
+        pair.denoms.base().to_string(),
+        pair.denoms.quote().to_string(),
+    ];
+
+    if !expected.is_empty() && expected != denoms {
+        return Err(StdError::generic_err(format!(
+            "Pair {pair_address} trades {} and {}, not {}",
+            denoms[0],
+            denoms[1],
+            expected.join(" and ")
+        )));
+    }
+
+    Ok(denoms)
+}
+
 pub fn get_mid_price(deps: Deps, address: &Addr) -> StdResult<Decimal> {
     let quote_price = get_side_price(deps, address, &Side::Quote)?;
     let base_price = get_side_price(deps, address, &Side::Base)?;
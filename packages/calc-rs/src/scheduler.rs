@@ -52,6 +52,7 @@ impl CreateTriggerMsg {
                 pair_address,
                 side,
                 price,
+                oracle_offset,
             } => {
                 2u8.hash(&mut hasher);
                 owner
@@ -61,6 +62,11 @@ impl CreateTriggerMsg {
                 pair_address.hash(&mut hasher);
                 side.to_string().hash(&mut hasher);
                 price.to_string().hash(&mut hasher);
+
+                // Only hashed when set so existing trigger ids are unchanged
+                if let Some(offset) = oracle_offset {
+                    offset.hash(&mut hasher);
+                }
             }
             _ => Err(StdError::generic_err(format!(
                 "ID generation for condition {:?} not supported",
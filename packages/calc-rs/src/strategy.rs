@@ -1,13 +1,25 @@
 use std::vec;
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Coin, Coins, CosmosMsg, Deps, Env, StdResult};
+use cosmwasm_std::{
+    Addr, Coin, Coins, CosmosMsg, Decimal, Deps, Env, Int128, SignedDecimal, StdError, StdResult,
+    Uint128,
+};
+use rujira_rs::fin::Side;
 
 use crate::{
-    actions::action::Action,
-    conditions::condition::Condition,
-    manager::Affiliate,
+    actions::{
+        action::Action,
+        distribution::{Destination, Recipient},
+        limit_orders::fin_limit_order::{FinLimitOrder, LimitOrderStats},
+        swaps::{fin::FinMultiHopSwap, swap::SwapRoute, thor::StreamingSwap},
+        trailing_stop::TrailingStop,
+    },
+    cadence::Cadence,
+    conditions::{asset_value_ratio::PriceSource, condition::Condition},
+    manager::{Affiliate, ThorchainAffiliate, Valuation},
     operation::{Operation, StatefulOperation},
+    performance::{HighWaterMark, PerformanceFee},
 };
 
 #[cw_serde]
@@ -33,25 +45,46 @@ impl StrategyOperation {
     }
 }
 
+#[cw_serde]
+pub enum Branch {
+    OnSuccess,
+    OnFailure,
+}
+
+#[cw_serde]
+pub struct DisabledNode {
+    pub index: u16,
+    pub branch: Option<Branch>,
+}
+
 #[cw_serde]
 pub struct StrategyInstantiateMsg {
     pub contract_address: Addr,
     pub owner: Addr,
     pub affiliates: Vec<Affiliate>,
     pub nodes: Vec<Node>,
+    pub checkpoint_interval: Option<u16>,
+    pub restrict_outflows: bool,
+    pub performance_fee: Option<PerformanceFee>,
+    pub thorchain_affiliate: Option<ThorchainAffiliate>,
+    #[serde(default)]
+    pub valuation: Option<Valuation>,
 }
 
 #[cw_serde]
 pub enum StrategyExecuteMsg {
     Init(Vec<Node>),
     Execute {},
+    Deposit {},
     Withdraw(Vec<Coin>),
     Update(Vec<Node>),
     Cancel {},
+    SetDisabledNodes(Vec<DisabledNode>),
     Process {
         operation: StrategyOperation,
         previous: Option<u16>,
     },
+    SwapFinHops(FinMultiHopSwap),
 }
 
 #[cw_serde]
@@ -61,6 +94,400 @@ pub enum StrategyQueryMsg {
     Config {},
     #[returns(Vec<Coin>)]
     Balances {},
+    #[returns(Option<u16>)]
+    Checkpoint {},
+    #[returns(Vec<DisabledNode>)]
+    DisabledNodes {},
+    #[returns(UpdatePreview)]
+    UpdatePreview { nodes: Vec<Node> },
+    #[returns(Option<HighWaterMark>)]
+    HighWaterMark {},
+    #[returns(Performance)]
+    Performance {
+        quote: Option<String>,
+        oracle: Option<PriceSource>,
+    },
+    #[returns(Vec<LedgerEntry>)]
+    Ledger {
+        start_after: Option<u64>,
+        limit: Option<u16>,
+    },
+    #[returns(Vec<LimitOrderNodeStats>)]
+    LimitOrderStats {},
+}
+
+#[cw_serde]
+pub enum LedgerEvent {
+    Deposit,
+    Withdrawal,
+    Distribution,
+    Fee,
+}
+
+#[cw_serde]
+pub struct LedgerEntry {
+    pub id: u64,
+    pub time: u64,
+    pub event: LedgerEvent,
+    pub amounts: Vec<Coin>,
+    // Values of the amounts and of the strategy just before a deposit or
+    // withdrawal, if the strategy has a valuation. Distributions and their fees
+    // are recorded unvalued and folded into performance with the next valuation.
+    #[serde(default)]
+    pub value: Option<Uint128>,
+    #[serde(default)]
+    pub nav: Option<Uint128>,
+}
+
+// Running totals updated as each ledger entry is recorded, so performance
+// never has to revisit the ledger
+#[cw_serde]
+pub struct PerformanceTotals {
+    pub deposited: Uint128,
+    pub withdrawn: Uint128,
+    pub distributed: Uint128,
+    pub fees: Uint128,
+    pub cost_basis: Uint128,
+    pub realised_pnl: Int128,
+    pub growth: Decimal,
+    pub nav_after: Option<Uint128>,
+}
+
+impl Default for PerformanceTotals {
+    fn default() -> Self {
+        PerformanceTotals {
+            deposited: Uint128::zero(),
+            withdrawn: Uint128::zero(),
+            distributed: Uint128::zero(),
+            fees: Uint128::zero(),
+            cost_basis: Uint128::zero(),
+            realised_pnl: Int128::zero(),
+            growth: Decimal::one(),
+            nav_after: None,
+        }
+    }
+}
+
+impl PerformanceTotals {
+    // Takes the value of the event and of the strategy just before it
+    pub fn record(&mut self, event: &LedgerEvent, value: Uint128, nav: Uint128) -> StdResult<()> {
+        if let Some(nav_after) = self.nav_after {
+            if !nav_after.is_zero() {
+                self.growth *= Decimal::from_ratio(nav, nav_after);
+            }
+        }
+
+        self.nav_after = Some(match event {
+            LedgerEvent::Deposit => {
+                self.deposited += value;
+                self.cost_basis += value;
+                nav + value
+            }
+            LedgerEvent::Withdrawal | LedgerEvent::Distribution => {
+                let cost = if nav.is_zero() {
+                    self.cost_basis
+                } else {
+                    self.cost_basis.multiply_ratio(value.min(nav), nav)
+                };
+
+                if *event == LedgerEvent::Withdrawal {
+                    self.withdrawn += value;
+                } else {
+                    self.distributed += value;
+                }
+
+                self.cost_basis -= cost;
+                self.realised_pnl += Int128::try_from(value)? - Int128::try_from(cost)?;
+                nav.saturating_sub(value)
+            }
+            // Fees are a cost of the strategy rather than a flow out of it, so
+            // the drop in value they cause is left in the return
+            LedgerEvent::Fee => {
+                self.fees += value;
+                nav
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cw_serde]
+pub struct Performance {
+    pub quote: String,
+    pub nav: Uint128,
+    pub deposited: Uint128,
+    pub withdrawn: Uint128,
+    pub distributed: Uint128,
+    pub fees: Uint128,
+    pub net_deposits: Int128,
+    pub realised_pnl: Int128,
+    pub unrealised_pnl: Int128,
+    pub time_weighted_return: SignedDecimal,
+}
+
+impl Performance {
+    pub fn new(quote: String, totals: &PerformanceTotals, nav: Uint128) -> StdResult<Self> {
+        let mut growth = totals.growth;
+
+        if let Some(nav_after) = totals.nav_after {
+            if !nav_after.is_zero() {
+                growth *= Decimal::from_ratio(nav, nav_after);
+            }
+        }
+
+        Ok(Performance {
+            quote,
+            nav,
+            deposited: totals.deposited,
+            withdrawn: totals.withdrawn,
+            distributed: totals.distributed,
+            fees: totals.fees,
+            net_deposits: Int128::try_from(totals.deposited)?
+                - Int128::try_from(totals.withdrawn)?
+                - Int128::try_from(totals.distributed)?,
+            realised_pnl: totals.realised_pnl,
+            unrealised_pnl: Int128::try_from(nav)? - Int128::try_from(totals.cost_basis)?,
+            time_weighted_return: SignedDecimal::try_from(growth)
+                .map_err(|_| StdError::generic_err("Time weighted return out of range"))?
+                - SignedDecimal::one(),
+        })
+    }
+}
+
+#[cw_serde]
+pub struct WithdrawnOrder {
+    pub index: u16,
+    pub pair_address: Addr,
+    pub side: Side,
+    pub price: Decimal,
+    pub oracle_offset: Option<i16>,
+    pub released: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct LimitOrderNodeStats {
+    pub index: u16,
+    pub pair_address: Addr,
+    pub side: Side,
+    pub budget: Option<Uint128>,
+    pub stats: LimitOrderStats,
+    pub average_price: Option<Decimal>,
+}
+
+#[cw_serde]
+pub struct RecreatedSchedule {
+    pub index: u16,
+    pub cadence: Cadence,
+    pub executions: Option<u32>,
+    pub reset: bool,
+}
+
+#[cw_serde]
+pub struct LostDistribution {
+    pub index: u16,
+    pub recipient: Recipient,
+    pub label: Option<String>,
+    pub distributions: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct LostStreamingSwap {
+    pub index: u16,
+    pub latest_swap: StreamingSwap,
+}
+
+#[cw_serde]
+pub struct UpdatePreview {
+    pub withdrawn_orders: Vec<WithdrawnOrder>,
+    pub released: Vec<Coin>,
+    pub recreated_schedules: Vec<RecreatedSchedule>,
+    pub lost_distributions: Vec<LostDistribution>,
+    pub lost_swaps: Vec<LostStreamingSwap>,
+}
+
+impl UpdatePreview {
+    pub fn new(deps: Deps, env: &Env, current: &[Node], proposed: &[Node]) -> StdResult<Self> {
+        let mut withdrawn_orders = vec![];
+        let mut released = Coins::default();
+        let mut recreated_schedules = vec![];
+        let mut lost_distributions = vec![];
+        let mut lost_swaps = vec![];
+
+        for node in current {
+            let index = node.index();
+            let proposed_node = proposed.get(index as usize);
+
+            match node {
+                Node::Action {
+                    action: Action::LimitOrder(limit_order),
+                    ..
+                } => {
+                    // Update cancels every node, so all resting orders are withdrawn
+                    if let Some(current_order) = &limit_order.current_order {
+                        let balances = limit_order.balances(deps, env)?;
+
+                        for balance in balances.iter() {
+                            released.add(balance.clone())?;
+                        }
+
+                        withdrawn_orders.push(WithdrawnOrder {
+                            index,
+                            pair_address: limit_order.pair_address.clone(),
+                            side: limit_order.side.clone(),
+                            price: current_order.price,
+                            oracle_offset: current_order.oracle_offset,
+                            released: balances.into_vec(),
+                        });
+                    }
+                }
+                Node::Action {
+                    action: Action::MarketMake(market_maker),
+                    ..
+                } => {
+                    for limit_order in market_maker.limit_orders(deps)? {
+                        if let Some(current_order) = &limit_order.current_order {
+                            let balances = limit_order.balances(deps, env)?;
+
+                            for balance in balances.iter() {
+                                released.add(balance.clone())?;
+                            }
+
+                            withdrawn_orders.push(WithdrawnOrder {
+                                index,
+                                pair_address: limit_order.pair_address.clone(),
+                                side: limit_order.side.clone(),
+                                price: current_order.price,
+                                oracle_offset: current_order.oracle_offset,
+                                released: balances.into_vec(),
+                            });
+                        }
+                    }
+                }
+                Node::Action {
+                    action: Action::LimitOrderLadder(ladder),
+                    ..
+                } => {
+                    for (order, balances) in ladder.order_balances(deps, env)? {
+                        for balance in balances.iter() {
+                            released.add(balance.clone())?;
+                        }
+
+                        withdrawn_orders.push(WithdrawnOrder {
+                            index,
+                            pair_address: ladder.pair_address.clone(),
+                            side: order.side,
+                            price: order.price,
+                            oracle_offset: None,
+                            released: balances.into_vec(),
+                        });
+                    }
+                }
+                Node::Action {
+                    action: Action::Distribute(distribution),
+                    ..
+                } => {
+                    let proposed_destinations: &[Destination] = match proposed_node {
+                        Some(Node::Action {
+                            action: Action::Distribute(proposed_distribution),
+                            ..
+                        }) => proposed_distribution.destinations.as_slice(),
+                        _ => &[],
+                    };
+
+                    for destination in distribution.destinations.iter() {
+                        let distributions = match &destination.distributions {
+                            Some(distributions) if !distributions.is_empty() => distributions,
+                            _ => continue,
+                        };
+
+                        let carried = proposed_destinations.iter().any(|proposed| {
+                            proposed.recipient == destination.recipient
+                                && proposed.distributions.as_ref() == Some(distributions)
+                        });
+
+                        if !carried {
+                            lost_distributions.push(LostDistribution {
+                                index,
+                                recipient: destination.recipient.clone(),
+                                label: destination.label.clone(),
+                                distributions: distributions.clone(),
+                            });
+                        }
+                    }
+                }
+                Node::Action {
+                    action: Action::Swap(swap) | Action::TrailingStop(TrailingStop { swap, .. }),
+                    ..
+                } => {
+                    let proposed_routes: &[SwapRoute] = match proposed_node {
+                        Some(Node::Action {
+                            action:
+                                Action::Swap(proposed_swap)
+                                | Action::TrailingStop(TrailingStop {
+                                    swap: proposed_swap,
+                                    ..
+                                }),
+                            ..
+                        }) => proposed_swap.routes.as_slice(),
+                        _ => &[],
+                    };
+
+                    for route in swap.routes.iter() {
+                        if let SwapRoute::Thorchain(route) = route {
+                            if let Some(latest_swap) = &route.latest_swap {
+                                let carried = proposed_routes.iter().any(|proposed| {
+                                    matches!(
+                                        proposed,
+                                        SwapRoute::Thorchain(proposed)
+                                            if proposed.latest_swap.as_ref() == Some(latest_swap)
+                                    )
+                                });
+
+                                if !carried {
+                                    lost_swaps.push(LostStreamingSwap {
+                                        index,
+                                        latest_swap: latest_swap.clone(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+                Node::Condition {
+                    condition: Condition::Schedule(schedule),
+                    ..
+                } => {
+                    let reset = match proposed_node {
+                        Some(Node::Condition {
+                            condition: Condition::Schedule(proposed_schedule),
+                            ..
+                        }) => {
+                            proposed_schedule.cadence != schedule.cadence
+                                || proposed_schedule.executions != schedule.executions
+                        }
+                        _ => true,
+                    };
+
+                    recreated_schedules.push(RecreatedSchedule {
+                        index,
+                        cadence: schedule.cadence.clone(),
+                        executions: schedule.executions,
+                        reset,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(UpdatePreview {
+            withdrawn_orders,
+            released: released.into_vec(),
+            recreated_schedules,
+            lost_distributions,
+            lost_swaps,
+        })
+    }
 }
 
 #[cw_serde]
@@ -93,6 +520,209 @@ impl Node {
         }
     }
 
+    pub fn limit_order_stats(&self) -> Vec<LimitOrderNodeStats> {
+        let (index, pair_address, sides) = match self {
+            Node::Action {
+                action: Action::LimitOrder(limit_order),
+                index,
+                ..
+            } => {
+                return vec![LimitOrderNodeStats {
+                    index: *index,
+                    pair_address: limit_order.pair_address.clone(),
+                    side: limit_order.side.clone(),
+                    budget: limit_order.budget,
+                    stats: limit_order.stats.clone(),
+                    average_price: limit_order.stats.average_price(&limit_order.side),
+                }]
+            }
+            Node::Action {
+                action: Action::LimitOrderLadder(ladder),
+                index,
+                ..
+            } => (
+                index,
+                &ladder.pair_address,
+                [
+                    (Side::Quote, &ladder.bid_stats),
+                    (Side::Base, &ladder.ask_stats),
+                ],
+            ),
+            Node::Action {
+                action: Action::MarketMake(market_maker),
+                index,
+                ..
+            } => (
+                index,
+                &market_maker.pair_address,
+                [
+                    (Side::Quote, &market_maker.bid_stats),
+                    (Side::Base, &market_maker.ask_stats),
+                ],
+            ),
+            _ => return vec![],
+        };
+
+        // Ladders and market makers report the fills of each side
+        sides
+            .into_iter()
+            .map(|(side, stats)| LimitOrderNodeStats {
+                index: *index,
+                pair_address: pair_address.clone(),
+                average_price: stats.average_price(&side),
+                side,
+                budget: None,
+                stats: stats.clone(),
+            })
+            .collect()
+    }
+
+    pub fn recipients(&self) -> Vec<Recipient> {
+        match self {
+            Node::Action {
+                action: Action::Distribute(distribution),
+                ..
+            } => distribution
+                .destinations
+                .iter()
+                .map(|destination| destination.recipient.clone())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn denoms(&self) -> Vec<String> {
+        match self {
+            Node::Action { action, .. } => match action {
+                Action::Swap(swap) | Action::TrailingStop(TrailingStop { swap, .. }) => vec![
+                    swap.swap_amount.denom.clone(),
+                    swap.minimum_receive_amount.denom.clone(),
+                ],
+                // Pair denoms are only known once the action has been initialised
+                Action::LimitOrder(order) => {
+                    let mut denoms = vec![order.bid_denom.clone()];
+                    denoms.extend(order.pair_denoms.clone());
+
+                    if let Some(fallback) = &order.fallback {
+                        denoms.push(fallback.minimum_receive_amount.denom.clone());
+                    }
+
+                    denoms
+                }
+                Action::LimitOrderLadder(ladder) => ladder.pair_denoms.clone(),
+                Action::MarketMake(market_maker) => market_maker.pair_denoms.clone(),
+                Action::Distribute(distribution) => distribution.denoms.clone(),
+            },
+            Node::Condition { condition, .. } => match condition {
+                Condition::CanSwap(swap) => vec![
+                    swap.swap_amount.denom.clone(),
+                    swap.minimum_receive_amount.denom.clone(),
+                ],
+                Condition::BalanceAvailable { amount, .. } => vec![amount.denom.clone()],
+                Condition::OraclePrice { asset, .. } => vec![asset.clone()],
+                Condition::AssetValueRatio(ratio) => {
+                    vec![ratio.numerator.clone(), ratio.denominator.clone()]
+                }
+                _ => vec![],
+            },
+        }
+    }
+
+    pub fn pairs(&self) -> Vec<Addr> {
+        match self {
+            Node::Action { action, .. } => match action {
+                Action::Swap(swap) => swap.pairs(),
+                Action::LimitOrder(order) => {
+                    let mut pairs = vec![order.pair_address.clone()];
+
+                    if let Some(fallback) = &order.fallback {
+                        pairs.extend(fallback.pairs());
+                    }
+
+                    pairs
+                }
+                Action::LimitOrderLadder(ladder) => vec![ladder.pair_address.clone()],
+                Action::MarketMake(market_maker) => vec![market_maker.pair_address.clone()],
+                Action::TrailingStop(trailing_stop) => {
+                    let mut pairs = trailing_stop.swap.pairs();
+
+                    if let PriceSource::Fin { address } = &trailing_stop.oracle {
+                        pairs.push(address.clone());
+                    }
+
+                    pairs
+                }
+                Action::Distribute(_) => vec![],
+            },
+            Node::Condition { condition, .. } => match condition {
+                Condition::CanSwap(swap) => swap.pairs(),
+                Condition::FinLimitOrderFilled { pair_address, .. } => vec![pair_address.clone()],
+                Condition::AssetValueRatio(ratio) => match &ratio.oracle {
+                    PriceSource::Fin { address } => vec![address.clone()],
+                    PriceSource::Thorchain => vec![],
+                },
+                _ => vec![],
+            },
+        }
+    }
+
+    // Thorchain affiliates come from the manager fee schedule, overriding any provided
+    pub fn with_thorchain_affiliate(self, affiliate: Option<&ThorchainAffiliate>) -> Node {
+        match self {
+            Node::Action {
+                action: Action::Swap(swap),
+                index,
+                next,
+            } => Node::Action {
+                action: Action::Swap(swap.with_affiliates(affiliate)),
+                index,
+                next,
+            },
+            Node::Action {
+                action: Action::LimitOrder(limit_order),
+                index,
+                next,
+            } => Node::Action {
+                action: Action::LimitOrder(FinLimitOrder {
+                    fallback: limit_order
+                        .fallback
+                        .map(|fallback| fallback.with_affiliates(affiliate)),
+                    ..limit_order
+                }),
+                index,
+                next,
+            },
+            Node::Action {
+                action: Action::TrailingStop(trailing_stop),
+                index,
+                next,
+            } => Node::Action {
+                action: Action::TrailingStop(TrailingStop {
+                    swap: trailing_stop.swap.with_affiliates(affiliate),
+                    ..trailing_stop
+                }),
+                index,
+                next,
+            },
+            _ => self,
+        }
+    }
+
+    pub fn disabled_next_index(&self, branch: Option<&Branch>) -> Option<u16> {
+        match self {
+            Node::Action { next, .. } => *next,
+            Node::Condition {
+                on_success,
+                on_failure,
+                ..
+            } => match branch {
+                Some(Branch::OnSuccess) => *on_success,
+                Some(Branch::OnFailure) => *on_failure,
+                None => None,
+            },
+        }
+    }
+
     pub fn next_index(&self, deps: Deps, env: &Env) -> Option<u16> {
         match self {
             Node::Action { next, .. } => *next,
@@ -246,3 +876,343 @@ impl StatefulOperation<Node> for Node {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        to_json_binary, ContractResult, SystemResult, Timestamp, Uint128, WasmQuery,
+    };
+    use rujira_rs::fin::{ConfigResponse, Denoms, OrderResponse, Price, QueryMsg, Tick};
+
+    use crate::{
+        actions::{
+            distribution::Distribution,
+            limit_orders::{
+                fin_limit_order::{FinLimitOrder, LimitOrderStats, PriceStrategy, StaleOrder},
+                fin_limit_order_ladder::{FinLimitOrderLadder, LadderSize, Spacing},
+                fin_market_maker::FinMarketMaker,
+            },
+        },
+        conditions::schedule::Schedule,
+        core::Amount,
+    };
+
+    #[test]
+    fn update_preview_reports_withdrawn_orders_and_lost_state() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        deps.querier.update_wasm(move |query| {
+            SystemResult::Ok(ContractResult::Ok(match query {
+                WasmQuery::Smart { msg, .. } => match from_json(msg).unwrap() {
+                    QueryMsg::Config {} => to_json_binary(&ConfigResponse {
+                        denoms: Denoms::new("rune", "x/ruji"),
+                        oracles: None,
+                        market_makers: vec![],
+                        tick: Tick::new(6),
+                        range_delta: Decimal::zero(),
+                        range_min: Decimal::one(),
+                        fee_taker: Decimal::percent(1),
+                        fee_maker: Decimal::percent(1),
+                        fee_range: Decimal::zero(),
+                        fee_address: "feetaker".to_string(),
+                    })
+                    .unwrap(),
+                    QueryMsg::Order(_) => to_json_binary(&OrderResponse {
+                        remaining: Uint128::new(100),
+                        filled: Uint128::new(50),
+                        owner: env.contract.address.to_string(),
+                        side: Side::Base,
+                        price: Price::Fixed(Decimal::one()),
+                        rate: Some(Decimal::one()),
+                        updated_at: Timestamp::from_seconds(env.block.time.seconds()),
+                        offer: Uint128::new(150),
+                    })
+                    .unwrap(),
+                    _ => panic!("Unexpected query: {msg:?}"),
+                },
+                _ => panic!("Unexpected query: {query:?}"),
+            }))
+        });
+
+        let env = mock_env();
+
+        let limit_order = FinLimitOrder {
+            pair_address: Addr::unchecked("pair"),
+            pair_denoms: vec![],
+            bid_denom: "rune".to_string(),
+            bid_amount: Amount::Fraction(Decimal::one()),
+            side: Side::Base,
+            strategy: PriceStrategy::Fixed(Decimal::one()),
+            min_fill_ratio: None,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats: LimitOrderStats::default(),
+            current_order: None,
+        };
+
+        let distribution = Distribution {
+            denoms: vec!["x/ruji".to_string()],
+            destinations: vec![Destination {
+                shares: Uint128::new(10_000),
+                recipient: Recipient::Bank {
+                    address: Addr::unchecked("recipient"),
+                },
+                label: Some("recipient".to_string()),
+                distributions: None,
+            }],
+        };
+
+        let schedule = Schedule {
+            scheduler_address: Addr::unchecked("scheduler"),
+            manager_address: Addr::unchecked("manager"),
+            cadence: Cadence::Blocks {
+                interval: 10,
+                previous: Some(env.block.height),
+            },
+            next: None,
+            execution_rebate: vec![],
+            executors: vec![],
+            jitter: None,
+            executions: Some(3),
+            max_executions: None,
+        };
+
+        let current = vec![
+            Node::Action {
+                action: Action::LimitOrder(FinLimitOrder {
+                    current_order: Some(StaleOrder {
+                        price: Decimal::one(),
+                        oracle_offset: None,
+                        expires: None,
+                    }),
+                    ..limit_order.clone()
+                }),
+                index: 0,
+                next: Some(1),
+            },
+            Node::Action {
+                action: Action::Distribute(Distribution {
+                    destinations: vec![Destination {
+                        distributions: Some(vec![Coin::new(50u128, "x/ruji")]),
+                        ..distribution.destinations[0].clone()
+                    }],
+                    ..distribution.clone()
+                }),
+                index: 1,
+                next: Some(2),
+            },
+            Node::Condition {
+                condition: Condition::Schedule(schedule.clone()),
+                index: 2,
+                on_success: Some(0),
+                on_failure: None,
+            },
+        ];
+
+        let proposed = vec![
+            Node::Action {
+                action: Action::LimitOrder(limit_order),
+                index: 0,
+                next: Some(1),
+            },
+            Node::Action {
+                action: Action::Distribute(distribution.clone()),
+                index: 1,
+                next: Some(2),
+            },
+            Node::Condition {
+                condition: Condition::Schedule(schedule.clone()),
+                index: 2,
+                on_success: Some(0),
+                on_failure: None,
+            },
+        ];
+
+        let preview = UpdatePreview::new(deps.as_ref(), &env, &current, &proposed).unwrap();
+
+        let ask_denom = Denoms::new("rune", "x/ruji").ask(&Side::Base).to_string();
+        let expected_released = Coins::try_from(vec![
+            Coin::new(100u128, "rune"),
+            Coin::new(50u128, ask_denom),
+        ])
+        .unwrap()
+        .into_vec();
+
+        assert_eq!(
+            preview.withdrawn_orders,
+            vec![WithdrawnOrder {
+                index: 0,
+                pair_address: Addr::unchecked("pair"),
+                side: Side::Base,
+                price: Decimal::one(),
+                oracle_offset: None,
+                released: expected_released.clone(),
+            }]
+        );
+        assert_eq!(preview.released, expected_released);
+
+        assert_eq!(
+            preview.lost_distributions,
+            vec![LostDistribution {
+                index: 1,
+                recipient: Recipient::Bank {
+                    address: Addr::unchecked("recipient"),
+                },
+                label: Some("recipient".to_string()),
+                distributions: vec![Coin::new(50u128, "x/ruji")],
+            }]
+        );
+
+        assert_eq!(
+            preview.recreated_schedules,
+            vec![RecreatedSchedule {
+                index: 2,
+                cadence: schedule.cadence.clone(),
+                executions: Some(3),
+                reset: false,
+            }]
+        );
+
+        assert!(preview.lost_swaps.is_empty());
+
+        let preview = UpdatePreview::new(deps.as_ref(), &env, &current, &current[..1]).unwrap();
+
+        assert_eq!(preview.lost_distributions.len(), 1);
+        assert!(preview.recreated_schedules[0].reset);
+    }
+
+    #[test]
+    fn performance_reports_pnl_and_time_weighted_return() {
+        let mut totals = PerformanceTotals::default();
+
+        totals
+            .record(&LedgerEvent::Deposit, Uint128::new(1_000), Uint128::zero())
+            .unwrap();
+        totals
+            .record(
+                &LedgerEvent::Withdrawal,
+                Uint128::new(500),
+                Uint128::new(1_500),
+            )
+            .unwrap();
+        totals
+            .record(
+                &LedgerEvent::Deposit,
+                Uint128::new(1_000),
+                Uint128::new(1_100),
+            )
+            .unwrap();
+
+        let performance =
+            Performance::new("usdc".to_string(), &totals, Uint128::new(2_100)).unwrap();
+
+        assert_eq!(
+            performance,
+            Performance {
+                quote: "usdc".to_string(),
+                nav: Uint128::new(2_100),
+                deposited: Uint128::new(2_000),
+                withdrawn: Uint128::new(500),
+                distributed: Uint128::zero(),
+                fees: Uint128::zero(),
+                net_deposits: Int128::new(1_500),
+                realised_pnl: Int128::new(167),
+                unrealised_pnl: Int128::new(433),
+                time_weighted_return: SignedDecimal::percent(65),
+            }
+        );
+
+        let performance =
+            Performance::new("usdc".to_string(), &totals, Uint128::new(1_050)).unwrap();
+
+        assert_eq!(performance.unrealised_pnl, Int128::new(-617));
+        assert_eq!(
+            performance.time_weighted_return,
+            SignedDecimal::from_str("-0.175").unwrap()
+        );
+    }
+
+    #[test]
+    fn performance_counts_distributions_as_flows_and_fees_as_costs() {
+        let mut totals = PerformanceTotals::default();
+
+        totals
+            .record(&LedgerEvent::Deposit, Uint128::new(1_000), Uint128::zero())
+            .unwrap();
+        totals
+            .record(
+                &LedgerEvent::Distribution,
+                Uint128::new(200),
+                Uint128::new(1_000),
+            )
+            .unwrap();
+        totals
+            .record(&LedgerEvent::Fee, Uint128::new(80), Uint128::new(800))
+            .unwrap();
+
+        let performance = Performance::new("usdc".to_string(), &totals, Uint128::new(720)).unwrap();
+
+        assert_eq!(performance.distributed, Uint128::new(200));
+        assert_eq!(performance.fees, Uint128::new(80));
+        assert_eq!(performance.net_deposits, Int128::new(800));
+        assert_eq!(performance.realised_pnl, Int128::zero());
+        assert_eq!(performance.unrealised_pnl, Int128::new(-80));
+        assert_eq!(
+            performance.time_weighted_return,
+            SignedDecimal::percent(-10)
+        );
+    }
+
+    #[test]
+    fn denoms_reads_pair_denoms_resolved_at_init() {
+        let pair_denoms = vec!["rune".to_string(), "x/ruji".to_string()];
+
+        let actions = [
+            Action::LimitOrderLadder(FinLimitOrderLadder {
+                pair_address: Addr::unchecked("pair"),
+                pair_denoms: pair_denoms.clone(),
+                lower_price: Decimal::percent(80),
+                upper_price: Decimal::percent(120),
+                levels: 5,
+                spacing: Spacing::Arithmetic,
+                size: LadderSize::Even,
+                orders: vec![],
+                bid_stats: LimitOrderStats::default(),
+                ask_stats: LimitOrderStats::default(),
+            }),
+            Action::MarketMake(FinMarketMaker {
+                pair_address: Addr::unchecked("pair"),
+                pair_denoms: pair_denoms.clone(),
+                spread: Decimal::percent(1),
+                size: Amount::Fraction(Decimal::percent(10)),
+                target_base_ratio: Decimal::percent(50),
+                skew: Decimal::one(),
+                min_fill_ratio: None,
+                bid: None,
+                ask: None,
+                bid_stats: LimitOrderStats::default(),
+                ask_stats: LimitOrderStats::default(),
+            }),
+        ];
+
+        for action in actions {
+            assert_eq!(
+                Node::Action {
+                    action,
+                    index: 0,
+                    next: None,
+                }
+                .denoms(),
+                pair_denoms
+            );
+        }
+    }
+}
@@ -0,0 +1,528 @@
+// Frozen copies of the types stored by contracts deployed before version
+// tracking (state version 0). They must never change, as migrations read the
+// stored state with them before converting it to the current types.
+
+use std::time::Duration;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    from_json, to_json_vec, Addr, Binary, Coin, Decimal, StdResult, Timestamp, Uint128, Uint64,
+};
+use rujira_rs::fin::Side;
+
+use crate::{
+    actions::{
+        action, distribution,
+        limit_orders::fin_limit_order,
+        swaps::{fin, swap, thor},
+    },
+    cadence, conditions, core, manager, scheduler, strategy,
+};
+
+#[cw_serde]
+pub enum Node {
+    Action {
+        action: Action,
+        index: u16,
+        next: Option<u16>,
+    },
+    Condition {
+        condition: Condition,
+        index: u16,
+        on_success: Option<u16>,
+        on_failure: Option<u16>,
+    },
+}
+
+#[cw_serde]
+pub enum Action {
+    Swap(Swap),
+    LimitOrder(FinLimitOrder),
+    Distribute(Distribution),
+}
+
+#[cw_serde]
+pub enum Recipient {
+    Bank { address: Addr },
+    Contract { address: Addr, msg: Binary },
+    Deposit { memo: String },
+}
+
+#[cw_serde]
+pub struct Destination {
+    pub shares: Uint128,
+    pub recipient: Recipient,
+    pub label: Option<String>,
+    pub distributions: Option<Vec<Coin>>,
+}
+
+#[cw_serde]
+pub struct Distribution {
+    pub denoms: Vec<String>,
+    pub destinations: Vec<Destination>,
+}
+
+#[cw_serde]
+pub enum Amount {
+    Fixed(Uint128),
+    Fraction(Decimal),
+}
+
+#[cw_serde]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+#[cw_serde]
+pub enum Offset {
+    Exact(Decimal),
+    Percent(u64),
+}
+
+#[cw_serde]
+pub enum PriceStrategy {
+    Fixed(Decimal),
+    Offset {
+        side: Side,
+        direction: Direction,
+        offset: Offset,
+        tolerance: Option<Offset>,
+    },
+}
+
+#[cw_serde]
+pub struct StaleOrder {
+    pub price: Decimal,
+}
+
+#[cw_serde]
+pub struct FinLimitOrder {
+    pub pair_address: Addr,
+    pub bid_denom: String,
+    pub bid_amount: Amount,
+    pub side: Side,
+    pub strategy: PriceStrategy,
+    pub min_fill_ratio: Option<Decimal>,
+    pub current_order: Option<StaleOrder>,
+}
+
+#[cw_serde]
+pub enum SwapAmountAdjustment {
+    Fixed,
+    LinearScalar {
+        base_receive_amount: Coin,
+        minimum_swap_amount: Option<Coin>,
+        scalar: Decimal,
+    },
+}
+
+#[cw_serde]
+pub struct FinRoute {
+    pub pair_address: Addr,
+}
+
+#[cw_serde]
+pub struct StreamingSwap {
+    pub swap_amount: Coin,
+    pub expected_receive_amount: Coin,
+    pub starting_block: u64,
+    pub streaming_swap_blocks: u64,
+    pub memo: String,
+}
+
+#[cw_serde]
+pub struct ThorchainRoute {
+    pub streaming_interval: Option<u64>,
+    pub max_streaming_quantity: Option<u64>,
+    pub affiliate_code: Option<String>,
+    pub affiliate_bps: Option<u64>,
+    pub latest_swap: Option<StreamingSwap>,
+}
+
+#[cw_serde]
+pub enum SwapRoute {
+    Fin(FinRoute),
+    Thorchain(ThorchainRoute),
+}
+
+#[cw_serde]
+pub struct Swap {
+    pub swap_amount: Coin,
+    pub minimum_receive_amount: Coin,
+    pub maximum_slippage_bps: u64,
+    pub adjustment: SwapAmountAdjustment,
+    pub routes: Vec<SwapRoute>,
+}
+
+#[cw_serde]
+pub enum Cadence {
+    Blocks {
+        interval: u64,
+        previous: Option<u64>,
+    },
+    Time {
+        duration: Duration,
+        previous: Option<Timestamp>,
+    },
+    Cron {
+        expr: String,
+        previous: Option<Timestamp>,
+    },
+}
+
+#[cw_serde]
+pub struct Schedule {
+    pub scheduler_address: Addr,
+    pub manager_address: Addr,
+    pub cadence: Cadence,
+    pub next: Option<Cadence>,
+    pub execution_rebate: Vec<Coin>,
+    pub executors: Vec<Addr>,
+    pub jitter: Option<Duration>,
+    pub executions: Option<u32>,
+    pub max_executions: Option<u32>,
+}
+
+#[cw_serde]
+pub enum StrategyStatus {
+    Active,
+    Paused,
+    Archived,
+}
+
+#[cw_serde]
+pub enum PriceSource {
+    Fin { address: Addr },
+    Thorchain,
+}
+
+#[cw_serde]
+pub struct AssetValueRatio {
+    pub numerator: String,
+    pub denominator: String,
+    pub ratio: Decimal,
+    pub tolerance: Decimal,
+    pub oracle: PriceSource,
+}
+
+#[cw_serde]
+pub enum Condition {
+    TimestampElapsed(Timestamp),
+    BlocksCompleted(u64),
+    Schedule(Schedule),
+    CanSwap(Swap),
+    FinLimitOrderFilled {
+        owner: Option<Addr>,
+        pair_address: Addr,
+        side: Side,
+        price: Decimal,
+    },
+    BalanceAvailable {
+        address: Option<Addr>,
+        amount: Coin,
+    },
+    StrategyStatus {
+        manager_contract: Addr,
+        contract_address: Addr,
+        status: StrategyStatus,
+    },
+    OraclePrice {
+        asset: String,
+        direction: Direction,
+        price: Decimal,
+    },
+    AssetValueRatio(AssetValueRatio),
+}
+
+#[cw_serde]
+pub struct Trigger {
+    pub id: Uint64,
+    pub owner: Addr,
+    pub condition: Condition,
+    pub msg: Binary,
+    pub contract_address: Addr,
+    pub executors: Vec<Addr>,
+    pub execution_rebate: Vec<Coin>,
+    pub jitter: Option<Duration>,
+}
+
+impl Node {
+    pub fn migrate(self) -> StdResult<strategy::Node> {
+        Ok(match self {
+            Node::Action {
+                action,
+                index,
+                next,
+            } => strategy::Node::Action {
+                action: action.migrate()?,
+                index,
+                next,
+            },
+            Node::Condition {
+                condition,
+                index,
+                on_success,
+                on_failure,
+            } => strategy::Node::Condition {
+                condition: condition.migrate()?,
+                index,
+                on_success,
+                on_failure,
+            },
+        })
+    }
+}
+
+impl Action {
+    pub fn migrate(self) -> StdResult<action::Action> {
+        Ok(match self {
+            Action::Swap(swap) => action::Action::Swap(swap.migrate()?),
+            Action::LimitOrder(limit_order) => action::Action::LimitOrder(limit_order.migrate()),
+            Action::Distribute(distribution) => action::Action::Distribute(distribution.migrate()),
+        })
+    }
+}
+
+impl Recipient {
+    pub fn migrate(self) -> distribution::Recipient {
+        match self {
+            Recipient::Bank { address } => distribution::Recipient::Bank { address },
+            Recipient::Contract { address, msg } => {
+                distribution::Recipient::Contract { address, msg }
+            }
+            Recipient::Deposit { memo } => distribution::Recipient::Deposit { memo },
+        }
+    }
+}
+
+impl Distribution {
+    pub fn migrate(self) -> distribution::Distribution {
+        distribution::Distribution {
+            denoms: self.denoms,
+            destinations: self
+                .destinations
+                .into_iter()
+                .map(|destination| distribution::Destination {
+                    shares: destination.shares,
+                    recipient: destination.recipient.migrate(),
+                    label: destination.label,
+                    distributions: destination.distributions,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Direction {
+    pub fn migrate(self) -> fin_limit_order::Direction {
+        match self {
+            Direction::Above => fin_limit_order::Direction::Above,
+            Direction::Below => fin_limit_order::Direction::Below,
+        }
+    }
+}
+
+impl Offset {
+    pub fn migrate(self) -> fin_limit_order::Offset {
+        match self {
+            Offset::Exact(value) => fin_limit_order::Offset::Exact(value),
+            Offset::Percent(percent) => fin_limit_order::Offset::Percent(percent),
+        }
+    }
+}
+
+impl FinLimitOrder {
+    // Pair denoms are left empty, so the manager only indexes the order under
+    // them once the strategy is reindexed
+    pub fn migrate(self) -> fin_limit_order::FinLimitOrder {
+        fin_limit_order::FinLimitOrder {
+            pair_address: self.pair_address,
+            pair_denoms: vec![],
+            bid_denom: self.bid_denom,
+            bid_amount: match self.bid_amount {
+                Amount::Fixed(amount) => core::Amount::Fixed(amount),
+                Amount::Fraction(fraction) => core::Amount::Fraction(fraction),
+            },
+            side: self.side,
+            strategy: match self.strategy {
+                PriceStrategy::Fixed(price) => fin_limit_order::PriceStrategy::Fixed(price),
+                PriceStrategy::Offset {
+                    side,
+                    direction,
+                    offset,
+                    tolerance,
+                } => fin_limit_order::PriceStrategy::Offset {
+                    side,
+                    direction: direction.migrate(),
+                    offset: offset.migrate(),
+                    tolerance: tolerance.map(Offset::migrate),
+                },
+            },
+            min_fill_ratio: self.min_fill_ratio,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats: fin_limit_order::LimitOrderStats::default(),
+            current_order: self
+                .current_order
+                .map(|current_order| fin_limit_order::StaleOrder {
+                    price: current_order.price,
+                    oracle_offset: None,
+                    expires: None,
+                }),
+        }
+    }
+}
+
+impl SwapRoute {
+    pub fn migrate(self) -> StdResult<swap::SwapRoute> {
+        Ok(match self {
+            SwapRoute::Fin(route) => swap::SwapRoute::Fin(fin::FinRoute {
+                pair_address: route.pair_address,
+            }),
+            SwapRoute::Thorchain(route) => swap::SwapRoute::Thorchain(thor::ThorchainRoute {
+                streaming_interval: route.streaming_interval,
+                max_streaming_quantity: route.max_streaming_quantity,
+                affiliate_code: route.affiliate_code,
+                affiliate_bps: route.affiliate_bps,
+                // The cached swap is opaque outside its route, and kept its layout
+                latest_swap: route
+                    .latest_swap
+                    .map(|latest_swap| from_json(to_json_vec(&latest_swap)?))
+                    .transpose()?,
+            }),
+        })
+    }
+}
+
+impl Swap {
+    pub fn migrate(self) -> StdResult<swap::Swap> {
+        Ok(swap::Swap {
+            swap_amount: self.swap_amount,
+            minimum_receive_amount: self.minimum_receive_amount,
+            maximum_slippage_bps: self.maximum_slippage_bps,
+            adjustment: match self.adjustment {
+                SwapAmountAdjustment::Fixed => swap::SwapAmountAdjustment::Fixed,
+                SwapAmountAdjustment::LinearScalar {
+                    base_receive_amount,
+                    minimum_swap_amount,
+                    scalar,
+                } => swap::SwapAmountAdjustment::LinearScalar {
+                    base_receive_amount,
+                    minimum_swap_amount,
+                    scalar,
+                },
+            },
+            routes: self
+                .routes
+                .into_iter()
+                .map(SwapRoute::migrate)
+                .collect::<StdResult<_>>()?,
+            routing: swap::SwapRouting::Best,
+        })
+    }
+}
+
+impl Cadence {
+    pub fn migrate(self) -> cadence::Cadence {
+        match self {
+            Cadence::Blocks { interval, previous } => {
+                cadence::Cadence::Blocks { interval, previous }
+            }
+            Cadence::Time { duration, previous } => cadence::Cadence::Time { duration, previous },
+            Cadence::Cron { expr, previous } => cadence::Cadence::Cron { expr, previous },
+        }
+    }
+}
+
+impl Condition {
+    pub fn migrate(self) -> StdResult<conditions::condition::Condition> {
+        use conditions::condition::Condition as Current;
+
+        Ok(match self {
+            Condition::TimestampElapsed(timestamp) => Current::TimestampElapsed(timestamp),
+            Condition::BlocksCompleted(height) => Current::BlocksCompleted(height),
+            Condition::Schedule(schedule) => Current::Schedule(conditions::schedule::Schedule {
+                scheduler_address: schedule.scheduler_address,
+                manager_address: schedule.manager_address,
+                cadence: schedule.cadence.migrate(),
+                next: schedule.next.map(Cadence::migrate),
+                execution_rebate: schedule.execution_rebate,
+                executors: schedule.executors,
+                jitter: schedule.jitter,
+                executions: schedule.executions,
+                max_executions: schedule.max_executions,
+            }),
+            Condition::CanSwap(swap) => Current::CanSwap(swap.migrate()?),
+            Condition::FinLimitOrderFilled {
+                owner,
+                pair_address,
+                side,
+                price,
+            } => Current::FinLimitOrderFilled {
+                owner,
+                pair_address,
+                side,
+                price,
+                oracle_offset: None,
+            },
+            Condition::BalanceAvailable { address, amount } => {
+                Current::BalanceAvailable { address, amount }
+            }
+            Condition::StrategyStatus {
+                manager_contract,
+                contract_address,
+                status,
+            } => Current::StrategyStatus {
+                manager_contract,
+                contract_address,
+                status: match status {
+                    StrategyStatus::Active => manager::StrategyStatus::Active,
+                    StrategyStatus::Paused => manager::StrategyStatus::Paused,
+                    StrategyStatus::Archived => manager::StrategyStatus::Archived,
+                },
+            },
+            Condition::OraclePrice {
+                asset,
+                direction,
+                price,
+            } => Current::OraclePrice {
+                asset,
+                direction: direction.migrate(),
+                price,
+            },
+            Condition::AssetValueRatio(ratio) => {
+                Current::AssetValueRatio(conditions::asset_value_ratio::AssetValueRatio {
+                    numerator: ratio.numerator,
+                    denominator: ratio.denominator,
+                    ratio: ratio.ratio,
+                    tolerance: ratio.tolerance,
+                    oracle: match ratio.oracle {
+                        PriceSource::Fin { address } => {
+                            conditions::asset_value_ratio::PriceSource::Fin { address }
+                        }
+                        PriceSource::Thorchain => {
+                            conditions::asset_value_ratio::PriceSource::Thorchain
+                        }
+                    },
+                })
+            }
+        })
+    }
+}
+
+impl Trigger {
+    pub fn migrate(self) -> StdResult<scheduler::Trigger> {
+        Ok(scheduler::Trigger {
+            id: self.id,
+            owner: self.owner,
+            condition: self.condition.migrate()?,
+            msg: self.msg,
+            contract_address: self.contract_address,
+            executors: self.executors,
+            execution_rebate: self.execution_rebate,
+            jitter: self.jitter,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+
+#[cw_serde]
+pub struct ContractVersion {
+    pub contract: String,
+    pub version: u16,
+}
+
+pub const CONTRACT_VERSION: Item<ContractVersion> = Item::new("contract_version");
+
+// Each migration upgrades stored state from the version at its index to the
+// next one, so the current version of a contract is its number of migrations
+pub type Migration = fn(&mut dyn Storage) -> StdResult<()>;
+
+pub fn set_contract_version(
+    storage: &mut dyn Storage,
+    contract: &str,
+    migrations: &[Migration],
+) -> StdResult<()> {
+    CONTRACT_VERSION.save(
+        storage,
+        &ContractVersion {
+            contract: contract.to_string(),
+            version: migrations.len() as u16,
+        },
+    )
+}
+
+pub fn migrate_state(
+    storage: &mut dyn Storage,
+    contract: &str,
+    migrations: &[Migration],
+) -> StdResult<(u16, u16)> {
+    // Contracts deployed before version tracking are at version 0
+    let from = match CONTRACT_VERSION.may_load(storage)? {
+        Some(stored) if stored.contract != contract => {
+            return Err(StdError::generic_err(format!(
+                "Cannot migrate {} state to {contract}",
+                stored.contract
+            )));
+        }
+        Some(stored) => stored.version,
+        None => 0,
+    };
+
+    let to = migrations.len() as u16;
+
+    if from > to {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate {contract} from version {from} down to {to}"
+        )));
+    }
+
+    for migration in &migrations[from as usize..] {
+        migration(storage)?;
+    }
+
+    set_contract_version(storage, contract, migrations)?;
+
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{testing::MockStorage, StdResult, Storage};
+    use cw_storage_plus::Item;
+
+    use crate::version::{
+        migrate_state, set_contract_version, ContractVersion, Migration, CONTRACT_VERSION,
+    };
+
+    const STEPS: Item<Vec<u16>> = Item::new("steps");
+
+    fn step(storage: &mut dyn Storage, version: u16) -> StdResult<()> {
+        let mut steps = STEPS.may_load(storage)?.unwrap_or_default();
+        steps.push(version);
+        STEPS.save(storage, &steps)
+    }
+
+    const MIGRATIONS: &[Migration] = &[|s| step(s, 1), |s| step(s, 2), |s| step(s, 3)];
+
+    #[test]
+    fn runs_migrations_from_stored_version() {
+        let mut storage = MockStorage::default();
+
+        assert_eq!(
+            migrate_state(&mut storage, "calc-strategy", MIGRATIONS).unwrap(),
+            (0, 3)
+        );
+        assert_eq!(STEPS.load(&storage).unwrap(), vec![1, 2, 3]);
+
+        STEPS.remove(&mut storage);
+        set_contract_version(&mut storage, "calc-strategy", &MIGRATIONS[..1]).unwrap();
+
+        assert_eq!(
+            migrate_state(&mut storage, "calc-strategy", MIGRATIONS).unwrap(),
+            (1, 3)
+        );
+        assert_eq!(STEPS.load(&storage).unwrap(), vec![2, 3]);
+        assert_eq!(
+            CONTRACT_VERSION.load(&storage).unwrap(),
+            ContractVersion {
+                contract: "calc-strategy".to_string(),
+                version: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_downgrades_and_other_contracts() {
+        let mut storage = MockStorage::default();
+
+        set_contract_version(&mut storage, "calc-strategy", MIGRATIONS).unwrap();
+
+        assert!(
+            migrate_state(&mut storage, "calc-strategy", &MIGRATIONS[..2])
+                .unwrap_err()
+                .to_string()
+                .contains("from version 3 down to 2")
+        );
+
+        assert!(migrate_state(&mut storage, "calc-scheduler", MIGRATIONS)
+            .unwrap_err()
+            .to_string()
+            .contains("Cannot migrate calc-strategy state to calc-scheduler"));
+    }
+}
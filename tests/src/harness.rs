@@ -1,7 +1,9 @@
 use std::vec;
 
 use calc_rs::{
-    manager::{Affiliate, ManagerConfig, ManagerExecuteMsg, ManagerQueryMsg, Strategy},
+    manager::{
+        Affiliate, FeeSchedule, ManagerConfig, ManagerExecuteMsg, ManagerQueryMsg, Strategy,
+    },
     scheduler::{
         ConditionFilter, SchedulerExecuteMsg, SchedulerInstantiateMsg, SchedulerQueryMsg, Trigger,
     },
@@ -41,11 +43,14 @@ impl CalcTestApp {
             rujira_fin::contract::query,
         )));
 
-        let manager_code_id = app.store_code(Box::new(ContractWrapper::new(
-            calc_manager::contract::execute,
-            calc_manager::contract::instantiate,
-            calc_manager::contract::query,
-        )));
+        let manager_code_id = app.store_code(Box::new(
+            ContractWrapper::new(
+                calc_manager::contract::execute,
+                calc_manager::contract::instantiate,
+                calc_manager::contract::query,
+            )
+            .with_reply(calc_manager::contract::reply),
+        ));
 
         let strategy_code_id = app.store_code(Box::new(
             ContractWrapper::new(execute, instantiate, query).with_reply(reply),
@@ -57,7 +62,7 @@ impl CalcTestApp {
                 calc_scheduler::contract::instantiate,
                 calc_scheduler::contract::query,
             )
-            .with_reply(reply),
+            .with_reply(calc_scheduler::contract::reply),
         ));
 
         let admin = app.api().addr_make("admin");
@@ -95,8 +100,10 @@ impl CalcTestApp {
                 manager_code_id,
                 admin.clone(),
                 &ManagerConfig {
+                    admin: Some(admin.clone()),
                     strategy_code_id,
                     fee_collector: fee_collector_addr.clone(),
+                    fee_schedule: FeeSchedule::default(),
                 },
                 &[],
                 "calc-manager",
@@ -281,6 +288,11 @@ impl CalcTestApp {
             label: label.to_string(),
             affiliates,
             nodes,
+            checkpoint_interval: None,
+            restrict_outflows: false,
+            migratable: false,
+            performance_fee: None,
+            valuation: None,
         };
 
         let response = self.app.execute_contract(
@@ -413,6 +425,17 @@ impl CalcTestApp {
         });
     }
 
+    pub fn set_manager_paused(&mut self, paused: bool) -> AnyResult<AppResponse> {
+        let admin = self.app.api().addr_make("admin");
+
+        self.app.execute_contract(
+            admin,
+            self.manager_addr.clone(),
+            &ManagerExecuteMsg::SetPaused { paused },
+            &[],
+        )
+    }
+
     pub fn update_strategy_status(
         &mut self,
         sender: &Addr,
@@ -15,7 +15,7 @@ mod integration_tests {
         constants::BASE_FEE_BPS,
         core::Amount,
         manager::{Affiliate, StrategyStatus},
-        scheduler::{CreateTriggerMsg, SchedulerExecuteMsg},
+        scheduler::{ConditionFilter, CreateTriggerMsg, SchedulerExecuteMsg},
         strategy::Node,
     };
 
@@ -24,14 +24,16 @@ mod integration_tests {
     use calc_rs::{
         actions::{
             action::Action,
-            swaps::swap::{Swap, SwapAmountAdjustment, SwapRoute},
+            swaps::swap::{Swap, SwapAmountAdjustment, SwapRoute, SwapRouting},
         },
         strategy::StrategyConfig,
     };
     use cosmwasm_std::{to_json_binary, Addr, Binary, Coin, Coins, Decimal, Timestamp, Uint128};
     use rujira_rs::fin::{Price, Side};
 
-    use calc_rs::actions::limit_orders::fin_limit_order::{FinLimitOrder, PriceStrategy};
+    use calc_rs::actions::limit_orders::fin_limit_order::{
+        FinLimitOrder, LimitOrderStats, PriceStrategy,
+    };
 
     use crate::harness::CalcTestApp;
     use crate::strategy_builder::StrategyBuilder;
@@ -48,6 +50,7 @@ mod integration_tests {
             minimum_receive_amount: Coin::new(1u128, fin_pair.denoms.quote()),
             maximum_slippage_bps: 101,
             adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         }
     }
 
@@ -65,6 +68,7 @@ mod integration_tests {
             minimum_receive_amount: Coin::new(1u128, fin_pair.denoms.quote()),
             maximum_slippage_bps: 101,
             adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         }
     }
 
@@ -78,6 +82,7 @@ mod integration_tests {
             minimum_receive_amount: Coin::new(1u128, fin_pair.denoms.quote()),
             maximum_slippage_bps: 101,
             adjustment: SwapAmountAdjustment::Fixed,
+            routing: SwapRouting::Best,
         }
     }
 
@@ -85,11 +90,16 @@ mod integration_tests {
         let fin_pair = harness.query_fin_config(&harness.fin_addr);
         FinLimitOrder {
             pair_address: harness.fin_addr.clone(),
+            pair_denoms: vec![],
             bid_denom: fin_pair.denoms.base().to_string(),
             bid_amount: Amount::Fraction(Decimal::percent(100)),
             side: Side::Base,
             strategy: PriceStrategy::Fixed(Decimal::percent(100)),
             min_fill_ratio: None,
+            time_in_force: None,
+            fallback: None,
+            budget: None,
+            stats: LimitOrderStats::default(),
             current_order: None,
         }
     }
@@ -204,6 +214,7 @@ mod integration_tests {
                     routes: vec![SwapRoute::Fin(FinRoute {
                         pair_address: fin_pair.clone(),
                     })],
+                    routing: SwapRouting::Best,
                 }),
                 index: 4,
                 on_success: Some(5),
@@ -215,6 +226,7 @@ mod integration_tests {
                     pair_address: fin_pair,
                     side: Side::Base,
                     price: Decimal::percent(100),
+                    oracle_offset: None,
                 },
                 index: 5,
                 on_success: Some(6),
@@ -1030,6 +1042,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let mut strategy = StrategyBuilder::new(&mut harness)
@@ -1075,6 +1088,7 @@ mod integration_tests {
                     latest_swap: None,
                 }),
             ],
+            routing: SwapRouting::Best,
         };
 
         let mut strategy = StrategyBuilder::new(&mut harness)
@@ -1324,6 +1338,8 @@ mod integration_tests {
         let order_action = FinLimitOrder {
             current_order: Some(StaleOrder {
                 price: Decimal::one(),
+                oracle_offset: None,
+                expires: None,
             }),
             ..default_limit_order_action(&harness)
         };
@@ -2620,6 +2636,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2661,6 +2678,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2702,6 +2720,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let second_swap_action = Swap {
@@ -2712,6 +2731,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2761,6 +2781,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2810,6 +2831,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2915,6 +2937,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -2985,6 +3008,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -3061,6 +3085,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![Coin::new(
@@ -3187,6 +3212,7 @@ mod integration_tests {
             routes: vec![SwapRoute::Fin(FinRoute {
                 pair_address: harness.fin_addr.clone(),
             })],
+            routing: SwapRouting::Best,
         };
 
         let funds = vec![
@@ -3732,6 +3758,75 @@ mod integration_tests {
             )]);
     }
 
+    #[test]
+    fn test_crank_time_schedule_keeps_triggers_while_manager_paused() {
+        let mut harness = CalcTestApp::setup();
+        let swap_action = default_swap_action(&harness);
+
+        let schedule = Schedule {
+            scheduler_address: harness.scheduler_addr.clone(),
+            executors: vec![],
+            jitter: None,
+            next: None,
+            manager_address: harness.manager_addr.clone(),
+            cadence: Cadence::Time {
+                duration: Duration::from_secs(60),
+                previous: Some(harness.app.block_info().time),
+            },
+            execution_rebate: vec![],
+            executions: None,
+            max_executions: None,
+        };
+
+        let funds = vec![Coin::new(
+            swap_action.swap_amount.amount * Uint128::new(20),
+            swap_action.swap_amount.denom.clone(),
+        )];
+
+        let mut strategy = StrategyBuilder::new(&mut harness)
+            .with_nodes(vec![
+                Node::Condition {
+                    condition: Condition::Schedule(schedule),
+                    index: 0,
+                    on_success: Some(1),
+                    on_failure: None,
+                },
+                Node::Action {
+                    action: Action::Swap(swap_action.clone()),
+                    index: 1,
+                    next: None,
+                },
+            ])
+            .instantiate(&funds);
+
+        strategy
+            .pause_manager()
+            .advance_time(60)
+            .assert_strategy_balances(&funds);
+
+        assert_eq!(
+            strategy
+                .harness
+                .get_triggers(
+                    ConditionFilter::Timestamp {
+                        start: None,
+                        end: None,
+                    },
+                    None,
+                )
+                .len(),
+            1
+        );
+
+        strategy
+            .resume_manager()
+            .advance_time(0)
+            .assert_strategy_balances(&[Coin::new(
+                funds[0].amount - swap_action.swap_amount.amount,
+                swap_action.swap_amount.denom.clone(),
+            )]);
+    }
+
     #[test]
     fn test_crank_block_schedule_sets_and_resets_triggers() {
         let mut harness = CalcTestApp::setup();
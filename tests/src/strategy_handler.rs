@@ -107,6 +107,18 @@ impl<'a> StrategyHandler<'a> {
         self
     }
 
+    pub fn pause_manager(&mut self) -> &mut Self {
+        println!("[StrategyHandler] Pausing manager");
+        self.harness.set_manager_paused(true).unwrap();
+        self
+    }
+
+    pub fn resume_manager(&mut self) -> &mut Self {
+        println!("[StrategyHandler] Resuming manager");
+        self.harness.set_manager_paused(false).unwrap();
+        self
+    }
+
     pub fn withdraw(&mut self, amounts: Vec<Coin>) -> &mut Self {
         self.harness
             .withdraw(&self.owner, &self.strategy_addr, amounts)